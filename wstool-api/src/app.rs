@@ -44,6 +44,22 @@ pub async fn init() -> anyhow::Result<(AppState, SocketAddr)> {
     // 执行数据库迁移
     db::migrate(&pool).await?;
 
+    // WebSocket管理器需要连接池来保存收到的消息
+    crate::service::websocket_manager::WEBSOCKET_MANAGER.set_pool(pool.clone());
+
+    // 加载配置间的转发路由
+    crate::service::bridge::load_routes(&pool).await?;
+    crate::service::bridge::track_deliveries(pool.clone());
+
+    // 加载 webhook 并启动批量投递任务
+    crate::service::webhook::load_webhooks(&pool).await?;
+
+    // 加载告警规则
+    crate::service::alert::load_rules(&pool).await?;
+
+    // 后台按保留策略清理和归档消息
+    crate::service::retention::spawn_retention_task(pool.clone());
+
     let state = AppState { pool };
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     info!("initialized with addr=http://{}", addr);
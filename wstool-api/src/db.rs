@@ -104,6 +104,20 @@ pub async fn migrate(pool: &SqlitePool) -> anyhow::Result<()> {
     init_kol_data(pool).await?;
     init_twitter_data(pool).await?;
     init_binlog_data(pool).await?;
+    upgrade_websocket_tables(pool).await?;
+    create_mock_tables(pool).await?;
+    create_scenario_tables(pool).await?;
+    create_loadtest_tables(pool).await?;
+    create_recording_tables(pool).await?;
+    create_message_search_index(pool).await?;
+    create_retention_tables(pool).await?;
+    create_route_tables(pool).await?;
+    create_webhook_tables(pool).await?;
+    create_alert_tables(pool).await?;
+    create_sequence_tables(pool).await?;
+    create_candle_tables(pool).await?;
+    create_state_tables(pool).await?;
+    create_group_tables(pool).await?;
     Ok(())
 }
 
@@ -326,6 +340,16 @@ pub async fn init_binlog_data(pool: &SqlitePool) -> anyhow::Result<()> {
             message_template TEXT,
             auto_reconnect BOOLEAN DEFAULT TRUE,
             status TEXT DEFAULT 'inactive' CHECK (status IN ('active', 'inactive', 'error')),
+            protocol TEXT NOT NULL DEFAULT 'raw',
+            protocol_options TEXT,
+            filters TEXT,
+            message_schema TEXT,
+            transforms TEXT,
+            outbox TEXT,
+            sequence TEXT,
+            orderbook TEXT,
+            candles TEXT,
+            state_store TEXT,
             created_at INTEGER NOT NULL,
             updated_at INTEGER NOT NULL
         )
@@ -345,6 +369,15 @@ pub async fn init_binlog_data(pool: &SqlitePool) -> anyhow::Result<()> {
             timestamp INTEGER NOT NULL,
             status TEXT DEFAULT 'success' CHECK (status IN ('success', 'failed', 'pending')),
             error_message TEXT,
+            topic TEXT,
+            event TEXT,
+            session_id TEXT,
+            timestamp_us INTEGER,
+            valid BOOLEAN,
+            validation_error TEXT,
+            raw_content TEXT,
+            qos INTEGER,
+            retain BOOLEAN,
             FOREIGN KEY (config_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
         )
         "#,
@@ -367,3 +400,683 @@ pub async fn init_binlog_data(pool: &SqlitePool) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// 升级旧库中的 WebSocket 表（补充后续版本新增的列）
+async fn upgrade_websocket_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    add_column_if_missing(pool, "t_websocket_config", "protocol", "TEXT NOT NULL DEFAULT 'raw'").await?;
+    add_column_if_missing(pool, "t_websocket_config", "protocol_options", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "filters", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "message_schema", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "transforms", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "outbox", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "sequence", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "orderbook", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "candles", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_config", "state_store", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_message", "topic", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_message", "event", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_message", "session_id", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_message", "timestamp_us", "INTEGER").await?;
+    add_column_if_missing(pool, "t_websocket_message", "valid", "BOOLEAN").await?;
+    add_column_if_missing(pool, "t_websocket_message", "validation_error", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_message", "raw_content", "TEXT").await?;
+    add_column_if_missing(pool, "t_websocket_message", "qos", "INTEGER").await?;
+    add_column_if_missing(pool, "t_websocket_message", "retain", "BOOLEAN").await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_message_session ON t_websocket_message(session_id)")
+        .execute(pool)
+        .await?;
+
+    // 发件箱中待发送的消息
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_message_pending ON t_websocket_message(config_id, timestamp_us) WHERE status = 'pending'")
+        .execute(pool)
+        .await?;
+
+    // 消息历史按 (timestamp, id) 游标分页
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_message_config_time ON t_websocket_message(config_id, timestamp, id)")
+        .execute(pool)
+        .await?;
+
+    // 按接收时间顺序分批清理过期消息
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_message_config_time_us ON t_websocket_message(config_id, COALESCE(timestamp_us, timestamp * 1000000))")
+        .execute(pool)
+        .await?;
+
+    // 服务重启时已交给连接但未写出的消息：启用发件箱的放回队列，其余标记为 failed
+    sqlx::query(
+        r#"
+        UPDATE t_websocket_message SET session_id = NULL
+        WHERE message_type = 'sent' AND status = 'pending' AND session_id IS NOT NULL
+        AND config_id IN (SELECT id FROM t_websocket_config WHERE outbox IS NOT NULL AND trim(outbox) != '')
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE t_websocket_message SET status = 'failed', error_message = 'interrupted'
+        WHERE message_type = 'sent' AND status = 'pending' AND session_id IS NOT NULL
+        "#,
+    )
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// 创建 mock WebSocket 服务相关表
+async fn create_mock_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_mock (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            description TEXT,
+            greeting TEXT,
+            rules TEXT,
+            pushes TEXT,
+            disconnect TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_mock_log (
+            id TEXT PRIMARY KEY,
+            mock_name TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            message_type TEXT NOT NULL CHECK (message_type IN ('sent', 'received', 'event')),
+            content TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_mock_log_name ON t_websocket_mock_log(mock_name, timestamp)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn create_scenario_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_scenario (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            ws_url TEXT,
+            steps TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_scenario_result (
+            id TEXT PRIMARY KEY,
+            scenario_id TEXT NOT NULL,
+            target TEXT NOT NULL,
+            passed BOOLEAN NOT NULL,
+            started_at INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            report TEXT NOT NULL,
+            FOREIGN KEY (scenario_id) REFERENCES t_websocket_scenario(id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_scenario_result_scenario ON t_websocket_scenario_result(scenario_id, started_at)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn create_loadtest_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_loadtest (
+            id TEXT PRIMARY KEY,
+            ws_url TEXT NOT NULL,
+            params TEXT NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('running', 'completed', 'stopped')),
+            started_at INTEGER NOT NULL,
+            finished_at INTEGER,
+            report TEXT
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    // 服务重启后未完成的任务不会继续运行
+    sqlx::query("UPDATE t_websocket_loadtest SET status = 'stopped' WHERE status = 'running'")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn create_recording_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_recording (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            config_id TEXT,
+            session_id TEXT,
+            ws_url TEXT,
+            frame_count INTEGER NOT NULL,
+            duration_us INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 创建消息全文索引（FTS5 外部内容表，通过触发器与 t_websocket_message 同步）
+async fn create_message_search_index(pool: &SqlitePool) -> anyhow::Result<()> {
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 't_websocket_message_fts'"
+    )
+        .fetch_one(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS t_websocket_message_fts USING fts5(
+            content, topic, event,
+            content = 't_websocket_message',
+            content_rowid = 'rowid'
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS t_websocket_message_fts_insert AFTER INSERT ON t_websocket_message BEGIN
+            INSERT INTO t_websocket_message_fts (rowid, content, topic, event)
+            VALUES (new.rowid, new.content, new.topic, new.event);
+        END
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER IF NOT EXISTS t_websocket_message_fts_delete AFTER DELETE ON t_websocket_message BEGIN
+            INSERT INTO t_websocket_message_fts (t_websocket_message_fts, rowid, content, topic, event)
+            VALUES ('delete', old.rowid, old.content, old.topic, old.event);
+        END
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    // 只在索引列变化时更新索引（状态、会话ID等更新不触发）；旧版本的触发器需要替换
+    sqlx::query("DROP TRIGGER IF EXISTS t_websocket_message_fts_update")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER t_websocket_message_fts_update AFTER UPDATE OF content, topic, event ON t_websocket_message BEGIN
+            INSERT INTO t_websocket_message_fts (t_websocket_message_fts, rowid, content, topic, event)
+            VALUES ('delete', old.rowid, old.content, old.topic, old.event);
+            INSERT INTO t_websocket_message_fts (rowid, content, topic, event)
+            VALUES (new.rowid, new.content, new.topic, new.event);
+        END
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    // 首次创建时为已有消息建立索引
+    if exists == 0 {
+        rebuild_message_search_index(pool).await?;
+    }
+
+    Ok(())
+}
+
+/// 重建消息全文索引（VACUUM 可能改变 rowid，之后需要重建）
+pub async fn rebuild_message_search_index(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query("INSERT INTO t_websocket_message_fts (t_websocket_message_fts) VALUES ('rebuild')")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// 创建消息保留策略与归档表
+async fn create_retention_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_retention (
+            scope TEXT PRIMARY KEY,
+            max_age_secs INTEGER,
+            max_rows INTEGER,
+            max_bytes INTEGER,
+            archive BOOLEAN NOT NULL DEFAULT FALSE,
+            updated_at INTEGER NOT NULL
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_archive (
+            id TEXT PRIMARY KEY,
+            config_id TEXT NOT NULL,
+            period TEXT NOT NULL,
+            path TEXT NOT NULL,
+            row_count INTEGER NOT NULL,
+            bytes INTEGER NOT NULL,
+            min_timestamp INTEGER NOT NULL,
+            max_timestamp INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            restored_at INTEGER
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_archive_config ON t_websocket_archive(config_id, period)")
+        .execute(pool)
+        .await?;
+
+    enable_incremental_vacuum(pool).await
+}
+
+/// 开启增量 VACUUM（旧库需要完整 VACUUM 一次才会生效）
+async fn enable_incremental_vacuum(pool: &SqlitePool) -> anyhow::Result<()> {
+    let mut conn = pool.acquire().await?;
+    let mode: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+        .fetch_one(&mut *conn)
+        .await?;
+
+    // 0 = NONE, 1 = FULL, 2 = INCREMENTAL
+    if mode != 2 {
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("VACUUM")
+            .execute(&mut *conn)
+            .await?;
+        drop(conn);
+        rebuild_message_search_index(pool).await?;
+    }
+
+    Ok(())
+}
+
+/// 创建配置间转发路由及死信表
+async fn create_route_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_route (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            destinations TEXT NOT NULL,
+            filter TEXT,
+            transforms TEXT,
+            enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (source_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_dead_letter (
+            id TEXT PRIMARY KEY,
+            route_id TEXT NOT NULL,
+            destination_id TEXT,
+            content TEXT NOT NULL,
+            error TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (route_id) REFERENCES t_websocket_route (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_dead_letter_route ON t_websocket_dead_letter(route_id, created_at)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 创建 webhook 及投递记录表
+async fn create_webhook_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_webhook (
+            id TEXT PRIMARY KEY,
+            config_id TEXT NOT NULL,
+            url TEXT NOT NULL,
+            secret TEXT,
+            filter TEXT,
+            batch_size INTEGER NOT NULL DEFAULT 1,
+            batch_interval_ms INTEGER NOT NULL DEFAULT 1000,
+            max_attempts INTEGER NOT NULL DEFAULT 5,
+            enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (config_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_webhook_delivery (
+            id TEXT PRIMARY KEY,
+            webhook_id TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            message_count INTEGER NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('pending', 'success', 'failed')),
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_status_code INTEGER,
+            last_error TEXT,
+            created_at INTEGER NOT NULL,
+            delivered_at INTEGER,
+            FOREIGN KEY (webhook_id) REFERENCES t_websocket_webhook (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_webhook_attempt (
+            id TEXT PRIMARY KEY,
+            delivery_id TEXT NOT NULL,
+            attempt INTEGER NOT NULL,
+            status_code INTEGER,
+            error TEXT,
+            duration_ms INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (delivery_id) REFERENCES t_websocket_webhook_delivery (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_webhook_delivery_webhook ON t_websocket_webhook_delivery(webhook_id, created_at)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_webhook_attempt_delivery ON t_websocket_webhook_attempt(delivery_id)")
+        .execute(pool)
+        .await?;
+
+    // 服务重启后未完成的投递不会继续重试，可手动重新投递
+    sqlx::query("UPDATE t_websocket_webhook_delivery SET status = 'failed', last_error = 'interrupted by restart' WHERE status = 'pending'")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 创建告警规则、告警、通知渠道及通知记录表
+async fn create_alert_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_alert_rule (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            config_id TEXT,
+            matcher TEXT NOT NULL,
+            severity TEXT NOT NULL CHECK (severity IN ('info', 'warning', 'critical')),
+            cooldown_secs INTEGER NOT NULL DEFAULT 300,
+            dedup_key TEXT,
+            channels TEXT NOT NULL DEFAULT '[]',
+            enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            FOREIGN KEY (config_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_alert (
+            id TEXT PRIMARY KEY,
+            rule_id TEXT NOT NULL,
+            rule_name TEXT NOT NULL,
+            config_id TEXT NOT NULL,
+            severity TEXT NOT NULL,
+            dedup_key TEXT NOT NULL DEFAULT '',
+            content TEXT NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('firing', 'acknowledged', 'resolved')),
+            occurrences INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            last_seen_at INTEGER NOT NULL,
+            acknowledged_at INTEGER,
+            resolved_at INTEGER,
+            FOREIGN KEY (rule_id) REFERENCES t_websocket_alert_rule (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_alert_channel (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL CHECK (kind IN ('webhook', 'telegram', 'discord', 'smtp')),
+            settings TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_alert_notification (
+            id TEXT PRIMARY KEY,
+            alert_id TEXT NOT NULL,
+            channel_id TEXT NOT NULL,
+            success BOOLEAN NOT NULL,
+            error TEXT,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (alert_id) REFERENCES t_websocket_alert (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_alert_status ON t_websocket_alert(status, created_at)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_alert_rule_key ON t_websocket_alert(rule_id, dedup_key, status)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_alert_notification_alert ON t_websocket_alert_notification(alert_id)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 创建序号异常事件表
+async fn create_sequence_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_sequence_event (
+            id TEXT PRIMARY KEY,
+            config_id TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            partition_key TEXT NOT NULL DEFAULT '',
+            kind TEXT NOT NULL CHECK (kind IN ('gap', 'duplicate', 'out_of_order')),
+            expected INTEGER NOT NULL,
+            actual INTEGER NOT NULL,
+            missed INTEGER NOT NULL DEFAULT 0,
+            action TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            FOREIGN KEY (config_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_websocket_sequence_event_config ON t_websocket_sequence_event(config_id, created_at)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 创建 K 线表
+async fn create_candle_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_candle (
+            config_id TEXT NOT NULL,
+            symbol TEXT NOT NULL,
+            interval TEXT NOT NULL,
+            open_time INTEGER NOT NULL,
+            close_time INTEGER NOT NULL,
+            open REAL NOT NULL,
+            high REAL NOT NULL,
+            low REAL NOT NULL,
+            close REAL NOT NULL,
+            volume REAL NOT NULL,
+            quote_volume REAL NOT NULL,
+            trades INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (config_id, symbol, interval, open_time),
+            FOREIGN KEY (config_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 列不存在时执行 ALTER TABLE ADD COLUMN
+async fn add_column_if_missing(pool: &SqlitePool, table: &str, column: &str, definition: &str) -> anyhow::Result<()> {
+    let exists: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = ?",
+        table
+    ))
+        .bind(column)
+        .fetch_one(pool)
+        .await?;
+
+    if exists == 0 {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// 创建最新值状态表
+async fn create_state_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_state (
+            config_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            content TEXT NOT NULL,
+            update_count INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (config_id, key),
+            FOREIGN KEY (config_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// 创建连接分组表
+async fn create_group_tables(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_group (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            description TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS t_websocket_group_member (
+            group_id TEXT NOT NULL,
+            config_id TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (group_id, config_id),
+            FOREIGN KEY (group_id) REFERENCES t_websocket_group (id) ON DELETE CASCADE,
+            FOREIGN KEY (config_id) REFERENCES t_websocket_config (id) ON DELETE CASCADE
+        )
+        "#,
+    )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    pub config_id: Option<String>, // Null evaluates the rule against every config
+    pub matcher: String, // JSON string: {"regex": "...", "assertions": [...], "rate": {"count": 5, "window_secs": 60}}
+    pub severity: String, // "info", "warning", "critical"
+    pub cooldown_secs: i64, // Minimum gap between two alerts with the same dedup key
+    pub dedup_key: Option<String>, // Field path whose value separates alerts, e.g. "s" for one alert per symbol
+    pub channels: String, // JSON string: ["<channel id>", ...]
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewAlertRule {
+    pub name: String,
+    pub config_id: Option<String>,
+    pub matcher: String,
+    pub severity: Option<String>,
+    pub cooldown_secs: Option<i64>,
+    pub dedup_key: Option<String>,
+    pub channels: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAlertRule {
+    pub name: Option<String>,
+    pub matcher: Option<String>,
+    pub severity: Option<String>,
+    pub cooldown_secs: Option<i64>,
+    pub dedup_key: Option<String>,
+    pub channels: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Alert {
+    pub id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub config_id: String,
+    pub severity: String,
+    pub dedup_key: String, // Value of the rule's dedup_key field, empty when the rule has none
+    pub content: String, // Latest matching message
+    pub status: String, // "firing", "acknowledged", "resolved"
+    pub occurrences: i64, // Matches folded into this alert while it was open
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub acknowledged_at: Option<i64>,
+    pub resolved_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AlertChannel {
+    pub id: String,
+    pub name: String,
+    pub kind: String, // "webhook", "telegram", "discord", "smtp"
+    pub settings: String, // JSON string, fields depend on kind
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewAlertChannel {
+    pub name: String,
+    pub kind: String,
+    pub settings: String,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAlertChannel {
+    pub name: Option<String>,
+    pub settings: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AlertNotification {
+    pub id: String,
+    pub alert_id: String,
+    pub channel_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: i64,
+}
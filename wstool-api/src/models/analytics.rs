@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsAggregate {
+    #[serde(rename = "fn")]
+    pub function: String, // "count", "min", "max", "avg", "sum", "first" or "last"
+    pub path: Option<String>, // JSON path, e.g. "data.p"; required except for count (count counts messages without it)
+    #[serde(rename = "as")]
+    pub alias: Option<String>, // Output column, defaults to "<fn>(<path>)"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsQuery {
+    pub config_id: String,
+    pub since: Option<i64>, // Seconds since epoch, defaults to one hour before until
+    pub until: Option<i64>, // Seconds since epoch (inclusive), defaults to now
+    pub message_type: Option<String>, // "sent" or "received"
+    pub group_by: Option<Vec<String>>, // JSON paths, e.g. ["data.s"]
+    pub bucket: Option<String>, // Time bucket, e.g. "1m", "5m", "1h"
+    pub aggregates: Vec<AnalyticsAggregate>,
+    pub limit: Option<i64>, // Result rows, defaults to 1000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsResult {
+    pub columns: Vec<String>, // "bucket" (start second) first when bucketed, then group_by paths, then aggregates
+    pub rows: Vec<Map<String, Value>>,
+    pub scanned: i64, // Messages in the window
+    pub truncated: bool, // More rows than limit
+    pub elapsed_ms: u64,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CandleSettings {
+    pub items: Option<String>, // Path of an array of trades in each message, e.g. "data" for OKX; the message itself when not set
+    pub price: String, // Field paths relative to each trade, e.g. "data.p" (Binance), "px" (OKX), "price" (Coinbase)
+    pub size: String,
+    pub time: Option<String>, // Trade time: epoch s/ms/us (number or string) or RFC 3339; receive time when not set
+    pub symbol: Option<String>, // All trades go to one "" symbol when not set
+    pub intervals: Option<Vec<String>>, // "<n>s", "<n>m", "<n>h" or "<n>d"; defaults to 1s, 1m, 5m and 1h
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Candle {
+    pub config_id: String,
+    pub symbol: String,
+    pub interval: String,
+    pub open_time: i64, // Milliseconds since epoch
+    pub close_time: i64, // open_time + interval - 1
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub quote_volume: f64, // Sum of price * size
+    pub trades: i64,
+    pub updated_at: i64, // Milliseconds since epoch
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ConnectionGroup {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionGroupDetail {
+    #[serde(flatten)]
+    pub group: ConnectionGroup,
+    pub config_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewConnectionGroup {
+    pub name: String,
+    pub description: Option<String>,
+    pub config_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateConnectionGroup {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub config_ids: Option<Vec<String>>, // Replaces the members when set
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BulkConnectionRequest {
+    pub config_ids: Option<Vec<String>>, // Required for /websocket/bulk/*, ignored for groups
+    pub concurrency: Option<usize>, // Connections opened at the same time, defaults to 5
+    pub stagger_ms: Option<u64>, // Minimum gap between two connection attempts, defaults to 200
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkItemResult {
+    pub config_id: String,
+    pub name: Option<String>,
+    pub result: String, // "connected", "already_connected", "pending" (still connecting after the wait), "stopped", "failed" or "not_found"
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkConnectionResult {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub items: Vec<BulkItemResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMemberStatus {
+    pub config_id: String,
+    pub name: String,
+    pub state: String, // "connected", "reconnecting", "failed" or "stopped"
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionGroupStatus {
+    pub group_id: String,
+    pub name: String,
+    pub total: usize,
+    pub connected: usize,
+    pub reconnecting: usize,
+    pub failed: usize,
+    pub stopped: usize,
+    pub members: Vec<GroupMemberStatus>,
+}
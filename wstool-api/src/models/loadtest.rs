@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadTestRequest {
+    pub ws_url: Option<String>,
+    pub config_id: Option<String>, // Use the URL, headers and protocol of a saved config
+    pub connections: u32,
+    pub ramp_up_per_sec: Option<f64>, // New connections per second, all at once if not set
+    pub messages_per_sec: Option<f64>, // Per connection, no messages if not set
+    pub duration_secs: u64,
+    pub message: Option<String>, // Template, supports the same tokens as mock responses ({{seq}}, {{uuid}}, ...)
+    pub topic: Option<String>,
+    pub event: Option<String>,
+    pub correlation_path: Option<String>, // JSON path present in both request and reply, e.g. "id"
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LoadTest {
+    pub id: String,
+    pub ws_url: String,
+    pub params: String, // JSON string of LoadTestRequest
+    pub status: String, // "running", "completed", "stopped"
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub report: Option<String>, // JSON string of LoadTestReport
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadTestReport {
+    pub id: String,
+    pub status: String,
+    pub elapsed_ms: u64,
+    pub connections_opened: u64,
+    pub connections_active: u64,
+    pub connect_failures: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub errors: u64,
+    pub closes: u64,
+    pub connect_ms: LatencyStats,
+    pub rtt_ms: LatencyStats,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MockDefinition {
+    pub id: String,
+    pub name: String, // Served at /mock/:name
+    pub description: Option<String>,
+    pub greeting: Option<String>, // Sent right after the client connects
+    pub rules: Option<String>, // JSON string: [{"match": "regex"|"json", "pattern": ..., "response": "...", "close": false}]
+    pub pushes: Option<String>, // JSON string: [{"interval_ms": 1000, "payload": "...", "count": 10}]
+    pub disconnect: Option<String>, // JSON string: {"after_ms": 5000, "after_messages": 3, "code": 1000, "reason": "..."}
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewMockDefinition {
+    pub name: String,
+    pub description: Option<String>,
+    pub greeting: Option<String>,
+    pub rules: Option<String>,
+    pub pushes: Option<String>,
+    pub disconnect: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateMockDefinition {
+    pub description: Option<String>,
+    pub greeting: Option<String>,
+    pub rules: Option<String>,
+    pub pushes: Option<String>,
+    pub disconnect: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MockLog {
+    pub id: String,
+    pub mock_name: String,
+    pub session_id: String,
+    pub message_type: String, // "received", "sent", "event"
+    pub content: String,
+    pub timestamp: i64,
+}
@@ -3,6 +3,19 @@ pub mod r;
 pub mod frontend;
 mod data;
 pub mod websocket;
+pub mod mock;
+pub mod scenario;
+pub mod loadtest;
+pub mod recording;
+pub mod retention;
+pub mod route;
+pub mod webhook;
+pub mod alert;
+pub mod orderbook;
+pub mod candle;
+pub mod state_store;
+pub mod connection_group;
+pub mod analytics;
 
 pub use item::{Item, NewItem, UpdateItem};
 pub use r::ApiResponse;
@@ -11,5 +24,35 @@ pub use data::{BinlogAfter};
 pub use websocket::{
     WebSocketConfig, NewWebSocketConfig, UpdateWebSocketConfig,
     WebSocketMessage, SendMessageRequest, SubscribeRequest,
-    WebSocketStatus, TestConnectionRequest, TestConnectionResponse
+    WebSocketStatus, TestConnectionRequest, TestConnectionResponse,
+    PresetConfigRequest, PresetInfo, MessageImportResult, MessageSearchHit, MessagePage, MessageSchema,
+    TransformPreviewRequest, TransformPreview, OutboxSettings, SentMessage,
+    SequenceRule, SequenceEvent
 };
+pub use mock::{MockDefinition, NewMockDefinition, UpdateMockDefinition, MockLog};
+pub use scenario::{
+    Scenario, NewScenario, UpdateScenario, RunScenarioRequest,
+    ScenarioResult, ScenarioReport, StepReport
+};
+pub use loadtest::{LoadTestRequest, LoadTest, LoadTestReport, LatencyStats};
+pub use recording::{
+    Recording, SessionSummary, CreateRecordingRequest, ImportRecordingRequest,
+    RecordingLine, RecordingHeader, RecordingFrame, ReplayRequest, ReplayStatus
+};
+pub use retention::{
+    RetentionPolicy, UpsertRetentionPolicy, MessageArchive, RetentionReport, RestoreResult
+};
+pub use route::{Route, NewRoute, UpdateRoute, RouteStats, DeadLetter};
+pub use webhook::{Webhook, NewWebhook, UpdateWebhook, WebhookDelivery, WebhookAttempt};
+pub use alert::{
+    AlertRule, NewAlertRule, UpdateAlertRule, Alert,
+    AlertChannel, NewAlertChannel, UpdateAlertChannel, AlertNotification
+};
+pub use orderbook::{OrderBookSettings, OrderBookLevel, OrderBookSummary, OrderBookView};
+pub use candle::{CandleSettings, Candle};
+pub use state_store::{StateSettings, StateEntry, StateChange};
+pub use connection_group::{
+    ConnectionGroup, ConnectionGroupDetail, NewConnectionGroup, UpdateConnectionGroup, BulkConnectionRequest,
+    BulkItemResult, BulkConnectionResult, GroupMemberStatus, ConnectionGroupStatus
+};
+pub use analytics::{AnalyticsAggregate, AnalyticsQuery, AnalyticsResult};
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderBookSettings {
+    pub format: Option<String>, // "binance" or "okx", defaults to the config's protocol
+    pub snapshot_url: Option<String>, // Binance REST depth snapshot, "{symbol}" is replaced, e.g. "https://api.binance.com/api/v3/depth?symbol={symbol}&limit=1000"
+    pub max_levels: Option<usize>, // Levels kept per side, unlimited when not set
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBookLevel {
+    pub price: String,
+    pub size: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderBookSummary {
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub spread: Option<f64>,
+    pub mid: Option<f64>,
+    pub spread_bps: Option<f64>, // Spread relative to mid, in basis points
+    pub bid_volume: f64, // Total size of the top `depth` bid levels
+    pub ask_volume: f64,
+    pub imbalance: Option<f64>, // (bid_volume - ask_volume) / (bid_volume + ask_volume), in [-1, 1]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBookView {
+    pub config_id: String,
+    pub symbol: String,
+    pub status: String, // "syncing" (waiting for a snapshot), "live" or "disconnected"
+    pub last_update_id: Option<i64>, // Binance update id / OKX seqId of the last applied update
+    pub updated_at: Option<i64>, // Milliseconds since epoch
+    pub invalid_count: i64, // Times the book failed a sequence, crossed-book or checksum check and was rebuilt
+    pub last_error: Option<String>,
+    pub bids: Vec<OrderBookLevel>, // Best first
+    pub asks: Vec<OrderBookLevel>,
+    pub summary: OrderBookSummary,
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Recording {
+    pub id: String,
+    pub name: String,
+    pub config_id: Option<String>,
+    pub session_id: Option<String>,
+    pub ws_url: Option<String>,
+    pub frame_count: i64,
+    pub duration_us: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub started_at_us: i64,
+    pub ended_at_us: i64,
+    pub sent: i64,
+    pub received: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateRecordingRequest {
+    pub session_id: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportRecordingRequest {
+    pub name: Option<String>,
+    pub content: String, // JSONL, as produced by the download endpoint
+}
+
+// One line of a recording file. The first line is the header, every other line is a frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RecordingLine {
+    Header(RecordingHeader),
+    Frame(RecordingFrame),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u32,
+    pub name: String,
+    pub ws_url: Option<String>,
+    pub protocol: Option<String>,
+    pub config_id: Option<String>,
+    pub session_id: Option<String>,
+    pub started_at_us: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingFrame {
+    pub direction: String, // "sent" (client -> server) or "received" (server -> client)
+    pub offset_us: i64, // Relative to the start of the session
+    pub topic: Option<String>,
+    pub event: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayRequest {
+    pub ws_url: Option<String>, // Defaults to the URL in the recording header
+    pub config_id: Option<String>, // Use the headers and protocol of a saved config
+    pub speed: Option<f64>, // 2.0 replays twice as fast, 0.01 to 1000, defaults to 1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayStatus {
+    pub id: String,
+    pub recording_id: String,
+    pub target: String,
+    pub speed: f64,
+    pub status: String, // "running", "completed", "stopped", "failed"
+    pub frames_total: u64,
+    pub frames_sent: u64,
+    pub frames_received: u64,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub error: Option<String>,
+}
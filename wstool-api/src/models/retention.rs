@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RetentionPolicy {
+    pub scope: String, // "global" or a config id; a config policy replaces the global one
+    pub max_age_secs: Option<i64>,
+    pub max_rows: Option<i64>,
+    pub max_bytes: Option<i64>, // Total content length per config
+    pub archive: bool, // Write expired rows to compressed NDJSON before deleting them
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpsertRetentionPolicy {
+    pub max_age_secs: Option<i64>,
+    pub max_rows: Option<i64>,
+    pub max_bytes: Option<i64>,
+    pub archive: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MessageArchive {
+    pub id: String,
+    pub config_id: String,
+    pub period: String, // UTC day of the archived messages, "YYYY-MM-DD"
+    pub path: String,
+    pub row_count: i64,
+    pub bytes: i64, // Compressed file size
+    pub min_timestamp: i64,
+    pub max_timestamp: i64,
+    pub created_at: i64,
+    pub restored_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionReport {
+    pub configs: u64,
+    pub deleted: u64,
+    pub archived: u64,
+    pub archives: Vec<String>, // Ids of archives written in this run
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RestoreResult {
+    pub restored: u64,
+    pub skipped: u64, // Rows already present in the table
+}
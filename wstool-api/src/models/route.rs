@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Route {
+    pub id: String,
+    pub name: String,
+    pub source_id: String, // Config whose received messages are forwarded
+    pub destinations: String, // JSON string: ["<sender config id>", ...]
+    pub filter: Option<String>, // JSON string: {"regex": "...", "assertions": [{"path": "e", "op": "eq", "value": "trade"}]}
+    pub transforms: Option<String>, // JSON string, same steps as config transforms (decompress not allowed)
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewRoute {
+    pub name: String,
+    pub source_id: String,
+    pub destinations: String,
+    pub filter: Option<String>,
+    pub transforms: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateRoute {
+    pub name: Option<String>,
+    pub destinations: Option<String>,
+    pub filter: Option<String>,
+    pub transforms: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteStats {
+    pub route_id: String,
+    pub forwarded: u64, // Messages written to a destination socket, counted once per destination
+    pub dropped: u64, // Messages rejected by the filter or emptied by the transforms
+    pub errors: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DeadLetter {
+    pub id: String,
+    pub route_id: String,
+    pub destination_id: Option<String>, // Null when the route transforms failed
+    pub content: String,
+    pub error: String,
+    pub created_at: i64,
+}
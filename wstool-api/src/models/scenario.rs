@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Scenario {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub ws_url: Option<String>, // Default target, can be overridden per run
+    pub steps: String, // JSON string: [{"type": "connect"}, {"type": "send", ...}, {"type": "expect", ...}, ...]
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewScenario {
+    pub name: String,
+    pub description: Option<String>,
+    pub ws_url: Option<String>,
+    pub steps: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateScenario {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub ws_url: Option<String>,
+    pub steps: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunScenarioRequest {
+    pub ws_url: Option<String>,
+    pub config_id: Option<String>, // Use the URL and headers of a saved config
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ScenarioResult {
+    pub id: String,
+    pub scenario_id: String,
+    pub target: String,
+    pub passed: bool,
+    pub started_at: i64,
+    pub duration_ms: i64,
+    pub report: String, // JSON string of ScenarioReport
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioReport {
+    pub scenario_id: String,
+    pub scenario_name: String,
+    pub target: String,
+    pub passed: bool,
+    pub duration_ms: u64,
+    pub steps: Vec<StepReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReport {
+    pub index: usize,
+    pub step_type: String,
+    pub status: String, // "passed", "failed", "skipped"
+    pub duration_ms: u64,
+    pub detail: Option<String>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateSettings {
+    pub key: String, // Key path relative to each item, e.g. "s" for Binance tickers, "instId" for OKX
+    pub items: Option<String>, // Path of an array of items in each message, e.g. "data" for OKX; the message itself when not set
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct StateEntry {
+    pub config_id: String,
+    pub key: String,
+    pub content: String, // Latest item for the key
+    pub update_count: i64, // Items received for the key, including unchanged ones
+    pub created_at: i64, // Milliseconds since epoch
+    pub updated_at: i64, // Milliseconds since epoch
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateChange {
+    pub config_id: String,
+    pub key: String,
+    pub content: String,
+    pub previous: Option<String>, // None for a new key
+    pub changed_fields: Vec<String>, // Top-level fields added, removed or changed; empty when either item is not an object
+    pub update_count: i64,
+    pub updated_at: i64,
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Webhook {
+    pub id: String,
+    pub config_id: String,
+    pub url: String,
+    pub secret: Option<String>, // HMAC-SHA256 key, signature sent as "X-Wstool-Signature: sha256=<hex>"
+    pub filter: Option<String>, // JSON string, same format as route filters
+    pub batch_size: i64, // Deliver once this many messages are pending
+    pub batch_interval_ms: i64, // ... or once the oldest pending message is this old
+    pub max_attempts: i64,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewWebhook {
+    pub url: String,
+    pub secret: Option<String>,
+    pub filter: Option<String>,
+    pub batch_size: Option<i64>,
+    pub batch_interval_ms: Option<i64>,
+    pub max_attempts: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub filter: Option<String>,
+    pub batch_size: Option<i64>,
+    pub batch_interval_ms: Option<i64>,
+    pub max_attempts: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub payload: String, // Request body: {"webhook_id", "config_id", "delivery_id", "messages": [...]}
+    pub message_count: i64,
+    pub status: String, // "pending", "success", "failed"
+    pub attempts: i64,
+    pub last_status_code: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub delivered_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookAttempt {
+    pub id: String,
+    pub delivery_id: String,
+    pub attempt: i64,
+    pub status_code: Option<i64>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: i64,
+}
@@ -14,6 +14,16 @@ pub struct WebSocketConfig {
     pub message_template: Option<String>, // For sender type
     pub auto_reconnect: bool,
     pub status: String, // "active", "inactive", "error"
+    pub protocol: String, // "raw", "mqtt", "graphql-ws", "phoenix", "actioncable", or a preset name ("binance", "okx", "coinbase")
+    pub protocol_options: Option<String>, // JSON string for protocol specific options
+    pub filters: Option<String>, // JSON string for subscriptions (e.g. MQTT topics)
+    pub message_schema: Option<String>, // JSON string of MessageSchema, validates received messages
+    pub transforms: Option<String>, // JSON string: [{"op": "decompress"}, {"op": "extract", "path": "data"}, ...]
+    pub outbox: Option<String>, // JSON string of OutboxSettings, queues sends while disconnected when set
+    pub sequence: Option<String>, // JSON string of SequenceRule, checks received sequence numbers when set
+    pub orderbook: Option<String>, // JSON string of OrderBookSettings, maintains L2 books from depth streams when set
+    pub candles: Option<String>, // JSON string of CandleSettings, aggregates trades into OHLCV candles when set
+    pub state_store: Option<String>, // JSON string of StateSettings, keeps the latest message per key when set
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -28,6 +38,16 @@ pub struct NewWebSocketConfig {
     pub auth_token: Option<String>,
     pub message_template: Option<String>,
     pub auto_reconnect: Option<bool>,
+    pub protocol: Option<String>,
+    pub protocol_options: Option<String>,
+    pub filters: Option<String>,
+    pub message_schema: Option<String>,
+    pub transforms: Option<String>,
+    pub outbox: Option<String>,
+    pub sequence: Option<String>,
+    pub orderbook: Option<String>,
+    pub candles: Option<String>,
+    pub state_store: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,6 +61,9 @@ pub struct UpdateWebSocketConfig {
     pub message_template: Option<String>,
     pub auto_reconnect: Option<bool>,
     pub status: Option<String>,
+    pub protocol: Option<String>,
+    pub protocol_options: Option<String>,
+    pub filters: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -52,13 +75,74 @@ pub struct WebSocketMessage {
     pub timestamp: i64,
     pub status: String, // "success", "failed", "pending"
     pub error_message: Option<String>,
+    pub topic: Option<String>, // MQTT topic, GraphQL operation id, Phoenix topic, ActionCable identifier
+    pub event: Option<String>, // protocol event, e.g. GraphQL "next", "error", "complete"; Phoenix event
+    pub session_id: Option<String>, // One id per connection, used for session recording
+    pub timestamp_us: Option<i64>, // Microseconds since epoch
+    pub valid: Option<bool>, // Schema validation result, null when no schema applies
+    pub validation_error: Option<String>, // "<instance path>: <error>" of the first violation
+    pub raw_content: Option<String>, // Received payload before the transform pipeline, null when unchanged
+    pub qos: Option<i64>, // MQTT send options of queued outbox messages, reused when flushing
+    pub retain: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SentMessage {
+    pub id: String,
+    pub status: String, // "pending" until the frame is written to the socket, then "success" or "failed"
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutboxSettings {
+    pub ttl_secs: Option<i64>, // Pending messages older than this fail as expired instead of being sent
+    pub max_queue: Option<i64>, // Sends are rejected once this many messages are pending
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SequenceRule {
+    pub path: String, // Sequence field, e.g. "u", "data.seqId", "sequence"
+    pub prev_path: Option<String>, // Field holding the previous message's sequence, e.g. "pu"; without it the next sequence must be last + 1
+    pub key: Option<String>, // Field partitioning sequences, e.g. "s" to track each symbol separately
+    pub on_gap: Option<String>, // "none" (default), "resubscribe" or "reconnect"
+    pub resync_message: Option<String>, // Extra frame sent on resubscribe, for raw streams without saved filters
+    #[serde(default)]
+    pub drop_stale: bool, // Discard duplicate and out-of-order messages instead of storing them
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SequenceEvent {
+    pub id: String,
+    pub config_id: String,
+    pub session_id: String,
+    pub partition_key: String, // Value of the rule's key field, empty when not partitioned
+    pub kind: String, // "gap", "duplicate" or "out_of_order"
+    pub expected: i64, // Next sequence expected, or the previous sequence expected when prev_path is set
+    pub actual: i64, // Sequence (or previous sequence) carried by the message
+    pub missed: i64, // Sequences skipped by a gap (actual - expected), 0 otherwise
+    pub action: String, // "none", "resubscribe", "reconnect" or "dropped"
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageSchema {
+    pub schema: Option<serde_json::Value>, // Default JSON Schema (draft 2020-12)
+    pub discriminator: Option<String>, // Field path selecting a schema from `schemas`, e.g. "e" or "channel"
+    #[serde(default)]
+    pub schemas: std::collections::HashMap<String, serde_json::Value>, // Discriminator value -> schema
+    #[serde(default)]
+    pub violations_only: bool, // Store only messages that fail validation
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendMessageRequest {
     pub config_id: String,
     pub message: String,
     pub custom_headers: Option<serde_json::Value>,
+    pub topic: Option<String>, // MQTT publish topic, Phoenix topic, ActionCable identifier
+    pub event: Option<String>, // Phoenix event / ActionCable action
+    pub qos: Option<u8>,
+    pub retain: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,6 +151,39 @@ pub struct SubscribeRequest {
     pub filters: Option<serde_json::Value>, // Message filters
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessagePage {
+    pub messages: Vec<WebSocketMessage>,
+    pub next_cursor: Option<String>, // Pass as `before` (desc) or `after` (asc) for the next page, null on the last page
+    pub total: Option<i64>, // Messages matching the filters, only when requested with total=true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MessageSearchHit {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub message: WebSocketMessage,
+    pub snippet: String, // Matched text with <mark></mark> highlights
+    pub rank: f64, // bm25 score, lower is more relevant
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransformPreviewRequest {
+    pub transforms: serde_json::Value,
+    pub content: String,
+    pub base64: Option<bool>, // Content is a base64 encoded binary frame
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransformPreview {
+    pub messages: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageImportResult {
+    pub imported: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WebSocketStatus {
     pub config_id: String,
@@ -76,6 +193,30 @@ pub struct WebSocketStatus {
     pub message_count: i64,
     pub error_count: i64,
     pub last_error: Option<String>,
+    pub schema_violations: i64,
+    pub sequence_gaps: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PresetConfigRequest {
+    pub preset: String, // "binance", "okx", "coinbase"
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub ws_url: Option<String>, // Override the preset's public URL
+    pub symbols: Vec<String>,
+    pub channels: Vec<String>,
+    pub auto_reconnect: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PresetInfo {
+    pub name: String,
+    pub ws_url: String,
+    pub max_streams: usize,
+    pub ping_interval_secs: Option<u64>,
+    pub ping_message: Option<String>,
+    pub subscribe_example: serde_json::Value,
+    pub unsubscribe_example: serde_json::Value,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1,9 +1,13 @@
 use axum::{routing::get, Router};
 use axum::routing::{post, put, delete};
-use crate::service::{items, cex, kol, twitter, health, websocket, websocket_actions};
+use axum::extract::DefaultBodyLimit;
+use crate::service::{items, cex, kol, twitter, health, websocket, websocket_actions, mock_server, scenario, loadtest, recording, message_io, retention, bridge, webhook, alert, outbox, sequence, orderbook, candle, state_store, connection_group, analytics};
 use crate::app::AppState;
 use crate::service::binlog::{binlog_add_batch_handler, binlog_add_handler, binlog_list_handler};
 
+// 消息导入文件大小上限
+const IMPORT_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
 pub fn build_router() -> Router<AppState> {
     Router::new()
         .merge(health_router())
@@ -11,6 +15,17 @@ pub fn build_router() -> Router<AppState> {
         .merge(feeds_router())
         .merge(binlog_router())
         .merge(websocket_router())
+        .merge(mock_router())
+        .merge(scenario_router())
+        .merge(loadtest_router())
+        .merge(recording_router())
+        .merge(retention_router())
+        .merge(route_router())
+        .merge(webhook_router())
+        .merge(alert_router())
+        .merge(orderbook_router())
+        .merge(candle_router())
+        .merge(group_router())
 }
 
 fn health_router() -> Router<AppState> {
@@ -42,10 +57,29 @@ fn websocket_router() -> Router<AppState> {
         // WebSocket配置管理
         .route("/websocket/configs", get(websocket::list_configs).post(websocket::create_config))
         .route("/websocket/configs/:id", get(websocket::get_config).put(websocket::update_config).delete(websocket::delete_config))
+        .route("/websocket/configs/from-preset", post(websocket::create_config_from_preset))
+        .route("/websocket/configs/:id/schema", put(websocket::set_message_schema).delete(websocket::delete_message_schema))
+        .route("/websocket/configs/:id/transforms", put(websocket::set_transforms).delete(websocket::delete_transforms))
+        .route("/websocket/configs/:id/outbox", put(websocket::set_outbox).delete(websocket::delete_outbox))
+        .route("/websocket/configs/:id/outbox/messages", get(outbox::list_pending).delete(outbox::cancel_all))
+        .route("/websocket/configs/:id/outbox/messages/:message_id", delete(outbox::cancel_message))
+        .route("/websocket/configs/:id/sequence", put(websocket::set_sequence).delete(websocket::delete_sequence))
+        .route("/websocket/configs/:id/sequence/events", get(sequence::list_events))
+        .route("/websocket/configs/:id/orderbook", put(websocket::set_orderbook).delete(websocket::delete_orderbook))
+        .route("/websocket/configs/:id/candles", put(websocket::set_candles).delete(websocket::delete_candles))
+        .route("/websocket/configs/:id/state", put(websocket::set_state_store).delete(websocket::delete_state_store))
+        // 最新值状态
+        .route("/websocket/state/:config_id", get(state_store::list_state))
+        .route("/websocket/state-stream/:config_id", get(state_store::stream_state))
+        .route("/websocket/state/:config_id/:key", get(state_store::get_state))
+        .route("/websocket/transforms/preview", post(websocket::preview_transforms))
+        .route("/websocket/presets", get(websocket::list_presets))
         
         // WebSocket连接操作
         .route("/websocket/test", post(websocket_actions::test_websocket_connection))
         .route("/websocket/send", post(websocket_actions::send_message))
+        .route("/websocket/send/:id", get(websocket_actions::get_sent_message))
+        .route("/websocket/send/:id/stream", get(websocket_actions::stream_sent_message))
         .route("/websocket/subscribe", post(websocket_actions::subscribe_websocket))
         .route("/websocket/unsubscribe/:id", post(websocket_actions::unsubscribe_websocket))
         
@@ -56,5 +90,134 @@ fn websocket_router() -> Router<AppState> {
         // WebSocket状态和消息
         .route("/websocket/status", get(websocket_actions::get_all_connection_status))
         .route("/websocket/status/:id", get(websocket::get_config_status))
+        .route("/websocket/messages/search", get(websocket::search_messages))
         .route("/websocket/messages/:id", get(websocket::get_messages))
-}
\ No newline at end of file
+        .route("/websocket/analytics", post(analytics::query_messages))
+        .route("/websocket/messages/:id/export", get(message_io::export_messages))
+        .route("/websocket/messages/:id/import", post(message_io::import_messages).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+}
+fn mock_router() -> Router<AppState> {
+    Router::new()
+        // Mock定义管理
+        .route("/websocket/mocks", get(mock_server::list_mocks).post(mock_server::create_mock))
+        .route("/websocket/mocks/:name", get(mock_server::get_mock).put(mock_server::update_mock).delete(mock_server::delete_mock))
+        .route("/websocket/mocks/:name/logs", get(mock_server::get_mock_logs))
+
+        // Mock WebSocket端点
+        .route("/mock/:name", get(mock_server::mock_endpoint))
+}
+
+fn scenario_router() -> Router<AppState> {
+    Router::new()
+        // 场景管理
+        .route("/websocket/scenarios", get(scenario::list_scenarios).post(scenario::create_scenario))
+        .route("/websocket/scenarios/:id", get(scenario::get_scenario).put(scenario::update_scenario).delete(scenario::delete_scenario))
+
+        // 场景运行与结果
+        .route("/websocket/scenarios/:id/run", post(scenario::run_scenario_handler))
+        .route("/websocket/scenarios/:id/results", get(scenario::list_results))
+        .route("/websocket/scenario-results/:id", get(scenario::get_result))
+        .route("/websocket/scenario-results/:id/junit", get(scenario::get_result_junit))
+}
+
+fn loadtest_router() -> Router<AppState> {
+    Router::new()
+        .route("/websocket/loadtest", get(loadtest::list_load_tests).post(loadtest::start_load_test))
+        .route("/websocket/loadtest/:id", get(loadtest::get_load_test))
+        .route("/websocket/loadtest/:id/stream", get(loadtest::stream_load_test))
+        .route("/websocket/loadtest/:id/stop", post(loadtest::stop_load_test))
+}
+
+fn recording_router() -> Router<AppState> {
+    Router::new()
+        // 会话与录制
+        .route("/websocket/configs/:id/sessions", get(recording::list_sessions))
+        .route("/websocket/recordings", get(recording::list_recordings).post(recording::create_recording))
+        .route("/websocket/recordings/import", post(recording::import_recording))
+        .route("/websocket/recordings/:id", get(recording::get_recording).delete(recording::delete_recording))
+        .route("/websocket/recordings/:id/download", get(recording::download_recording))
+
+        // 回放：客户端回放任务 / 服务端回放端点
+        .route("/websocket/recordings/:id/replay", post(recording::start_replay))
+        .route("/websocket/replays/:id", get(recording::get_replay))
+        .route("/websocket/replays/:id/stop", post(recording::stop_replay))
+        .route("/mock/recordings/:id", get(recording::replay_endpoint))
+}
+
+fn retention_router() -> Router<AppState> {
+    Router::new()
+        // 保留策略（scope 为 "global" 或配置ID）
+        .route("/websocket/retention", get(retention::list_policies))
+        .route("/websocket/retention/run", post(retention::run_now))
+        .route("/websocket/retention/:scope", get(retention::get_policy).put(retention::upsert_policy).delete(retention::delete_policy))
+
+        // 冷归档
+        .route("/websocket/archives", get(retention::list_archives))
+        .route("/websocket/archives/:id/restore", post(retention::restore_archive))
+}
+
+fn route_router() -> Router<AppState> {
+    Router::new()
+        // 配置间转发路由
+        .route("/websocket/routes", get(bridge::list_routes).post(bridge::create_route))
+        .route("/websocket/routes/:id", get(bridge::get_route).put(bridge::update_route).delete(bridge::delete_route))
+        .route("/websocket/routes/:id/stats", get(bridge::get_route_stats))
+        .route("/websocket/routes/:id/dead-letters", get(bridge::list_dead_letters).delete(bridge::clear_dead_letters))
+}
+
+fn webhook_router() -> Router<AppState> {
+    Router::new()
+        // 接收消息的 webhook 推送
+        .route("/websocket/configs/:id/webhooks", get(webhook::list_webhooks).post(webhook::create_webhook))
+        .route("/websocket/webhooks/:id", get(webhook::get_webhook).put(webhook::update_webhook).delete(webhook::delete_webhook))
+        .route("/websocket/webhooks/:id/deliveries", get(webhook::list_deliveries))
+        .route("/websocket/webhook-deliveries/:id/attempts", get(webhook::list_attempts))
+        .route("/websocket/webhook-deliveries/:id/redeliver", post(webhook::redeliver))
+}
+
+fn alert_router() -> Router<AppState> {
+    Router::new()
+        // 告警规则
+        .route("/websocket/alert-rules", get(alert::list_rules).post(alert::create_rule))
+        .route("/websocket/alert-rules/:id", get(alert::get_rule).put(alert::update_rule).delete(alert::delete_rule))
+        // 告警
+        .route("/websocket/alerts", get(alert::list_alerts))
+        .route("/websocket/alerts/:id", get(alert::get_alert))
+        .route("/websocket/alerts/:id/acknowledge", post(alert::acknowledge_alert))
+        .route("/websocket/alerts/:id/resolve", post(alert::resolve_alert))
+        .route("/websocket/alerts/:id/notifications", get(alert::list_notifications))
+        // 通知渠道
+        .route("/websocket/alert-channels", get(alert::list_channels).post(alert::create_channel))
+        .route("/websocket/alert-channels/:id", get(alert::get_channel).put(alert::update_channel).delete(alert::delete_channel))
+        .route("/websocket/alert-channels/:id/test", post(alert::test_channel))
+}
+
+fn orderbook_router() -> Router<AppState> {
+    Router::new()
+        // 订单簿
+        .route("/orderbook/:config_id", get(orderbook::list_books))
+        .route("/orderbook/:config_id/:symbol", get(orderbook::get_book))
+        .route("/orderbook/:config_id/:symbol/summary", get(orderbook::get_summary))
+}
+
+fn candle_router() -> Router<AppState> {
+    Router::new()
+        // K 线
+        .route("/candles", get(candle::list_candles))
+        .route("/candles/stream", get(candle::stream_candles))
+}
+
+fn group_router() -> Router<AppState> {
+    Router::new()
+        // 连接分组
+        .route("/websocket/groups", get(connection_group::list_groups).post(connection_group::create_group))
+        .route("/websocket/groups/:id", get(connection_group::get_group).put(connection_group::update_group).delete(connection_group::delete_group))
+        .route("/websocket/groups/:id/status", get(connection_group::get_group_status))
+        .route("/websocket/groups/:id/start", post(connection_group::start_group))
+        .route("/websocket/groups/:id/stop", post(connection_group::stop_group))
+        .route("/websocket/groups/:id/restart", post(connection_group::restart_group))
+        // 批量操作
+        .route("/websocket/bulk/start", post(connection_group::bulk_start))
+        .route("/websocket/bulk/stop", post(connection_group::bulk_stop))
+        .route("/websocket/bulk/restart", post(connection_group::bulk_restart))
+}
@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::models::Alert;
+use crate::service::webhook;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// 通知正文中消息内容的最大长度
+const MAX_CONTENT_CHARS: usize = 1000;
+
+lazy_static::lazy_static! {
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("failed to build alert http client");
+}
+
+/// 告警通知渠道：把一条告警发送到外部系统
+pub trait Notifier: Send + Sync {
+    fn send<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<(), String>>;
+}
+
+// 根据渠道类型和 settings（JSON 字符串）创建通知渠道
+pub fn build_channel(kind: &str, settings: &str) -> Result<Box<dyn Notifier>, String> {
+    match kind {
+        "webhook" => Ok(Box::new(parse_settings::<WebhookChannel>(settings)?)),
+        "telegram" => Ok(Box::new(parse_settings::<TelegramChannel>(settings)?)),
+        "discord" => Ok(Box::new(parse_settings::<DiscordChannel>(settings)?)),
+        "smtp" => Ok(Box::new(SmtpChannel::new(parse_settings(settings)?)?)),
+        other => Err(format!("unsupported channel kind: {}", other)),
+    }
+}
+
+fn parse_settings<T: DeserializeOwned>(settings: &str) -> Result<T, String> {
+    serde_json::from_str(settings).map_err(|e| format!("invalid channel settings: {}", e))
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_CONTENT_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn title(alert: &Alert) -> String {
+    format!("[{}] {}", alert.severity.to_uppercase(), alert.rule_name)
+}
+
+// 纯文本通知正文
+fn body(alert: &Alert) -> String {
+    let mut lines = vec![format!("config: {}", alert.config_id)];
+    if !alert.dedup_key.is_empty() {
+        lines.push(format!("key: {}", alert.dedup_key));
+    }
+    lines.push(format!("occurrences: {}", alert.occurrences));
+    lines.push(truncate(&alert.content));
+    lines.join("\n")
+}
+
+async fn post_json(request: reqwest::RequestBuilder, body: &Value) -> Result<reqwest::Response, String> {
+    let response = request.json(body).send().await.map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(format!("HTTP {}", response.status()))
+    }
+}
+
+// 通用 webhook：POST 告警 JSON，配置 secret 时带上与消息 webhook 相同的签名头
+#[derive(Debug, Deserialize)]
+struct WebhookChannel {
+    url: String,
+    secret: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+impl Notifier for WebhookChannel {
+    fn send<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let payload = serde_json::to_string(alert).map_err(|e| e.to_string())?;
+            let mut request = CLIENT
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json");
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
+            }
+            if let Some(secret) = &self.secret {
+                request = request.header("X-Wstool-Signature", format!("sha256={}", webhook::sign(secret, &payload)));
+            }
+            let response = request.body(payload).send().await.map_err(|e| e.to_string())?;
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(format!("HTTP {}", response.status()))
+            }
+        })
+    }
+}
+
+fn default_telegram_api() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+// Telegram Bot API：POST {api_url}/bot<token>/sendMessage
+#[derive(Debug, Deserialize)]
+struct TelegramChannel {
+    bot_token: String,
+    chat_id: Value, // 数字 ID 或 "@channel"
+    #[serde(default = "default_telegram_api")]
+    api_url: String,
+}
+
+impl Notifier for TelegramChannel {
+    fn send<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let url = format!("{}/bot{}/sendMessage", self.api_url.trim_end_matches('/'), self.bot_token);
+            let body = json!({
+                "chat_id": self.chat_id,
+                "text": format!("{}\n{}", title(alert), body(alert)),
+                "disable_web_page_preview": true,
+            });
+            let response = post_json(CLIENT.post(url), &body).await?;
+            // Bot API 出错时 ok 为 false 并带 description
+            let result: Value = response.json().await.map_err(|e| e.to_string())?;
+            if result.get("ok") == Some(&Value::Bool(true)) {
+                Ok(())
+            } else {
+                Err(format!("telegram error: {}", result.get("description").unwrap_or(&result)))
+            }
+        })
+    }
+}
+
+// Discord webhook：一条 embed，颜色按严重程度区分
+#[derive(Debug, Deserialize)]
+struct DiscordChannel {
+    url: String,
+    username: Option<String>,
+}
+
+impl Notifier for DiscordChannel {
+    fn send<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let color = match alert.severity.as_str() {
+                "critical" => 0xe74c3c,
+                "warning" => 0xf1c40f,
+                _ => 0x3498db,
+            };
+            let mut body = json!({
+                "embeds": [{
+                    "title": title(alert),
+                    "description": body(alert),
+                    "color": color,
+                    "timestamp": chrono::DateTime::from_timestamp(alert.last_seen_at, 0).map(|t| t.to_rfc3339()),
+                }],
+            });
+            if let Some(username) = &self.username {
+                body["username"] = Value::String(username.clone());
+            }
+            post_json(CLIENT.post(&self.url), &body).await.map(|_| ())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Security {
+    // 明文（本地测试用的 SMTP 服务）
+    None,
+    #[default]
+    Starttls,
+    Tls,
+}
+
+#[derive(Debug, Deserialize)]
+struct SmtpSettings {
+    host: String,
+    port: Option<u16>,
+    #[serde(default)]
+    security: Security,
+    username: Option<String>,
+    password: Option<String>,
+    from: String,
+    to: Vec<String>,
+}
+
+// SMTP 邮件：security 为 none、starttls 或 tls，端口默认分别为 25、587、465
+struct SmtpChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl SmtpChannel {
+    fn new(settings: SmtpSettings) -> Result<Self, String> {
+        let builder = match settings.security {
+            Security::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host).port(25),
+            Security::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host)
+                .map_err(|e| e.to_string())?,
+            Security::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host).map_err(|e| e.to_string())?,
+        };
+        let mut builder = builder.timeout(Some(REQUEST_TIMEOUT));
+        if let Some(port) = settings.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (settings.username, settings.password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        let parse = |address: &str| address.parse::<Mailbox>().map_err(|e| format!("invalid address {}: {}", address, e));
+        if settings.to.is_empty() {
+            return Err("smtp channel needs at least one recipient".to_string());
+        }
+        Ok(Self {
+            transport: builder.build(),
+            from: parse(&settings.from)?,
+            to: settings.to.iter().map(|address| parse(address)).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl Notifier for SmtpChannel {
+    fn send<'a>(&'a self, alert: &'a Alert) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let mut builder = lettre::Message::builder().from(self.from.clone()).subject(title(alert));
+            for to in &self.to {
+                builder = builder.to(to.clone());
+            }
+            let email = builder.body(body(alert)).map_err(|e| e.to_string())?;
+            self.transport.send(email).await.map(|_| ()).map_err(|e| e.to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode, Uri};
+    use axum::Router;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    struct Received {
+        path: String,
+        headers: HeaderMap,
+        body: String,
+    }
+
+    struct Listener {
+        requests: mpsc::UnboundedSender<Received>,
+        status: StatusCode,
+        response: &'static str,
+    }
+
+    async fn receive(State(listener): State<Arc<Listener>>, uri: Uri, headers: HeaderMap, body: String) -> (StatusCode, &'static str) {
+        let _ = listener.requests.send(Received { path: uri.path().to_string(), headers, body });
+        (listener.status, listener.response)
+    }
+
+    // 本地 HTTP 服务：记录收到的请求，按给定状态码和内容应答
+    async fn spawn_listener(status: u16, response: &'static str) -> (String, mpsc::UnboundedReceiver<Received>) {
+        let (requests, received) = mpsc::unbounded_channel();
+        let listener = Arc::new(Listener { requests, status: StatusCode::from_u16(status).unwrap(), response });
+        let router = Router::new().fallback(receive).with_state(listener);
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", tcp.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(tcp, router).await.unwrap() });
+        (url, received)
+    }
+
+    // 本地 SMTP 服务：接收一封邮件，返回会话中客户端发送的所有行
+    async fn spawn_smtp() -> (u16, tokio::sync::oneshot::Receiver<Vec<String>>) {
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = tcp.local_addr().unwrap().port();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (socket, _) = tcp.accept().await.unwrap();
+            let (read, mut write) = socket.into_split();
+            let mut lines = BufReader::new(read).lines();
+            write.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            let mut received = Vec::new();
+            let mut in_data = false;
+            while let Ok(Some(line)) = lines.next_line().await {
+                received.push(line.clone());
+                if in_data {
+                    if line == "." {
+                        in_data = false;
+                        write.write_all(b"250 queued\r\n").await.unwrap();
+                    }
+                    continue;
+                }
+                let command = line.to_uppercase();
+                if command.starts_with("DATA") {
+                    in_data = true;
+                    write.write_all(b"354 end with .\r\n").await.unwrap();
+                } else if command.starts_with("QUIT") {
+                    write.write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    write.write_all(b"250 localhost\r\n").await.unwrap();
+                }
+            }
+            let _ = tx.send(received);
+        });
+        (port, rx)
+    }
+
+    fn alert() -> Alert {
+        Alert {
+            id: "alert-1".to_string(),
+            rule_id: "rule-1".to_string(),
+            rule_name: "BTC spread".to_string(),
+            config_id: "config-1".to_string(),
+            severity: "critical".to_string(),
+            dedup_key: "BTCUSDT".to_string(),
+            content: r#"{"s":"BTCUSDT","spread":12}"#.to_string(),
+            status: "firing".to_string(),
+            occurrences: 3,
+            created_at: 1_700_000_000,
+            last_seen_at: 1_700_000_060,
+            acknowledged_at: None,
+            resolved_at: None,
+        }
+    }
+
+    async fn next_request(received: &mut mpsc::UnboundedReceiver<Received>) -> Received {
+        received.recv().await.expect("no request")
+    }
+
+    #[tokio::test]
+    async fn webhook_posts_signed_alert() {
+        let (url, mut received) = spawn_listener(200, "").await;
+        let settings = json!({ "url": format!("{}/alerts", url), "secret": "s3cret", "headers": { "X-Team": "ops" } });
+        let channel = build_channel("webhook", &settings.to_string()).unwrap();
+        channel.send(&alert()).await.unwrap();
+
+        let request = next_request(&mut received).await;
+        assert_eq!(request.path, "/alerts");
+        assert_eq!(request.headers["x-team"], "ops");
+        let signature = format!("sha256={}", webhook::sign("s3cret", &request.body));
+        assert_eq!(request.headers["x-wstool-signature"], signature.as_str());
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["id"], "alert-1");
+        assert_eq!(body["occurrences"], 3);
+    }
+
+    #[tokio::test]
+    async fn webhook_reports_http_errors() {
+        let (url, _received) = spawn_listener(500, "").await;
+        let channel = build_channel("webhook", &json!({ "url": url }).to_string()).unwrap();
+        assert_eq!(channel.send(&alert()).await.unwrap_err(), "HTTP 500 Internal Server Error");
+    }
+
+    #[tokio::test]
+    async fn discord_posts_an_embed() {
+        let (url, mut received) = spawn_listener(204, "").await;
+        let settings = json!({ "url": format!("{}/api/webhooks/1/token", url), "username": "wstool" });
+        let channel = build_channel("discord", &settings.to_string()).unwrap();
+        channel.send(&alert()).await.unwrap();
+
+        let request = next_request(&mut received).await;
+        assert_eq!(request.path, "/api/webhooks/1/token");
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["username"], "wstool");
+        let embed = &body["embeds"][0];
+        assert_eq!(embed["title"], "[CRITICAL] BTC spread");
+        assert_eq!(embed["color"], 0xe74c3c);
+        assert!(embed["description"].as_str().unwrap().contains("key: BTCUSDT"));
+    }
+
+    #[tokio::test]
+    async fn telegram_uses_the_configured_api_url() {
+        let (url, mut received) = spawn_listener(200, r#"{"ok":true,"result":{}}"#).await;
+        let settings = json!({ "bot_token": "123:abc", "chat_id": -100, "api_url": format!("{}/", url) });
+        let channel = build_channel("telegram", &settings.to_string()).unwrap();
+        channel.send(&alert()).await.unwrap();
+
+        let request = next_request(&mut received).await;
+        assert_eq!(request.path, "/bot123:abc/sendMessage");
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["chat_id"], -100);
+        assert!(body["text"].as_str().unwrap().starts_with("[CRITICAL] BTC spread\nconfig: config-1"));
+    }
+
+    // Bot API 以 200 返回 ok: false 时视为失败
+    #[tokio::test]
+    async fn telegram_reports_api_errors() {
+        let (url, _received) = spawn_listener(200, r#"{"ok":false,"description":"Bad Request: chat not found"}"#).await;
+        let settings = json!({ "bot_token": "123:abc", "chat_id": "@alerts", "api_url": url });
+        let channel = build_channel("telegram", &settings.to_string()).unwrap();
+        let error = channel.send(&alert()).await.unwrap_err();
+        assert!(error.contains("chat not found"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn smtp_sends_a_mail() {
+        let (port, received) = spawn_smtp().await;
+        let settings = json!({
+            "host": "127.0.0.1",
+            "port": port,
+            "security": "none",
+            "from": "wstool <alerts@example.com>",
+            "to": ["ops@example.com", "oncall@example.com"],
+        });
+        let channel = build_channel("smtp", &settings.to_string()).unwrap();
+        channel.send(&alert()).await.unwrap();
+        drop(channel);
+
+        let lines = received.await.unwrap();
+        assert!(lines.iter().any(|line| line == "MAIL FROM:<alerts@example.com>"), "{:?}", lines);
+        assert!(lines.iter().any(|line| line == "RCPT TO:<ops@example.com>"));
+        assert!(lines.iter().any(|line| line == "RCPT TO:<oncall@example.com>"));
+        assert!(lines.iter().any(|line| line == "Subject: [CRITICAL] BTC spread"));
+        assert!(lines.iter().any(|line| line == "key: BTCUSDT"));
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        assert!(build_channel("pager", "{}").is_err());
+        assert!(build_channel("webhook", "{}").is_err());
+        let no_recipients = json!({ "host": "127.0.0.1", "security": "none", "from": "alerts@example.com", "to": [] });
+        assert!(build_channel("smtp", &no_recipients.to_string()).is_err());
+    }
+}
@@ -0,0 +1,1026 @@
+pub mod channel;
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tokio::sync::{mpsc, RwLock};
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{
+    Alert, AlertChannel, AlertNotification, AlertRule, ApiResponse, NewAlertChannel, NewAlertRule,
+    UpdateAlertChannel, UpdateAlertRule, WebSocketMessage,
+};
+use crate::utils::json::{self, MessageFilter};
+
+const SEVERITIES: [&str; 3] = ["info", "warning", "critical"];
+const CHANNEL_KINDS: [&str; 4] = ["webhook", "telegram", "discord", "smtp"];
+// 清理过期命中记录和冷却记录的间隔
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+// 出现频率条件：window_secs 秒内匹配 count 次才触发
+#[derive(Debug, Deserialize)]
+struct RateSpec {
+    count: usize,
+    window_secs: u64,
+}
+
+// 规则对同一个去重键的一次命中结果
+enum Hit {
+    // 冷却期已过：新建告警（已有未解决的告警时并入该告警）
+    Fire,
+    // 冷却期内：只累计到未解决的告警
+    Repeat,
+}
+
+#[derive(Default)]
+struct RuleState {
+    hits: HashMap<String, VecDeque<Instant>>,
+    fired: HashMap<String, Instant>,
+    swept_at: Option<Instant>,
+}
+
+// 规则命中后待写入的告警
+struct Raised {
+    key: String,
+    message: WebSocketMessage,
+    hit: Hit,
+}
+
+// 已加载的规则及其告警写入队列
+#[derive(Clone)]
+struct LoadedRule {
+    rule: Arc<ActiveRule>,
+    writer: mpsc::UnboundedSender<Raised>,
+}
+
+struct ActiveRule {
+    rule: AlertRule,
+    filter: MessageFilter,
+    rate: Option<(usize, Duration)>,
+    channels: Vec<String>,
+    state: Mutex<RuleState>,
+}
+
+impl ActiveRule {
+    fn compile(rule: AlertRule) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Matcher {
+            rate: Option<RateSpec>,
+        }
+
+        let filter = MessageFilter::parse(Some(&rule.matcher))?;
+        let matcher: Matcher = serde_json::from_str(&rule.matcher).map_err(|e| format!("invalid matcher: {}", e))?;
+        let rate = match matcher.rate {
+            Some(rate) if rate.count == 0 || rate.window_secs == 0 => {
+                return Err("rate count and window_secs must be positive".to_string());
+            }
+            Some(rate) => Some((rate.count, Duration::from_secs(rate.window_secs))),
+            None => None,
+        };
+        if !SEVERITIES.contains(&rule.severity.as_str()) {
+            return Err(format!("severity must be one of {}", SEVERITIES.join(", ")));
+        }
+        if rule.cooldown_secs < 0 {
+            return Err("cooldown_secs must not be negative".to_string());
+        }
+        let channels: Vec<String> =
+            serde_json::from_str(&rule.channels).map_err(|e| format!("invalid channels: {}", e))?;
+        Ok(Self { rule, filter, rate, channels, state: Mutex::new(RuleState::default()) })
+    }
+
+    // 去重键：dedup_key 路径在消息中的值，未配置或取不到时为空
+    fn dedup_key(&self, content: &str) -> String {
+        let Some(path) = self.rule.dedup_key.as_deref() else {
+            return String::new();
+        };
+        serde_json::from_str::<Value>(content)
+            .ok()
+            .and_then(|value| json::lookup(&value, path).map(json::value_to_string))
+            .unwrap_or_default()
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.rule.cooldown_secs as u64)
+    }
+
+    // 记录一次匹配，返回是否需要写告警
+    fn record(&self, key: &str, now: Instant) -> Option<Hit> {
+        let mut state = self.state.lock().unwrap();
+        self.sweep(&mut state, now);
+        if let Some((count, window)) = self.rate {
+            let hits = state.hits.entry(key.to_string()).or_default();
+            hits.push_back(now);
+            while hits.front().is_some_and(|t| now.duration_since(*t) > window) {
+                hits.pop_front();
+            }
+            if hits.len() < count {
+                return None;
+            }
+            hits.clear();
+        }
+        match state.fired.get(key) {
+            Some(fired) if now.duration_since(*fired) < self.cooldown() => Some(Hit::Repeat),
+            _ => {
+                state.fired.insert(key.to_string(), now);
+                Some(Hit::Fire)
+            }
+        }
+    }
+
+    // 定期去掉已移出频率窗口的命中和冷却期已过的记录，避免去重键只增不减
+    fn sweep(&self, state: &mut RuleState, now: Instant) {
+        if state.swept_at.is_some_and(|at| now.duration_since(at) < SWEEP_INTERVAL) {
+            return;
+        }
+        state.swept_at = Some(now);
+        if let Some((_, window)) = self.rate {
+            state.hits.retain(|_, hits| hits.back().is_some_and(|hit| now.duration_since(*hit) <= window));
+        }
+        let cooldown = self.cooldown();
+        state.fired.retain(|_, fired| now.duration_since(*fired) < cooldown);
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref RULES: RwLock<Vec<LoadedRule>> = RwLock::new(Vec::new());
+}
+
+// 从数据库加载启用的告警规则（启动时及规则变更后调用）
+pub async fn load_rules(pool: &SqlitePool) -> anyhow::Result<()> {
+    let rules = sqlx::query_as::<_, AlertRule>("SELECT * FROM t_websocket_alert_rule WHERE enabled = TRUE")
+        .fetch_all(pool)
+        .await?;
+
+    let mut active = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let id = rule.id.clone();
+        match ActiveRule::compile(rule) {
+            Ok(rule) => {
+                // 规则重新加载后旧的写入队列被丢弃，写完剩余告警后任务结束
+                let rule = Arc::new(rule);
+                let (writer, rx) = mpsc::unbounded_channel();
+                tokio::spawn(write_alerts(pool.clone(), rule.clone(), rx));
+                active.push(LoadedRule { rule, writer });
+            }
+            Err(e) => tracing::warn!("Skipping invalid alert rule {}: {}", id, e),
+        }
+    }
+    *RULES.write().await = active;
+    Ok(())
+}
+
+async fn reload(pool: &SqlitePool) {
+    if let Err(e) = load_rules(pool).await {
+        tracing::error!("Failed to reload alert rules: {}", e);
+    }
+}
+
+// 在接收路径中对收到的消息执行告警规则；命中后交给规则的写入任务，不等待数据库
+pub async fn evaluate(message: &WebSocketMessage) {
+    let rules = RULES.read().await.clone();
+    for LoadedRule { rule, writer } in rules {
+        if rule.rule.config_id.as_deref().is_some_and(|id| id != message.config_id) {
+            continue;
+        }
+        if !rule.filter.matches(&message.content) {
+            continue;
+        }
+        let key = rule.dedup_key(&message.content);
+        if let Some(hit) = rule.record(&key, Instant::now()) {
+            let _ = writer.send(Raised { key, message: message.clone(), hit });
+        }
+    }
+}
+
+// 按命中顺序写入同一规则的告警，保证合并到未解决告警的计数不乱序
+async fn write_alerts(pool: SqlitePool, rule: Arc<ActiveRule>, mut rx: mpsc::UnboundedReceiver<Raised>) {
+    while let Some(Raised { key, message, hit }) = rx.recv().await {
+        if let Err(e) = raise(&pool, &rule, key, &message, hit).await {
+            tracing::error!("Failed to save alert for rule {}: {}", rule.rule.id, e);
+        }
+    }
+}
+
+// 同一规则和去重键下只保留一条未解决的告警，新的匹配累计到 occurrences
+async fn raise(pool: &SqlitePool, rule: &ActiveRule, key: String, message: &WebSocketMessage, hit: Hit) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().timestamp();
+    let folded = sqlx::query(
+        r#"
+        UPDATE t_websocket_alert
+        SET occurrences = occurrences + 1, last_seen_at = ?, content = ?
+        WHERE rule_id = ? AND dedup_key = ? AND status != 'resolved'
+        "#
+    )
+    .bind(now)
+    .bind(&message.content)
+    .bind(&rule.rule.id)
+    .bind(&key)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    if folded > 0 || matches!(hit, Hit::Repeat) {
+        return Ok(());
+    }
+
+    let alert = Alert {
+        id: Uuid::new_v4().to_string(),
+        rule_id: rule.rule.id.clone(),
+        rule_name: rule.rule.name.clone(),
+        config_id: message.config_id.clone(),
+        severity: rule.rule.severity.clone(),
+        dedup_key: key,
+        content: message.content.clone(),
+        status: "firing".to_string(),
+        occurrences: 1,
+        created_at: now,
+        last_seen_at: now,
+        acknowledged_at: None,
+        resolved_at: None,
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO t_websocket_alert
+        (id, rule_id, rule_name, config_id, severity, dedup_key, content, status, occurrences, created_at, last_seen_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&alert.id)
+    .bind(&alert.rule_id)
+    .bind(&alert.rule_name)
+    .bind(&alert.config_id)
+    .bind(&alert.severity)
+    .bind(&alert.dedup_key)
+    .bind(&alert.content)
+    .bind(&alert.status)
+    .bind(alert.occurrences)
+    .bind(alert.created_at)
+    .bind(alert.last_seen_at)
+    .execute(pool)
+    .await?;
+
+    tokio::spawn(notify(pool.clone(), alert, rule.channels.clone()));
+    Ok(())
+}
+
+// 通过规则配置的渠道发送告警，并记录每个渠道的结果
+async fn notify(pool: SqlitePool, alert: Alert, channel_ids: Vec<String>) {
+    let channels = match sqlx::query_as::<_, AlertChannel>("SELECT * FROM t_websocket_alert_channel WHERE enabled = TRUE")
+        .fetch_all(&pool)
+        .await
+    {
+        Ok(channels) => channels,
+        Err(e) => {
+            tracing::error!("Failed to fetch alert channels: {}", e);
+            return;
+        }
+    };
+
+    for channel in channels.iter().filter(|channel| channel_ids.contains(&channel.id)) {
+        let result = match channel::build_channel(&channel.kind, &channel.settings) {
+            Ok(notifier) => notifier.send(&alert).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            tracing::warn!("Failed to send alert {} via channel {}: {}", alert.id, channel.id, e);
+        }
+        if let Err(e) = sqlx::query(
+            r#"
+            INSERT INTO t_websocket_alert_notification (id, alert_id, channel_id, success, error, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&alert.id)
+        .bind(&channel.id)
+        .bind(result.is_ok())
+        .bind(result.err())
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&pool)
+        .await
+        {
+            tracing::error!("Failed to save alert notification: {}", e);
+        }
+    }
+}
+
+async fn validate_rule(pool: &SqlitePool, rule: &AlertRule) -> Result<(), StatusCode> {
+    let compiled = ActiveRule::compile(rule.clone()).map_err(|e| {
+        tracing::warn!("Invalid alert rule: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    if let Some(config_id) = &rule.config_id {
+        let config: Option<String> = sqlx::query_scalar("SELECT id FROM t_websocket_config WHERE id = ?")
+            .bind(config_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch websocket config: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if config.is_none() {
+            tracing::warn!("Alert rule config {} not found", config_id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    for channel_id in &compiled.channels {
+        let channel: Option<String> = sqlx::query_scalar("SELECT id FROM t_websocket_alert_channel WHERE id = ?")
+            .bind(channel_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch alert channel: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if channel.is_none() {
+            tracing::warn!("Alert channel {} not found", channel_id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_rule(pool: &SqlitePool, id: &str) -> Result<AlertRule, StatusCode> {
+    match sqlx::query_as::<_, AlertRule>("SELECT * FROM t_websocket_alert_rule WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(rule) => Ok(rule),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch alert rule: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取告警规则列表
+pub async fn list_rules(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<AlertRule>>>, StatusCode> {
+    match sqlx::query_as::<_, AlertRule>("SELECT * FROM t_websocket_alert_rule ORDER BY created_at DESC")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(rules) => Ok(Json(ApiResponse::ok(rules))),
+        Err(e) => {
+            tracing::error!("Failed to fetch alert rules: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个告警规则
+pub async fn get_rule(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<AlertRule>>, StatusCode> {
+    fetch_rule(&state.pool, &id).await.map(|rule| Json(ApiResponse::ok(rule)))
+}
+
+// 创建告警规则
+pub async fn create_rule(
+    State(state): State<AppState>,
+    Json(payload): Json<NewAlertRule>,
+) -> Result<Json<ApiResponse<AlertRule>>, StatusCode> {
+    let now = chrono::Utc::now().timestamp();
+    let rule = AlertRule {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name,
+        config_id: payload.config_id,
+        matcher: payload.matcher,
+        severity: payload.severity.unwrap_or_else(|| "warning".to_string()),
+        cooldown_secs: payload.cooldown_secs.unwrap_or(300),
+        dedup_key: payload.dedup_key,
+        channels: payload.channels.unwrap_or_else(|| "[]".to_string()),
+        enabled: payload.enabled.unwrap_or(true),
+        created_at: now,
+        updated_at: now,
+    };
+    validate_rule(&state.pool, &rule).await?;
+
+    match sqlx::query(
+        r#"
+        INSERT INTO t_websocket_alert_rule
+        (id, name, config_id, matcher, severity, cooldown_secs, dedup_key, channels, enabled, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&rule.id)
+    .bind(&rule.name)
+    .bind(&rule.config_id)
+    .bind(&rule.matcher)
+    .bind(&rule.severity)
+    .bind(rule.cooldown_secs)
+    .bind(&rule.dedup_key)
+    .bind(&rule.channels)
+    .bind(rule.enabled)
+    .bind(rule.created_at)
+    .bind(rule.updated_at)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(rule)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create alert rule: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 更新告警规则（未提供的字段保持不变，冷却与频率计数会重新开始）
+pub async fn update_rule(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateAlertRule>,
+) -> Result<Json<ApiResponse<AlertRule>>, StatusCode> {
+    let mut rule = fetch_rule(&state.pool, &id).await?;
+    if let Some(name) = payload.name {
+        rule.name = name;
+    }
+    if let Some(matcher) = payload.matcher {
+        rule.matcher = matcher;
+    }
+    if let Some(severity) = payload.severity {
+        rule.severity = severity;
+    }
+    if let Some(cooldown_secs) = payload.cooldown_secs {
+        rule.cooldown_secs = cooldown_secs;
+    }
+    if payload.dedup_key.is_some() {
+        rule.dedup_key = payload.dedup_key;
+    }
+    if let Some(channels) = payload.channels {
+        rule.channels = channels;
+    }
+    if let Some(enabled) = payload.enabled {
+        rule.enabled = enabled;
+    }
+    rule.updated_at = chrono::Utc::now().timestamp();
+    validate_rule(&state.pool, &rule).await?;
+
+    match sqlx::query(
+        r#"
+        UPDATE t_websocket_alert_rule
+        SET name = ?, matcher = ?, severity = ?, cooldown_secs = ?, dedup_key = ?, channels = ?, enabled = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&rule.name)
+    .bind(&rule.matcher)
+    .bind(&rule.severity)
+    .bind(rule.cooldown_secs)
+    .bind(&rule.dedup_key)
+    .bind(&rule.channels)
+    .bind(rule.enabled)
+    .bind(rule.updated_at)
+    .bind(&rule.id)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(rule)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to update alert rule: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除告警规则及其告警
+pub async fn delete_rule(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_alert_rule WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(())))
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete alert rule: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlertQuery {
+    pub status: Option<String>,
+    pub severity: Option<String>,
+    pub rule_id: Option<String>,
+    pub config_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// 查询告警（按最近一次匹配时间倒序）
+pub async fn list_alerts(
+    Query(query): Query<AlertQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Alert>>>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    match sqlx::query_as::<_, Alert>(
+        r#"
+        SELECT * FROM t_websocket_alert
+        WHERE (? IS NULL OR status = ?)
+          AND (? IS NULL OR severity = ?)
+          AND (? IS NULL OR rule_id = ?)
+          AND (? IS NULL OR config_id = ?)
+        ORDER BY last_seen_at DESC, created_at DESC LIMIT ?
+        "#
+    )
+    .bind(&query.status)
+    .bind(&query.status)
+    .bind(&query.severity)
+    .bind(&query.severity)
+    .bind(&query.rule_id)
+    .bind(&query.rule_id)
+    .bind(&query.config_id)
+    .bind(&query.config_id)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(alerts) => Ok(Json(ApiResponse::ok(alerts))),
+        Err(e) => {
+            tracing::error!("Failed to fetch alerts: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn fetch_alert(pool: &SqlitePool, id: &str) -> Result<Alert, StatusCode> {
+    match sqlx::query_as::<_, Alert>("SELECT * FROM t_websocket_alert WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(alert) => Ok(alert),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch alert: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个告警
+pub async fn get_alert(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Alert>>, StatusCode> {
+    fetch_alert(&state.pool, &id).await.map(|alert| Json(ApiResponse::ok(alert)))
+}
+
+// 确认告警（仍会累计新的匹配，直到被解决）
+pub async fn acknowledge_alert(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Alert>>, StatusCode> {
+    let alert = fetch_alert(&state.pool, &id).await?;
+    if alert.status != "firing" {
+        return Err(StatusCode::CONFLICT);
+    }
+    set_status(&state.pool, &id, "UPDATE t_websocket_alert SET status = 'acknowledged', acknowledged_at = ? WHERE id = ?").await
+}
+
+// 解决告警，之后同一去重键的匹配会产生新的告警
+pub async fn resolve_alert(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Alert>>, StatusCode> {
+    let alert = fetch_alert(&state.pool, &id).await?;
+    if alert.status == "resolved" {
+        return Err(StatusCode::CONFLICT);
+    }
+    set_status(&state.pool, &id, "UPDATE t_websocket_alert SET status = 'resolved', resolved_at = ? WHERE id = ?").await
+}
+
+async fn set_status(pool: &SqlitePool, id: &str, sql: &'static str) -> Result<Json<ApiResponse<Alert>>, StatusCode> {
+    if let Err(e) = sqlx::query(sql)
+        .bind(chrono::Utc::now().timestamp())
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        tracing::error!("Failed to update alert: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    fetch_alert(pool, id).await.map(|alert| Json(ApiResponse::ok(alert)))
+}
+
+// 获取告警的通知发送记录
+pub async fn list_notifications(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<AlertNotification>>>, StatusCode> {
+    match sqlx::query_as::<_, AlertNotification>(
+        "SELECT * FROM t_websocket_alert_notification WHERE alert_id = ? ORDER BY created_at"
+    )
+    .bind(&id)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(notifications) => Ok(Json(ApiResponse::ok(notifications))),
+        Err(e) => {
+            tracing::error!("Failed to fetch alert notifications: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn check_channel(channel: &AlertChannel) -> Result<(), StatusCode> {
+    if !CHANNEL_KINDS.contains(&channel.kind.as_str()) {
+        tracing::warn!("Invalid alert channel kind: {}", channel.kind);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    channel::build_channel(&channel.kind, &channel.settings).map(|_| ()).map_err(|e| {
+        tracing::warn!("Invalid alert channel: {}", e);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+async fn fetch_channel(pool: &SqlitePool, id: &str) -> Result<AlertChannel, StatusCode> {
+    match sqlx::query_as::<_, AlertChannel>("SELECT * FROM t_websocket_alert_channel WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(channel) => Ok(channel),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch alert channel: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取通知渠道列表
+pub async fn list_channels(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<AlertChannel>>>, StatusCode> {
+    match sqlx::query_as::<_, AlertChannel>("SELECT * FROM t_websocket_alert_channel ORDER BY created_at DESC")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(channels) => Ok(Json(ApiResponse::ok(channels))),
+        Err(e) => {
+            tracing::error!("Failed to fetch alert channels: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个通知渠道
+pub async fn get_channel(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<AlertChannel>>, StatusCode> {
+    fetch_channel(&state.pool, &id).await.map(|channel| Json(ApiResponse::ok(channel)))
+}
+
+// 创建通知渠道
+pub async fn create_channel(
+    State(state): State<AppState>,
+    Json(payload): Json<NewAlertChannel>,
+) -> Result<Json<ApiResponse<AlertChannel>>, StatusCode> {
+    let now = chrono::Utc::now().timestamp();
+    let channel = AlertChannel {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name,
+        kind: payload.kind,
+        settings: payload.settings,
+        enabled: payload.enabled.unwrap_or(true),
+        created_at: now,
+        updated_at: now,
+    };
+    check_channel(&channel)?;
+
+    match sqlx::query(
+        r#"
+        INSERT INTO t_websocket_alert_channel (id, name, kind, settings, enabled, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&channel.id)
+    .bind(&channel.name)
+    .bind(&channel.kind)
+    .bind(&channel.settings)
+    .bind(channel.enabled)
+    .bind(channel.created_at)
+    .bind(channel.updated_at)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::ok(channel))),
+        Err(e) => {
+            tracing::error!("Failed to create alert channel: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 更新通知渠道
+pub async fn update_channel(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateAlertChannel>,
+) -> Result<Json<ApiResponse<AlertChannel>>, StatusCode> {
+    let mut channel = fetch_channel(&state.pool, &id).await?;
+    if let Some(name) = payload.name {
+        channel.name = name;
+    }
+    if let Some(settings) = payload.settings {
+        channel.settings = settings;
+    }
+    if let Some(enabled) = payload.enabled {
+        channel.enabled = enabled;
+    }
+    channel.updated_at = chrono::Utc::now().timestamp();
+    check_channel(&channel)?;
+
+    match sqlx::query("UPDATE t_websocket_alert_channel SET name = ?, settings = ?, enabled = ?, updated_at = ? WHERE id = ?")
+        .bind(&channel.name)
+        .bind(&channel.settings)
+        .bind(channel.enabled)
+        .bind(channel.updated_at)
+        .bind(&channel.id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::ok(channel))),
+        Err(e) => {
+            tracing::error!("Failed to update alert channel: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除通知渠道（仍引用它的规则会跳过该渠道）
+pub async fn delete_channel(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_alert_channel WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(Json(ApiResponse::ok(()))),
+        Err(e) => {
+            tracing::error!("Failed to delete alert channel: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 发送一条测试告警，用于验证渠道配置
+pub async fn test_channel(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let channel = fetch_channel(&state.pool, &id).await?;
+    let now = chrono::Utc::now().timestamp();
+    let alert = Alert {
+        id: Uuid::new_v4().to_string(),
+        rule_id: String::new(),
+        rule_name: format!("Test alert from channel {}", channel.name),
+        config_id: String::new(),
+        severity: "info".to_string(),
+        dedup_key: String::new(),
+        content: "This is a test notification.".to_string(),
+        status: "firing".to_string(),
+        occurrences: 1,
+        created_at: now,
+        last_seen_at: now,
+        acknowledged_at: None,
+        resolved_at: None,
+    };
+    let result = match channel::build_channel(&channel.kind, &channel.settings) {
+        Ok(notifier) => notifier.send(&alert).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => Ok(Json(ApiResponse::ok(()))),
+        Err(e) => Ok(Json(ApiResponse::err(e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::State as AxumState, routing::post as post_route, Router};
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    fn rule(matcher: &str, cooldown_secs: i64, dedup_key: Option<&str>) -> ActiveRule {
+        ActiveRule::compile(AlertRule {
+            id: "rule-1".to_string(),
+            name: "spread".to_string(),
+            config_id: None,
+            matcher: matcher.to_string(),
+            severity: "warning".to_string(),
+            cooldown_secs,
+            dedup_key: dedup_key.map(str::to_string),
+            channels: "[]".to_string(),
+            enabled: true,
+            created_at: 0,
+            updated_at: 0,
+        })
+        .unwrap()
+    }
+
+    fn message(content: &str) -> WebSocketMessage {
+        WebSocketMessage {
+            id: Uuid::new_v4().to_string(),
+            config_id: "config-1".to_string(),
+            message_type: "received".to_string(),
+            content: content.to_string(),
+            timestamp: 0,
+            status: "success".to_string(),
+            error_message: None,
+            topic: None,
+            event: None,
+            session_id: None,
+            timestamp_us: None,
+            valid: None,
+            validation_error: None,
+            raw_content: None,
+            qos: None,
+            retain: None,
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn fires_once_per_key_and_cooldown() {
+        let rule = rule("{}", 60, Some("s"));
+        assert_eq!(rule.dedup_key(r#"{"s":"BTCUSDT"}"#), "BTCUSDT");
+        let start = Instant::now();
+        assert!(matches!(rule.record("BTCUSDT", start), Some(Hit::Fire)));
+        assert!(matches!(rule.record("BTCUSDT", start + secs(10)), Some(Hit::Repeat)));
+        // 不同的去重键各自冷却
+        assert!(matches!(rule.record("ETHUSDT", start + secs(10)), Some(Hit::Fire)));
+        assert!(matches!(rule.record("BTCUSDT", start + secs(60)), Some(Hit::Fire)));
+    }
+
+    #[test]
+    fn fires_when_rate_is_reached_within_the_window() {
+        let rule = rule(r#"{"rate": {"count": 3, "window_secs": 10}}"#, 0, None);
+        let start = Instant::now();
+        assert!(rule.record("", start).is_none());
+        assert!(rule.record("", start + secs(1)).is_none());
+        assert!(matches!(rule.record("", start + secs(2)), Some(Hit::Fire)));
+        // 触发后重新计数；移出窗口的命中不计入
+        assert!(rule.record("", start + secs(20)).is_none());
+        assert!(rule.record("", start + secs(31)).is_none());
+        assert!(rule.record("", start + secs(32)).is_none());
+        assert!(matches!(rule.record("", start + secs(33)), Some(Hit::Fire)));
+    }
+
+    #[test]
+    fn evicts_keys_outside_the_window_and_cooldown() {
+        let rule = rule(r#"{"rate": {"count": 2, "window_secs": 10}}"#, 30, Some("s"));
+        let start = Instant::now();
+        for index in 0..100 {
+            let key = format!("key-{}", index);
+            rule.record(&key, start);
+            rule.record(&key, start + secs(1));
+        }
+        rule.record("late", start + secs(5));
+        {
+            let state = rule.state.lock().unwrap();
+            assert_eq!((state.hits.len(), state.fired.len()), (101, 100));
+        }
+
+        // 窗口和冷却期都已过，下一次清理只留下新的命中
+        rule.record("fresh", start + SWEEP_INTERVAL + secs(31));
+        let state = rule.state.lock().unwrap();
+        assert_eq!(state.hits.keys().collect::<Vec<_>>(), ["fresh"]);
+        assert!(state.fired.is_empty());
+    }
+
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::migrate(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO t_websocket_alert_rule (id, name, matcher, severity, channels, created_at, updated_at) VALUES ('rule-1', 'spread', '{}', 'warning', '[]', 0, 0)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool
+    }
+
+    async fn stored_alerts(pool: &SqlitePool) -> Vec<Alert> {
+        sqlx::query_as("SELECT * FROM t_websocket_alert ORDER BY created_at, dedup_key")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    // 写入任务按顺序处理：冷却期内的命中并入未解决的告警，解决后再次触发时新建告警
+    #[tokio::test]
+    async fn folds_repeats_into_the_open_alert() {
+        let pool = test_pool().await;
+        let rule = Arc::new(rule("{}", 60, Some("s")));
+        let (writer, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(write_alerts(pool.clone(), rule.clone(), rx));
+        let start = Instant::now();
+        for (content, at) in [(r#"{"s":"BTC","n":1}"#, 0), (r#"{"s":"BTC","n":2}"#, 5), (r#"{"s":"ETH","n":3}"#, 6)] {
+            let key = rule.dedup_key(content);
+            let hit = rule.record(&key, start + secs(at)).unwrap();
+            writer.send(Raised { key, message: message(content), hit }).unwrap();
+        }
+        drop(writer);
+        task.await.unwrap();
+
+        let alerts = stored_alerts(&pool).await;
+        assert_eq!(alerts.len(), 2);
+        let btc = alerts.iter().find(|alert| alert.dedup_key == "BTC").unwrap();
+        assert_eq!(btc.occurrences, 2);
+        assert_eq!(btc.content, r#"{"s":"BTC","n":2}"#);
+        assert_eq!(btc.status, "firing");
+
+        sqlx::query("UPDATE t_websocket_alert SET status = 'resolved'").execute(&pool).await.unwrap();
+        // 冷却期内只累计到未解决的告警，没有时不新建
+        raise(&pool, &rule, "BTC".to_string(), &message("{}"), Hit::Repeat).await.unwrap();
+        assert_eq!(stored_alerts(&pool).await.len(), 2);
+        raise(&pool, &rule, "BTC".to_string(), &message("{}"), Hit::Fire).await.unwrap();
+        assert_eq!(stored_alerts(&pool).await.len(), 3);
+    }
+
+    async fn accept(AxumState(status): AxumState<u16>) -> StatusCode {
+        StatusCode::from_u16(status).unwrap()
+    }
+
+    async fn spawn_listener(status: u16) -> String {
+        let router = Router::new().route("/hook", post_route(accept)).with_state(status);
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", tcp.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(tcp, router).await.unwrap() });
+        url
+    }
+
+    // 每个渠道的发送结果都记录下来，禁用的渠道跳过
+    #[tokio::test]
+    async fn records_each_channel_result() {
+        let pool = test_pool().await;
+        let channels = [
+            ("channel-ok", spawn_listener(200).await, true),
+            ("channel-down", spawn_listener(503).await, true),
+            ("channel-off", spawn_listener(200).await, false),
+        ];
+        for (id, url, enabled) in &channels {
+            sqlx::query(
+                "INSERT INTO t_websocket_alert_channel (id, name, kind, settings, enabled, created_at, updated_at) VALUES (?, ?, 'webhook', ?, ?, 0, 0)"
+            )
+            .bind(id)
+            .bind(id)
+            .bind(serde_json::json!({ "url": url }).to_string())
+            .bind(enabled)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let rule = rule("{}", 0, None);
+        raise(&pool, &rule, String::new(), &message("{}"), Hit::Fire).await.unwrap();
+        let alert = stored_alerts(&pool).await.remove(0);
+        notify(pool.clone(), alert.clone(), vec!["channel-ok".to_string(), "channel-down".to_string(), "channel-off".to_string()]).await;
+
+        let mut results: Vec<(String, bool, Option<String>)> = sqlx::query_as(
+            "SELECT channel_id, success, error FROM t_websocket_alert_notification WHERE alert_id = ?"
+        )
+        .bind(&alert.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        results.sort();
+        assert_eq!(results, [
+            ("channel-down".to_string(), false, Some("HTTP 503 Service Unavailable".to_string())),
+            ("channel-ok".to_string(), true, None),
+        ]);
+    }
+}
@@ -0,0 +1,278 @@
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Connection, QueryBuilder, Row, Sqlite, SqliteConnection, TypeInfo, ValueRef};
+
+use crate::app::AppState;
+use crate::models::{AnalyticsAggregate, AnalyticsQuery, AnalyticsResult, ApiResponse};
+use crate::service::candle::parse_interval;
+use crate::utils::json;
+
+const DEFAULT_WINDOW_SECS: i64 = 3600;
+const MAX_WINDOW_SECS: i64 = 31 * 86400;
+// 时间窗口内的消息数上限，超过时拒绝查询
+const MAX_SCANNED_ROWS: i64 = 2_000_000;
+// 单次查询的执行时间上限，超时由 SQLite 中断
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_LIMIT: i64 = 1000;
+const MAX_LIMIT: i64 = 10_000;
+const MAX_GROUP_BY: usize = 5;
+const MAX_AGGREGATES: usize = 20;
+
+// 内容不是 JSON 时取 NULL，避免 json_extract 报错
+const EXTRACT: &str = "CASE WHEN json_valid(content) THEN json_extract(content, ";
+// first/last：按接收顺序取第一个/最后一个非空值（值以 JSON 文本返回）
+const ORDERED_VALUE: &str =
+    "printf('%020d', COALESCE(timestamp_us, timestamp * 1000000)) || (CASE WHEN json_valid(content) THEN content -> ";
+
+fn push_extract(builder: &mut QueryBuilder<'_, Sqlite>, path: &str) {
+    builder.push(EXTRACT).push_bind(json::sqlite_path(path)).push(") END");
+}
+
+// 配置、时间窗口和方向条件，走 (config_id, timestamp) 索引
+fn push_window(builder: &mut QueryBuilder<'_, Sqlite>, query: &AnalyticsQuery, since: i64, until: i64) {
+    builder
+        .push(" WHERE config_id = ")
+        .push_bind(query.config_id.clone())
+        .push(" AND timestamp >= ")
+        .push_bind(since)
+        .push(" AND timestamp <= ")
+        .push_bind(until);
+    if let Some(message_type) = &query.message_type {
+        builder.push(" AND message_type = ").push_bind(message_type.clone());
+    }
+}
+
+fn output_name(aggregate: &AnalyticsAggregate) -> String {
+    match (&aggregate.alias, &aggregate.path) {
+        (Some(alias), _) => alias.clone(),
+        (None, Some(path)) => format!("{}({})", aggregate.function, path),
+        (None, None) => aggregate.function.clone(),
+    }
+}
+
+fn check_query(query: &AnalyticsQuery) -> Result<(), String> {
+    let group_by = query.group_by.as_deref().unwrap_or_default();
+    if query.aggregates.is_empty() || query.aggregates.len() > MAX_AGGREGATES {
+        return Err(format!("between 1 and {} aggregates are required", MAX_AGGREGATES));
+    }
+    if group_by.len() > MAX_GROUP_BY {
+        return Err(format!("at most {} group_by paths are allowed", MAX_GROUP_BY));
+    }
+    if group_by.iter().any(|path| json::path_keys(path).is_empty()) {
+        return Err("group_by paths must not be empty".to_string());
+    }
+    for aggregate in &query.aggregates {
+        if !matches!(aggregate.function.as_str(), "count" | "min" | "max" | "avg" | "sum" | "first" | "last") {
+            return Err(format!("unsupported aggregate: {}", aggregate.function));
+        }
+        let has_path = aggregate.path.as_deref().is_some_and(|path| !json::path_keys(path).is_empty());
+        if aggregate.function != "count" && !has_path {
+            return Err(format!("{} needs a path", aggregate.function));
+        }
+    }
+    let mut names: Vec<String> = group_by.to_vec();
+    names.extend(query.aggregates.iter().map(output_name));
+    if query.bucket.is_some() {
+        names.push("bucket".to_string());
+    }
+    let mut unique = names.clone();
+    unique.sort();
+    unique.dedup();
+    if unique.len() != names.len() {
+        return Err("output column names must be unique".to_string());
+    }
+    Ok(())
+}
+
+// 把查询编译为参数化 SQL：路径全部作为绑定参数，函数名和列别名由服务端生成
+fn build_query(
+    query: &AnalyticsQuery,
+    bucket_secs: Option<i64>,
+    since: i64,
+    until: i64,
+    limit: i64,
+) -> QueryBuilder<'static, Sqlite> {
+    let group_by = query.group_by.clone().unwrap_or_default();
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT ");
+    let mut groups = Vec::new();
+    if let Some(bucket) = bucket_secs {
+        builder.push("(timestamp / ").push_bind(bucket).push(") * ").push_bind(bucket).push(" AS bucket, ");
+        groups.push("bucket".to_string());
+    }
+    for (i, path) in group_by.iter().enumerate() {
+        push_extract(&mut builder, path);
+        builder.push(format!(" AS g{}, ", i));
+        groups.push(format!("g{}", i));
+    }
+
+    for (i, aggregate) in query.aggregates.iter().enumerate() {
+        if i > 0 {
+            builder.push(", ");
+        }
+        let path = aggregate.path.as_deref().filter(|path| !json::path_keys(path).is_empty());
+        match (aggregate.function.as_str(), path) {
+            ("count", None) => {
+                builder.push("COUNT(*)");
+            }
+            ("count", Some(path)) => {
+                builder.push("COUNT(");
+                push_extract(&mut builder, path);
+                builder.push(")");
+            }
+            (function @ ("first" | "last"), Some(path)) => {
+                builder.push(format!("substr({}(", if function == "first" { "MIN" } else { "MAX" }));
+                builder.push(ORDERED_VALUE).push_bind(json::sqlite_path(path)).push(" END)), 21)");
+            }
+            // 数值聚合：字符串形式的数字（如 "100.5"）按数值计算
+            (function, Some(path)) => {
+                builder.push(format!("{}(CAST(", function.to_uppercase()));
+                push_extract(&mut builder, path);
+                builder.push(" AS REAL))");
+            }
+            (_, None) => {}
+        }
+        builder.push(format!(" AS a{}", i));
+    }
+
+    builder.push(" FROM t_websocket_message");
+    push_window(&mut builder, query, since, until);
+    if !groups.is_empty() {
+        let groups = groups.join(", ");
+        builder.push(format!(" GROUP BY {} ORDER BY {}", groups, groups));
+    }
+    builder.push(" LIMIT ").push_bind(limit + 1);
+    builder
+}
+
+fn column_value(row: &SqliteRow, index: usize, json_text: bool) -> Value {
+    let Ok(raw) = row.try_get_raw(index) else {
+        return Value::Null;
+    };
+    if raw.is_null() {
+        return Value::Null;
+    }
+    let kind = raw.type_info().name().to_string();
+    match kind.as_str() {
+        "INTEGER" => row.try_get::<i64, _>(index).map(Value::from).unwrap_or(Value::Null),
+        "REAL" => row.try_get::<f64, _>(index).map(Value::from).unwrap_or(Value::Null),
+        _ => match row.try_get::<String, _>(index) {
+            Ok(text) if json_text => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+            Ok(text) => Value::String(text),
+            Err(_) => Value::Null,
+        },
+    }
+}
+
+async fn run_query(
+    conn: &mut SqliteConnection,
+    query: &AnalyticsQuery,
+    bucket_secs: Option<i64>,
+    since: i64,
+    until: i64,
+    limit: i64,
+) -> Result<Result<(i64, Vec<SqliteRow>), String>, sqlx::Error> {
+    let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM t_websocket_message");
+    push_window(&mut count, query, since, until);
+    let scanned: i64 = count.build_query_scalar().fetch_one(&mut *conn).await?;
+    if scanned > MAX_SCANNED_ROWS {
+        return Ok(Err(format!("{} messages in the window, the limit is {}", scanned, MAX_SCANNED_ROWS)));
+    }
+    let rows = build_query(query, bucket_secs, since, until, limit).build().fetch_all(&mut *conn).await?;
+    Ok(Ok((scanned, rows)))
+}
+
+// 按 JSON 字段分组、按时间分桶聚合消息（只读）
+pub async fn query_messages(
+    State(state): State<AppState>,
+    Json(query): Json<AnalyticsQuery>,
+) -> Result<Json<ApiResponse<AnalyticsResult>>, StatusCode> {
+    let started = Instant::now();
+    if let Err(e) = check_query(&query) {
+        tracing::warn!("Invalid analytics query: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let bucket_secs = match query.bucket.as_deref().map(parse_interval).transpose() {
+        Ok(bucket) => bucket.map(|ms| ms / 1000),
+        Err(e) => {
+            tracing::warn!("Invalid analytics query: {}", e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    let until = query.until.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let since = query.since.unwrap_or(until - DEFAULT_WINDOW_SECS);
+    if since > until || until - since > MAX_WINDOW_SECS {
+        tracing::warn!("Invalid analytics window: {} - {}", since, until);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    // 使用独立连接并设置进度回调，超时后中断查询；连接用完即关闭，不放回连接池
+    let mut conn = match state.pool.acquire().await {
+        Ok(conn) => conn.detach(),
+        Err(e) => {
+            tracing::error!("Failed to acquire database connection: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let deadline = started + QUERY_TIMEOUT;
+    match conn.lock_handle().await {
+        Ok(mut handle) => handle.set_progress_handler(1000, move || Instant::now() < deadline),
+        Err(e) => {
+            tracing::error!("Failed to lock database connection: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    let result = run_query(&mut conn, &query, bucket_secs, since, until, limit).await;
+    let _ = conn.close().await;
+    let (scanned, mut rows) = match result {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => {
+            tracing::warn!("Analytics query rejected: {}", e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("9") => {
+            tracing::warn!("Analytics query exceeded {:?}", QUERY_TIMEOUT);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Err(e) => {
+            tracing::error!("Failed to run analytics query: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let truncated = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    // 列名：bucket、分组路径、聚合输出名，与 SELECT 的顺序一致
+    let mut columns: Vec<(String, bool)> = Vec::new();
+    if bucket_secs.is_some() {
+        columns.push(("bucket".to_string(), false));
+    }
+    columns.extend(query.group_by.iter().flatten().map(|path| (path.clone(), false)));
+    columns.extend(
+        query
+            .aggregates
+            .iter()
+            .map(|aggregate| (output_name(aggregate), matches!(aggregate.function.as_str(), "first" | "last"))),
+    );
+    let rows = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .enumerate()
+                .map(|(i, (name, json_text))| (name.clone(), column_value(row, i, *json_text)))
+                .collect::<Map<String, Value>>()
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::ok(AnalyticsResult {
+        columns: columns.into_iter().map(|(name, _)| name).collect(),
+        rows,
+        scanned,
+        truncated,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })))
+}
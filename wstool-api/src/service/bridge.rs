@@ -0,0 +1,663 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tokio::sync::{broadcast::error::RecvError, RwLock};
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{ApiResponse, DeadLetter, NewRoute, Route, RouteStats, SendMessageRequest, SentMessage, UpdateRoute};
+use crate::service::protocol::Inbound;
+use crate::service::transform::Pipeline;
+use crate::service::websocket_manager::WEBSOCKET_MANAGER;
+use crate::utils::json::MessageFilter;
+
+// 编译后的路由
+struct CompiledRoute {
+    id: String,
+    destinations: Vec<String>,
+    filter: MessageFilter,
+    pipeline: Option<Pipeline>,
+    stats: Arc<Mutex<RouteStats>>,
+}
+
+lazy_static::lazy_static! {
+    // 源配置ID -> 启用的路由
+    static ref ROUTES: RwLock<HashMap<String, Vec<Arc<CompiledRoute>>>> = RwLock::new(HashMap::new());
+    // 路由计数器，重新加载路由时保留
+    static ref STATS: Mutex<HashMap<String, Arc<Mutex<RouteStats>>>> = Mutex::new(HashMap::new());
+    // 已入队、等待写出结果的转发（发送消息ID -> 转发）
+    static ref PENDING: Mutex<HashMap<String, PendingForward>> = Mutex::new(HashMap::new());
+}
+
+// 超过该时间仍未确认写出的转发按失败处理
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct PendingForward {
+    route: Arc<CompiledRoute>,
+    destination: String,
+    content: String,
+    queued_at: Instant,
+}
+
+fn stats_for(route_id: &str) -> Arc<Mutex<RouteStats>> {
+    STATS
+        .lock()
+        .unwrap()
+        .entry(route_id.to_string())
+        .or_insert_with(|| {
+            Arc::new(Mutex::new(RouteStats {
+                route_id: route_id.to_string(),
+                ..Default::default()
+            }))
+        })
+        .clone()
+}
+
+fn compile(route: &Route) -> Result<CompiledRoute, String> {
+    let destinations: Vec<String> =
+        serde_json::from_str(&route.destinations).map_err(|e| format!("invalid destinations: {}", e))?;
+    if destinations.is_empty() {
+        return Err("route needs at least one destination".to_string());
+    }
+    if destinations.contains(&route.source_id) {
+        return Err("route cannot forward to its own source".to_string());
+    }
+    let filter = MessageFilter::parse(route.filter.as_deref())?;
+    let pipeline = match route.transforms.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => {
+            let steps: Value = serde_json::from_str(raw).map_err(|e| format!("invalid transforms: {}", e))?;
+            Some(Pipeline::new(&steps)?)
+        }
+        _ => None,
+    };
+    if pipeline.as_ref().is_some_and(Pipeline::decompresses) {
+        return Err("route transforms cannot decompress".to_string());
+    }
+    Ok(CompiledRoute {
+        id: route.id.clone(),
+        destinations,
+        filter,
+        pipeline,
+        stats: stats_for(&route.id),
+    })
+}
+
+// 从数据库加载启用的路由（启动时及路由变更后调用）
+pub async fn load_routes(pool: &SqlitePool) -> anyhow::Result<()> {
+    let routes = sqlx::query_as::<_, Route>("SELECT * FROM t_websocket_route WHERE enabled = TRUE")
+        .fetch_all(pool)
+        .await?;
+
+    let mut by_source: HashMap<String, Vec<Arc<CompiledRoute>>> = HashMap::new();
+    for route in routes {
+        match compile(&route) {
+            Ok(compiled) => by_source.entry(route.source_id.clone()).or_default().push(Arc::new(compiled)),
+            Err(e) => tracing::warn!("Skipping invalid route {}: {}", route.id, e),
+        }
+    }
+    *ROUTES.write().await = by_source;
+    Ok(())
+}
+
+async fn reload(pool: &SqlitePool) {
+    if let Err(e) = load_routes(pool).await {
+        tracing::error!("Failed to reload websocket routes: {}", e);
+    }
+}
+
+fn record_error(route: &CompiledRoute, error: &str) {
+    let mut stats = route.stats.lock().unwrap();
+    stats.errors += 1;
+    stats.last_error = Some(error.to_string());
+}
+
+async fn save_dead_letter(pool: &SqlitePool, route_id: &str, destination_id: Option<&str>, content: &str, error: &str) {
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_dead_letter (id, route_id, destination_id, content, error, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(route_id)
+    .bind(destination_id)
+    .bind(content)
+    .bind(error)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool)
+    .await
+    {
+        tracing::error!("Failed to save dead letter: {}", e);
+    }
+}
+
+// 把源配置收到的消息按路由转发到目标发送配置
+pub async fn forward(pool: &SqlitePool, source_id: &str, message: &Inbound) {
+    let routes = match ROUTES.read().await.get(source_id) {
+        Some(routes) => routes.clone(),
+        None => return,
+    };
+
+    for route in routes {
+        if !route.filter.matches(&message.content) {
+            route.stats.lock().unwrap().dropped += 1;
+            continue;
+        }
+        let outputs = match &route.pipeline {
+            Some(pipeline) => match pipeline.apply(message) {
+                Ok(outputs) => outputs,
+                Err(e) => {
+                    record_error(&route, &e);
+                    save_dead_letter(pool, &route.id, None, &message.content, &e).await;
+                    continue;
+                }
+            },
+            None => vec![message.clone()],
+        };
+        if outputs.is_empty() {
+            route.stats.lock().unwrap().dropped += 1;
+            continue;
+        }
+
+        for output in &outputs {
+            for destination in &route.destinations {
+                let request = SendMessageRequest {
+                    config_id: destination.clone(),
+                    message: output.content.clone(),
+                    custom_headers: None,
+                    topic: output.topic.clone(),
+                    event: output.event.clone(),
+                    qos: None,
+                    retain: None,
+                };
+                // 入队成功只说明进入了发送队列，写出结果由 track_deliveries 计数
+                let message_id = Uuid::new_v4().to_string();
+                PENDING.lock().unwrap().insert(message_id.clone(), PendingForward {
+                    route: route.clone(),
+                    destination: destination.clone(),
+                    content: output.content.clone(),
+                    queued_at: Instant::now(),
+                });
+                if let Err(e) = WEBSOCKET_MANAGER.send_message(request, Some(message_id.clone())).await {
+                    PENDING.lock().unwrap().remove(&message_id);
+                    fail(pool, &route, destination, &output.content, &e.to_string()).await;
+                }
+            }
+        }
+    }
+}
+
+async fn fail(pool: &SqlitePool, route: &CompiledRoute, destination: &str, content: &str, error: &str) {
+    tracing::warn!("Route {} failed to forward to {}: {}", route.id, destination, error);
+    record_error(route, error);
+    save_dead_letter(pool, &route.id, Some(destination), content, error).await;
+}
+
+// 按发送状态结算转发：写出成功计入 forwarded，写出失败记错误和死信
+async fn complete(pool: &SqlitePool, delivery: SentMessage) {
+    let Some(pending) = PENDING.lock().unwrap().remove(&delivery.id) else {
+        return;
+    };
+    if delivery.status == "success" {
+        pending.route.stats.lock().unwrap().forwarded += 1;
+        return;
+    }
+    let error = delivery.error_message.unwrap_or_else(|| "message was not sent".to_string());
+    fail(pool, &pending.route, &pending.destination, &pending.content, &error).await;
+}
+
+// 超时未确认的转发（如状态广播滞后丢失）按失败处理
+async fn expire(pool: &SqlitePool, now: Instant) {
+    let expired: Vec<PendingForward> = {
+        let mut pending = PENDING.lock().unwrap();
+        let ids: Vec<String> = pending
+            .iter()
+            .filter(|(_, forward)| now.duration_since(forward.queued_at) >= DELIVERY_TIMEOUT)
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.iter().filter_map(|id| pending.remove(id)).collect()
+    };
+    for forward in expired {
+        fail(pool, &forward.route, &forward.destination, &forward.content, "delivery was not confirmed").await;
+    }
+}
+
+// 后台跟踪转发消息的写出结果（启动时调用一次）
+pub fn track_deliveries(pool: SqlitePool) {
+    let mut deliveries = WEBSOCKET_MANAGER.subscribe_deliveries();
+    tokio::spawn(async move {
+        let mut sweep = tokio::time::interval(DELIVERY_TIMEOUT);
+        loop {
+            tokio::select! {
+                delivery = deliveries.recv() => match delivery {
+                    Ok(delivery) => complete(&pool, delivery).await,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Route delivery tracker skipped {} status updates", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = sweep.tick() => expire(&pool, Instant::now()).await,
+            }
+        }
+    });
+}
+
+// 检查启用后是否形成转发环（包括 A→B→A 这样的多跳环）
+async fn check_cycles(pool: &SqlitePool, route: &Route, destinations: &[String]) -> Result<(), StatusCode> {
+    if !route.enabled {
+        return Ok(());
+    }
+    let routes: Vec<(String, String)> =
+        sqlx::query_as("SELECT source_id, destinations FROM t_websocket_route WHERE enabled = TRUE AND id != ?")
+            .bind(&route.id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch websocket routes: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+    for (source_id, raw) in routes {
+        let targets: Vec<String> = serde_json::from_str(&raw).unwrap_or_default();
+        edges.entry(source_id).or_default().extend(targets);
+    }
+
+    let mut visited = HashSet::new();
+    let mut stack = destinations.to_vec();
+    while let Some(config_id) = stack.pop() {
+        if config_id == route.source_id {
+            tracing::warn!("Route {} would forward messages back to {}", route.id, route.source_id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        if let Some(targets) = edges.get(&config_id) {
+            if visited.insert(config_id) {
+                stack.extend(targets.iter().cloned());
+            }
+        }
+    }
+    Ok(())
+}
+
+// 校验路由：源配置存在，目标均为发送配置
+async fn validate(pool: &SqlitePool, route: &Route) -> Result<(), StatusCode> {
+    let compiled = compile(route).map_err(|e| {
+        tracing::warn!("Invalid websocket route: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let source: Option<String> = sqlx::query_scalar("SELECT id FROM t_websocket_config WHERE id = ?")
+        .bind(&route.source_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch websocket config: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if source.is_none() {
+        tracing::warn!("Route source {} not found", route.source_id);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    for destination in &compiled.destinations {
+        let config_type: Option<String> = sqlx::query_scalar("SELECT config_type FROM t_websocket_config WHERE id = ?")
+            .bind(destination)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to fetch websocket config: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if config_type.as_deref() != Some("sender") {
+            tracing::warn!("Route destination {} is not a sender config", destination);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    check_cycles(pool, route, &compiled.destinations).await
+}
+
+async fn fetch_route(pool: &SqlitePool, id: &str) -> Result<Route, StatusCode> {
+    match sqlx::query_as::<_, Route>("SELECT * FROM t_websocket_route WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(route) => Ok(route),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket route: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取路由列表
+pub async fn list_routes(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Route>>>, StatusCode> {
+    match sqlx::query_as::<_, Route>("SELECT * FROM t_websocket_route ORDER BY created_at DESC")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(routes) => Ok(Json(ApiResponse::ok(routes))),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket routes: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个路由
+pub async fn get_route(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Route>>, StatusCode> {
+    fetch_route(&state.pool, &id).await.map(|route| Json(ApiResponse::ok(route)))
+}
+
+// 创建路由
+pub async fn create_route(
+    State(state): State<AppState>,
+    Json(payload): Json<NewRoute>,
+) -> Result<Json<ApiResponse<Route>>, StatusCode> {
+    let now = chrono::Utc::now().timestamp();
+    let route = Route {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name,
+        source_id: payload.source_id,
+        destinations: payload.destinations,
+        filter: payload.filter,
+        transforms: payload.transforms,
+        enabled: payload.enabled.unwrap_or(true),
+        created_at: now,
+        updated_at: now,
+    };
+    validate(&state.pool, &route).await?;
+
+    match sqlx::query(
+        r#"
+        INSERT INTO t_websocket_route (id, name, source_id, destinations, filter, transforms, enabled, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&route.id)
+    .bind(&route.name)
+    .bind(&route.source_id)
+    .bind(&route.destinations)
+    .bind(&route.filter)
+    .bind(&route.transforms)
+    .bind(route.enabled)
+    .bind(route.created_at)
+    .bind(route.updated_at)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(route)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create websocket route: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 更新路由（未提供的字段保持不变）
+pub async fn update_route(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateRoute>,
+) -> Result<Json<ApiResponse<Route>>, StatusCode> {
+    let mut route = fetch_route(&state.pool, &id).await?;
+    if let Some(name) = payload.name {
+        route.name = name;
+    }
+    if let Some(destinations) = payload.destinations {
+        route.destinations = destinations;
+    }
+    if payload.filter.is_some() {
+        route.filter = payload.filter;
+    }
+    if payload.transforms.is_some() {
+        route.transforms = payload.transforms;
+    }
+    if let Some(enabled) = payload.enabled {
+        route.enabled = enabled;
+    }
+    route.updated_at = chrono::Utc::now().timestamp();
+    validate(&state.pool, &route).await?;
+
+    match sqlx::query(
+        r#"
+        UPDATE t_websocket_route
+        SET name = ?, destinations = ?, filter = ?, transforms = ?, enabled = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&route.name)
+    .bind(&route.destinations)
+    .bind(&route.filter)
+    .bind(&route.transforms)
+    .bind(route.enabled)
+    .bind(route.updated_at)
+    .bind(&route.id)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(route)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to update websocket route: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除路由
+pub async fn delete_route(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_route WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => {
+            STATS.lock().unwrap().remove(&id);
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(())))
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete websocket route: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取路由转发计数（自服务启动起）
+pub async fn get_route_stats(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<RouteStats>>, StatusCode> {
+    fetch_route(&state.pool, &id).await?;
+    let stats = stats_for(&id).lock().unwrap().clone();
+    Ok(Json(ApiResponse::ok(stats)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeadLetterQuery {
+    pub limit: Option<i64>,
+}
+
+// 获取路由的死信记录
+pub async fn list_dead_letters(
+    Path(id): Path<String>,
+    Query(query): Query<DeadLetterQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<DeadLetter>>>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    match sqlx::query_as::<_, DeadLetter>(
+        "SELECT * FROM t_websocket_dead_letter WHERE route_id = ? ORDER BY created_at DESC, rowid DESC LIMIT ?"
+    )
+    .bind(&id)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(letters) => Ok(Json(ApiResponse::ok(letters))),
+        Err(e) => {
+            tracing::error!("Failed to fetch dead letters: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 清空路由的死信记录
+pub async fn clear_dead_letters(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<u64>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_dead_letter WHERE route_id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) => Ok(Json(ApiResponse::ok(result.rows_affected()))),
+        Err(e) => {
+            tracing::error!("Failed to clear dead letters: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::migrate(&pool).await.unwrap();
+        for id in ["a", "b", "c"] {
+            sqlx::query(
+                "INSERT INTO t_websocket_config (id, name, ws_url, config_type, created_at, updated_at) VALUES (?, ?, 'ws://127.0.0.1', 'sender', 0, 0)"
+            )
+            .bind(id)
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        pool
+    }
+
+    fn route(source_id: &str, destinations: &[&str], enabled: bool) -> Route {
+        Route {
+            id: Uuid::new_v4().to_string(),
+            name: format!("{} -> {:?}", source_id, destinations),
+            source_id: source_id.to_string(),
+            destinations: serde_json::to_string(destinations).unwrap(),
+            filter: None,
+            transforms: None,
+            enabled,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    async fn insert(pool: &SqlitePool, route: &Route) {
+        sqlx::query(
+            "INSERT INTO t_websocket_route (id, name, source_id, destinations, enabled, created_at, updated_at) VALUES (?, ?, ?, ?, ?, 0, 0)"
+        )
+        .bind(&route.id)
+        .bind(&route.name)
+        .bind(&route.source_id)
+        .bind(&route.destinations)
+        .bind(route.enabled)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_multi_hop_cycles() {
+        let pool = test_pool().await;
+        insert(&pool, &route("a", &["b"], true)).await;
+        assert_eq!(validate(&pool, &route("b", &["a"], true)).await, Err(StatusCode::BAD_REQUEST));
+
+        let b_to_c = route("b", &["c"], true);
+        assert_eq!(validate(&pool, &b_to_c).await, Ok(()));
+        insert(&pool, &b_to_c).await;
+        assert_eq!(validate(&pool, &route("c", &["a"], true)).await, Err(StatusCode::BAD_REQUEST));
+        // 禁用的路由不参与转发，启用时再检查
+        assert_eq!(validate(&pool, &route("c", &["a"], false)).await, Ok(()));
+
+        // 更新已有路由时不和旧版本自身比较
+        let mut updated = b_to_c.clone();
+        updated.destinations = r#"["a"]"#.to_string();
+        assert_eq!(validate(&pool, &updated).await, Err(StatusCode::BAD_REQUEST));
+        updated.destinations = r#"["c"]"#.to_string();
+        assert_eq!(validate(&pool, &updated).await, Ok(()));
+    }
+
+    fn queue(route: &Arc<CompiledRoute>, queued_at: Instant) -> String {
+        let message_id = Uuid::new_v4().to_string();
+        PENDING.lock().unwrap().insert(message_id.clone(), PendingForward {
+            route: route.clone(),
+            destination: "b".to_string(),
+            content: "ping".to_string(),
+            queued_at,
+        });
+        message_id
+    }
+
+    // 入队时不计数，写出结果到达后再计入 forwarded 或 errors
+    #[tokio::test]
+    async fn counts_forwards_when_written() {
+        let pool = test_pool().await;
+        let stored = route("a", &["b"], true);
+        insert(&pool, &stored).await;
+        let compiled = Arc::new(compile(&stored).unwrap());
+
+        let start = Instant::now();
+        let written = queue(&compiled, start);
+        let failed = queue(&compiled, start);
+        let stale = queue(&compiled, start);
+        assert_eq!(compiled.stats.lock().unwrap().forwarded, 0);
+
+        complete(&pool, SentMessage { id: written, status: "success".to_string(), error_message: None }).await;
+        complete(&pool, SentMessage {
+            id: failed,
+            status: "failed".to_string(),
+            error_message: Some("connection reset".to_string()),
+        })
+        .await;
+        expire(&pool, start + DELIVERY_TIMEOUT).await;
+
+        let stats = compiled.stats.lock().unwrap().clone();
+        assert_eq!((stats.forwarded, stats.errors), (1, 2));
+        assert!(!PENDING.lock().unwrap().contains_key(&stale));
+
+        let mut errors: Vec<String> = sqlx::query_scalar("SELECT error FROM t_websocket_dead_letter WHERE route_id = ?")
+            .bind(&stored.id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        errors.sort();
+        assert_eq!(errors, ["connection reset", "delivery was not confirmed"]);
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
+use futures::stream::{BoxStream, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::app::AppState;
+use crate::models::{ApiResponse, Candle, CandleSettings, WebSocketConfig};
+use crate::utils::json;
+
+const DEFAULT_INTERVALS: [&str; 4] = ["1s", "1m", "5m", "1h"];
+// 周期上限（366 天）
+const MAX_INTERVAL_MS: i64 = 366 * 86_400_000;
+// 未收盘 K 线写入数据库的间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+// K 线更新广播的缓冲大小
+const UPDATE_CHANNEL_CAPACITY: usize = 4096;
+const DEFAULT_LIMIT: i64 = 500;
+const MAX_LIMIT: i64 = 5000;
+
+pub type SharedAggregator = Arc<Mutex<Aggregator>>;
+
+lazy_static::lazy_static! {
+    // 配置ID -> 正在聚合的连接
+    static ref AGGREGATORS: RwLock<HashMap<String, SharedAggregator>> = RwLock::new(HashMap::new());
+    static ref UPDATES: broadcast::Sender<Candle> = broadcast::channel(UPDATE_CHANNEL_CAPACITY).0;
+}
+
+// 解析周期："<n>s"、"<n>m"、"<n>h"、"<n>d"，返回毫秒数
+pub fn parse_interval(label: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid interval: {}", label);
+    let unit = match label.chars().last().ok_or_else(invalid)? {
+        's' => 1_000,
+        'm' => 60_000,
+        'h' => 3_600_000,
+        'd' => 86_400_000,
+        _ => return Err(invalid()),
+    };
+    let count: i64 = label[..label.len() - 1].parse().map_err(|_| invalid())?;
+    if count <= 0 {
+        return Err(invalid());
+    }
+    match count.checked_mul(unit) {
+        Some(ms) if ms <= MAX_INTERVAL_MS => Ok(ms),
+        _ => Err(invalid()),
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// 成交时间转为毫秒：按数量级区分秒、毫秒、微秒，字符串也可以是 RFC 3339
+fn timestamp_ms(value: &Value) -> Option<i64> {
+    let epoch = match (number(value), value) {
+        (Some(epoch), _) => epoch,
+        (None, Value::String(s)) => return chrono::DateTime::parse_from_rfc3339(s.trim()).ok().map(|t| t.timestamp_millis()),
+        _ => return None,
+    };
+    let ms = if epoch >= 1e14 {
+        epoch / 1_000.0
+    } else if epoch >= 1e11 {
+        epoch
+    } else {
+        epoch * 1_000.0
+    };
+    Some(ms as i64)
+}
+
+struct Trade {
+    symbol: String,
+    price: f64,
+    size: f64,
+    time: i64,
+}
+
+fn apply_trade(candle: &mut Candle, trade: &Trade, now: i64) {
+    candle.high = candle.high.max(trade.price);
+    candle.low = candle.low.min(trade.price);
+    candle.close = trade.price;
+    candle.volume += trade.size;
+    candle.quote_volume += trade.price * trade.size;
+    candle.trades += 1;
+    candle.updated_at = now;
+}
+
+// 按配置把成交聚合为各周期的 K 线：当前 K 线保存在内存中，收盘或定时写入数据库
+pub struct Aggregator {
+    config_id: String,
+    settings: CandleSettings,
+    intervals: Vec<(String, i64)>,
+    // (交易对, 周期) -> 当前 K 线
+    current: HashMap<(String, String), Candle>,
+    dirty: HashSet<(String, String)>,
+    last_flush: Instant,
+}
+
+impl Aggregator {
+    // 按配置中的 candles 创建，未配置时返回 None
+    pub fn from_config(config: &WebSocketConfig) -> Result<Option<Self>, String> {
+        match config.candles.as_deref() {
+            Some(raw) if !raw.trim().is_empty() => {
+                let settings: CandleSettings =
+                    serde_json::from_str(raw).map_err(|e| format!("invalid candles: {}", e))?;
+                Self::new(&config.id, settings).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn new(config_id: &str, settings: CandleSettings) -> Result<Self, String> {
+        if settings.price.trim().is_empty() || settings.size.trim().is_empty() {
+            return Err("candles need price and size fields".to_string());
+        }
+        let labels: Vec<String> = match &settings.intervals {
+            Some(labels) if !labels.is_empty() => labels.clone(),
+            Some(_) => return Err("intervals must not be empty".to_string()),
+            None => DEFAULT_INTERVALS.iter().map(|label| label.to_string()).collect(),
+        };
+        let intervals = labels
+            .into_iter()
+            .map(|label| parse_interval(&label).map(|ms| (label, ms)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            config_id: config_id.to_string(),
+            settings,
+            intervals,
+            current: HashMap::new(),
+            dirty: HashSet::new(),
+            last_flush: Instant::now(),
+        })
+    }
+
+    // 从一条消息中取出成交，缺少价格或数量的消息（订阅应答等）跳过
+    fn trades(&self, content: &str) -> Vec<Trade> {
+        let Ok(value) = serde_json::from_str::<Value>(content) else {
+            return Vec::new();
+        };
+        let items: Vec<&Value> = match self.settings.items.as_deref() {
+            Some(path) => json::lookup(&value, path)
+                .and_then(Value::as_array)
+                .map(|items| items.iter().collect())
+                .unwrap_or_default(),
+            None => vec![&value],
+        };
+        items.into_iter().filter_map(|item| self.trade(item)).collect()
+    }
+
+    fn trade(&self, item: &Value) -> Option<Trade> {
+        let price = json::lookup(item, &self.settings.price).and_then(number)?;
+        let size = json::lookup(item, &self.settings.size).and_then(number)?;
+        if !price.is_finite() || !size.is_finite() || price <= 0.0 || size < 0.0 {
+            return None;
+        }
+        let time = match self.settings.time.as_deref() {
+            Some(path) => json::lookup(item, path).and_then(timestamp_ms)?,
+            None => chrono::Utc::now().timestamp_millis(),
+        };
+        let symbol = self
+            .settings
+            .symbol
+            .as_deref()
+            .and_then(|path| json::lookup(item, path))
+            .map(json::value_to_string)
+            .unwrap_or_default();
+        Some(Trade { symbol, price, size, time })
+    }
+
+    async fn add(&mut self, pool: &SqlitePool, trade: &Trade) {
+        let now = chrono::Utc::now().timestamp_millis();
+        for (label, length) in &self.intervals {
+            let open_time = trade.time - trade.time.rem_euclid(*length);
+            let key = (trade.symbol.clone(), label.clone());
+            match self.current.get_mut(&key) {
+                Some(candle) if candle.open_time == open_time => apply_trade(candle, trade, now),
+                // 晚于当前 K 线到达的旧成交不再计入
+                Some(candle) if candle.open_time > open_time => continue,
+                _ => {
+                    // 上一根 K 线收盘
+                    if let Some(previous) = self.current.remove(&key) {
+                        save(pool, &previous).await;
+                        self.dirty.remove(&key);
+                    }
+                    // 重新连接时接上同一时间段内已保存的数据
+                    let mut candle = load(pool, &self.config_id, &trade.symbol, label, open_time)
+                        .await
+                        .unwrap_or_else(|| Candle {
+                            config_id: self.config_id.clone(),
+                            symbol: trade.symbol.clone(),
+                            interval: label.clone(),
+                            open_time,
+                            close_time: open_time + length - 1,
+                            open: trade.price,
+                            high: trade.price,
+                            low: trade.price,
+                            close: trade.price,
+                            volume: 0.0,
+                            quote_volume: 0.0,
+                            trades: 0,
+                            updated_at: now,
+                        });
+                    apply_trade(&mut candle, trade, now);
+                    self.current.insert(key.clone(), candle);
+                }
+            }
+            if let Some(candle) = self.current.get(&key) {
+                let _ = UPDATES.send(candle.clone());
+            }
+            self.dirty.insert(key);
+        }
+    }
+
+    // 把有变化的当前 K 线写入数据库
+    pub async fn flush(&mut self, pool: &SqlitePool) {
+        for key in std::mem::take(&mut self.dirty) {
+            if let Some(candle) = self.current.get(&key) {
+                save(pool, candle).await;
+            }
+        }
+        self.last_flush = Instant::now();
+    }
+}
+
+async fn load(pool: &SqlitePool, config_id: &str, symbol: &str, interval: &str, open_time: i64) -> Option<Candle> {
+    sqlx::query_as::<_, Candle>(
+        "SELECT * FROM t_websocket_candle WHERE config_id = ? AND symbol = ? AND interval = ? AND open_time = ?"
+    )
+    .bind(config_id)
+    .bind(symbol)
+    .bind(interval)
+    .bind(open_time)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!("Failed to fetch candle: {}", e);
+        None
+    })
+}
+
+async fn save(pool: &SqlitePool, candle: &Candle) {
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_candle
+        (config_id, symbol, interval, open_time, close_time, open, high, low, close, volume, quote_volume, trades, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(config_id, symbol, interval, open_time) DO UPDATE SET
+            open = excluded.open, high = excluded.high, low = excluded.low, close = excluded.close,
+            volume = excluded.volume, quote_volume = excluded.quote_volume, trades = excluded.trades,
+            updated_at = excluded.updated_at
+        "#
+    )
+    .bind(&candle.config_id)
+    .bind(&candle.symbol)
+    .bind(&candle.interval)
+    .bind(candle.open_time)
+    .bind(candle.close_time)
+    .bind(candle.open)
+    .bind(candle.high)
+    .bind(candle.low)
+    .bind(candle.close)
+    .bind(candle.volume)
+    .bind(candle.quote_volume)
+    .bind(candle.trades)
+    .bind(candle.updated_at)
+    .execute(pool)
+    .await
+    {
+        tracing::error!("Failed to save candle: {}", e);
+    }
+}
+
+// 连接建立时登记聚合器，供查询未收盘的 K 线
+pub async fn register(aggregator: Aggregator) -> SharedAggregator {
+    let shared = Arc::new(Mutex::new(aggregator));
+    let config_id = shared.lock().await.config_id.clone();
+    AGGREGATORS.write().await.insert(config_id, shared.clone());
+    shared
+}
+
+// 处理一条接收消息
+pub async fn handle(aggregator: &SharedAggregator, pool: &SqlitePool, content: &str) {
+    let mut aggregator = aggregator.lock().await;
+    for trade in aggregator.trades(content) {
+        aggregator.add(pool, &trade).await;
+    }
+    if aggregator.last_flush.elapsed() >= FLUSH_INTERVAL {
+        aggregator.flush(pool).await;
+    }
+}
+
+// 连接断开：保存未收盘的 K 线
+pub async fn close(pool: &SqlitePool, config_id: &str) {
+    let aggregator = AGGREGATORS.write().await.remove(config_id);
+    if let Some(aggregator) = aggregator {
+        aggregator.lock().await.flush(pool).await;
+    }
+}
+
+async fn current_candle(config_id: &str, symbol: &str, interval: &str) -> Option<Candle> {
+    let aggregator = AGGREGATORS.read().await.get(config_id).cloned()?;
+    let aggregator = aggregator.lock().await;
+    aggregator.current.get(&(symbol.to_string(), interval.to_string())).cloned()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CandleQuery {
+    pub config_id: String,
+    pub symbol: Option<String>,
+    pub interval: Option<String>,
+    pub start: Option<i64>, // open_time >= start (ms)
+    pub end: Option<i64>, // open_time <= end (ms)
+    pub limit: Option<i64>,
+}
+
+// 查询 K 线（按开盘时间升序，范围内超过 limit 时返回最新的 limit 根），包含尚未写入数据库的当前 K 线
+pub async fn list_candles(
+    Query(query): Query<CandleQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Candle>>>, StatusCode> {
+    let symbol = query.symbol.unwrap_or_default();
+    let interval = query.interval.unwrap_or_else(|| "1m".to_string());
+    if let Err(e) = parse_interval(&interval) {
+        tracing::warn!("Invalid candle query: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut candles = match sqlx::query_as::<_, Candle>(
+        r#"
+        SELECT * FROM t_websocket_candle
+        WHERE config_id = ? AND symbol = ? AND interval = ?
+          AND (? IS NULL OR open_time >= ?) AND (? IS NULL OR open_time <= ?)
+        ORDER BY open_time DESC LIMIT ?
+        "#
+    )
+    .bind(&query.config_id)
+    .bind(&symbol)
+    .bind(&interval)
+    .bind(query.start)
+    .bind(query.start)
+    .bind(query.end)
+    .bind(query.end)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(candles) => candles,
+        Err(e) => {
+            tracing::error!("Failed to fetch candles: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    candles.reverse();
+
+    if let Some(current) = current_candle(&query.config_id, &symbol, &interval).await {
+        let in_range = query.start.is_none_or(|start| current.open_time >= start)
+            && query.end.is_none_or(|end| current.open_time <= end);
+        if in_range {
+            match candles.last_mut() {
+                Some(last) if last.open_time == current.open_time => *last = current,
+                Some(last) if last.open_time > current.open_time => {}
+                _ => {
+                    candles.push(current);
+                    if candles.len() as i64 > limit {
+                        candles.remove(0);
+                    }
+                }
+            }
+        }
+    }
+    Ok(Json(ApiResponse::ok(candles)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CandleStreamQuery {
+    pub config_id: Option<String>,
+    pub symbol: Option<String>,
+    pub interval: Option<String>,
+}
+
+impl CandleStreamQuery {
+    fn matches(&self, candle: &Candle) -> bool {
+        self.config_id.as_ref().is_none_or(|id| *id == candle.config_id)
+            && self.symbol.as_ref().is_none_or(|symbol| *symbol == candle.symbol)
+            && self.interval.as_ref().is_none_or(|interval| *interval == candle.interval)
+    }
+}
+
+// 以 SSE 推送 K 线更新：每笔成交后推送所在的当前 K 线
+pub async fn stream_candles(
+    Query(query): Query<CandleStreamQuery>,
+) -> Sse<BoxStream<'static, Result<Event, Infallible>>> {
+    let receiver = UPDATES.subscribe();
+    let stream = futures::stream::unfold((receiver, query), |(mut receiver, query)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(candle) if query.matches(&candle) => {
+                    let event = Event::default().event("candle").data(serde_json::to_string(&candle).unwrap_or_default());
+                    return Some((Ok(event), (receiver, query)));
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream.boxed()).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,483 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use futures::stream::StreamExt;
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{
+    ApiResponse, BulkConnectionRequest, BulkConnectionResult, BulkItemResult, ConnectionGroup,
+    ConnectionGroupDetail, ConnectionGroupStatus, GroupMemberStatus, NewConnectionGroup,
+    UpdateConnectionGroup, WebSocketConfig,
+};
+use crate::service::websocket_actions::{spawn_connect, stop_config};
+use crate::service::websocket_manager::WEBSOCKET_MANAGER;
+
+const DEFAULT_CONCURRENCY: usize = 5;
+const MAX_CONCURRENCY: usize = 50;
+const DEFAULT_STAGGER_MS: u64 = 200;
+// 单个连接等待建立的最长时间，超时返回 pending（连接仍在后台进行）
+const START_WAIT: Duration = Duration::from_secs(10);
+const START_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operation {
+    Start,
+    Stop,
+    Restart,
+}
+
+fn item(config_id: &str, name: Option<&str>, result: &str, error: Option<String>) -> BulkItemResult {
+    BulkItemResult {
+        config_id: config_id.to_string(),
+        name: name.map(str::to_string),
+        result: result.to_string(),
+        error,
+    }
+}
+
+// 控制连接节奏：相邻两次建立连接至少间隔 gap
+async fn pace(last: &Mutex<Option<Instant>>, gap: Duration) {
+    let mut last = last.lock().await;
+    if let Some(previous) = *last {
+        tokio::time::sleep_until(previous + gap).await;
+    }
+    *last = Some(Instant::now());
+}
+
+// 启动一个连接，等待连接建立、失败或超时
+async fn start_one(pool: &SqlitePool, config: WebSocketConfig) -> BulkItemResult {
+    let (id, name) = (config.id.clone(), config.name.clone());
+    if let Some(status) = WEBSOCKET_MANAGER.get_connection_status(&id).await {
+        if status.is_connected {
+            return item(&id, Some(&name), "already_connected", None);
+        }
+    }
+
+    let handle = spawn_connect(pool.clone(), config);
+    let deadline = Instant::now() + START_WAIT;
+    loop {
+        if handle.is_finished() {
+            let error = match handle.await {
+                Ok(Err(e)) => e,
+                Ok(Ok(())) => "connection closed".to_string(),
+                Err(e) => e.to_string(),
+            };
+            return item(&id, Some(&name), "failed", Some(error));
+        }
+        if let Some(status) = WEBSOCKET_MANAGER.get_connection_status(&id).await {
+            if status.is_connected {
+                return item(&id, Some(&name), "connected", None);
+            }
+        }
+        if Instant::now() >= deadline {
+            return item(&id, Some(&name), "pending", None);
+        }
+        tokio::time::sleep(START_POLL).await;
+    }
+}
+
+// 对一组配置执行启动、停止或重启：按原顺序返回每项结果，建立连接时限制并发并错开
+async fn run_bulk(
+    pool: &SqlitePool,
+    config_ids: Vec<String>,
+    operation: Operation,
+    options: &BulkConnectionRequest,
+) -> BulkConnectionResult {
+    let concurrency = options.concurrency.unwrap_or(DEFAULT_CONCURRENCY).clamp(1, MAX_CONCURRENCY);
+    let gap = Duration::from_millis(options.stagger_ms.unwrap_or(DEFAULT_STAGGER_MS));
+    let last_start = Arc::new(Mutex::new(None));
+
+    let mut seen = HashSet::new();
+    let config_ids: Vec<String> = config_ids.into_iter().filter(|id| seen.insert(id.clone())).collect();
+
+    let items: Vec<BulkItemResult> = futures::stream::iter(config_ids)
+        .map(|config_id| {
+            let last_start = last_start.clone();
+            async move {
+                let config = match sqlx::query_as::<_, WebSocketConfig>("SELECT * FROM t_websocket_config WHERE id = ?")
+                    .bind(&config_id)
+                    .fetch_one(pool)
+                    .await
+                {
+                    Ok(config) => config,
+                    Err(sqlx::Error::RowNotFound) => return item(&config_id, None, "not_found", None),
+                    Err(e) => {
+                        tracing::error!("Failed to fetch websocket config: {}", e);
+                        return item(&config_id, None, "failed", Some(e.to_string()));
+                    }
+                };
+                if operation != Operation::Start {
+                    stop_config(pool, &config_id).await;
+                }
+                if operation == Operation::Stop {
+                    return item(&config_id, Some(&config.name), "stopped", None);
+                }
+                pace(&last_start, gap).await;
+                start_one(pool, config).await
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let succeeded = items
+        .iter()
+        .filter(|item| matches!(item.result.as_str(), "connected" | "already_connected" | "pending" | "stopped"))
+        .count();
+    BulkConnectionResult {
+        total: items.len(),
+        succeeded,
+        failed: items.len() - succeeded,
+        items,
+    }
+}
+
+async fn fetch_group(pool: &SqlitePool, id: &str) -> Result<ConnectionGroup, StatusCode> {
+    match sqlx::query_as::<_, ConnectionGroup>("SELECT * FROM t_websocket_group WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(group) => Ok(group),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch connection group: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 分组成员（已删除的配置不计入）
+async fn fetch_members(pool: &SqlitePool, group_id: &str) -> Result<Vec<WebSocketConfig>, StatusCode> {
+    match sqlx::query_as::<_, WebSocketConfig>(
+        r#"
+        SELECT c.* FROM t_websocket_group_member m
+        JOIN t_websocket_config c ON c.id = m.config_id
+        WHERE m.group_id = ?
+        ORDER BY m.created_at, c.name
+        "#
+    )
+    .bind(group_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(configs) => Ok(configs),
+        Err(e) => {
+            tracing::error!("Failed to fetch connection group members: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn detail(pool: &SqlitePool, group: ConnectionGroup) -> Result<ConnectionGroupDetail, StatusCode> {
+    let config_ids = fetch_members(pool, &group.id).await?.into_iter().map(|config| config.id).collect();
+    Ok(ConnectionGroupDetail { group, config_ids })
+}
+
+// 替换分组成员，配置不存在时返回 400
+async fn replace_members(pool: &SqlitePool, group_id: &str, config_ids: &[String]) -> Result<(), StatusCode> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        tracing::error!("Failed to begin transaction: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let result: Result<(), sqlx::Error> = async {
+        sqlx::query("DELETE FROM t_websocket_group_member WHERE group_id = ?")
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await?;
+        let now = chrono::Utc::now().timestamp();
+        for config_id in config_ids {
+            sqlx::query("SELECT id FROM t_websocket_config WHERE id = ?")
+                .bind(config_id)
+                .fetch_one(&mut *tx)
+                .await?;
+            sqlx::query("INSERT OR IGNORE INTO t_websocket_group_member (group_id, config_id, created_at) VALUES (?, ?, ?)")
+                .bind(group_id)
+                .bind(config_id)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Ok(())
+    }
+    .await;
+    match result {
+        Ok(()) => tx.commit().await.map_err(|e| {
+            tracing::error!("Failed to save connection group members: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }),
+        Err(sqlx::Error::RowNotFound) => {
+            tracing::warn!("Connection group references an unknown websocket config");
+            Err(StatusCode::BAD_REQUEST)
+        }
+        Err(e) => {
+            tracing::error!("Failed to save connection group members: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取分组列表
+pub async fn list_groups(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<ConnectionGroupDetail>>>, StatusCode> {
+    let groups = match sqlx::query_as::<_, ConnectionGroup>("SELECT * FROM t_websocket_group ORDER BY name")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(groups) => groups,
+        Err(e) => {
+            tracing::error!("Failed to fetch connection groups: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let mut details = Vec::with_capacity(groups.len());
+    for group in groups {
+        details.push(detail(&state.pool, group).await?);
+    }
+    Ok(Json(ApiResponse::ok(details)))
+}
+
+// 获取分组
+pub async fn get_group(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<ConnectionGroupDetail>>, StatusCode> {
+    let group = fetch_group(&state.pool, &id).await?;
+    Ok(Json(ApiResponse::ok(detail(&state.pool, group).await?)))
+}
+
+// 创建分组
+pub async fn create_group(
+    State(state): State<AppState>,
+    Json(payload): Json<NewConnectionGroup>,
+) -> Result<Json<ApiResponse<ConnectionGroupDetail>>, StatusCode> {
+    if payload.name.trim().is_empty() {
+        tracing::warn!("Connection group name must not be empty");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let now = chrono::Utc::now().timestamp();
+    let group = ConnectionGroup {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name,
+        description: payload.description,
+        created_at: now,
+        updated_at: now,
+    };
+
+    match sqlx::query("INSERT INTO t_websocket_group (id, name, description, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(&group.id)
+        .bind(&group.name)
+        .bind(&group.description)
+        .bind(group.created_at)
+        .bind(group.updated_at)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(_) => {}
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Err(StatusCode::CONFLICT),
+        Err(e) => {
+            tracing::error!("Failed to create connection group: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    if let Some(config_ids) = &payload.config_ids {
+        if let Err(status) = replace_members(&state.pool, &group.id, config_ids).await {
+            let _ = sqlx::query("DELETE FROM t_websocket_group WHERE id = ?")
+                .bind(&group.id)
+                .execute(&state.pool)
+                .await;
+            return Err(status);
+        }
+    }
+    Ok(Json(ApiResponse::ok(detail(&state.pool, group).await?)))
+}
+
+// 更新分组，传入 config_ids 时替换全部成员
+pub async fn update_group(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateConnectionGroup>,
+) -> Result<Json<ApiResponse<ConnectionGroupDetail>>, StatusCode> {
+    let mut group = fetch_group(&state.pool, &id).await?;
+    if let Some(name) = payload.name {
+        if name.trim().is_empty() {
+            tracing::warn!("Connection group name must not be empty");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        group.name = name;
+    }
+    if let Some(description) = payload.description {
+        group.description = Some(description);
+    }
+    group.updated_at = chrono::Utc::now().timestamp();
+
+    match sqlx::query("UPDATE t_websocket_group SET name = ?, description = ?, updated_at = ? WHERE id = ?")
+        .bind(&group.name)
+        .bind(&group.description)
+        .bind(group.updated_at)
+        .bind(&group.id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(_) => {}
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Err(StatusCode::CONFLICT),
+        Err(e) => {
+            tracing::error!("Failed to update connection group: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+    if let Some(config_ids) = &payload.config_ids {
+        replace_members(&state.pool, &group.id, config_ids).await?;
+    }
+    Ok(Json(ApiResponse::ok(detail(&state.pool, group).await?)))
+}
+
+// 删除分组（不影响成员连接）
+pub async fn delete_group(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_group WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => {
+            let _ = sqlx::query("DELETE FROM t_websocket_group_member WHERE group_id = ?")
+                .bind(&id)
+                .execute(&state.pool)
+                .await;
+            Ok(Json(ApiResponse::ok(())))
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete connection group: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 分组状态：按连接管理器汇总已连接、重连中、失败和未启动的数量
+pub async fn get_group_status(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<ConnectionGroupStatus>>, StatusCode> {
+    let group = fetch_group(&state.pool, &id).await?;
+    let configs = fetch_members(&state.pool, &id).await?;
+
+    let mut members = Vec::with_capacity(configs.len());
+    for config in configs {
+        let info = WEBSOCKET_MANAGER.get_connection_status(&config.id).await;
+        let state = if info.as_ref().is_some_and(|info| info.is_connected) {
+            "connected"
+        } else if WEBSOCKET_MANAGER.is_reconnecting(&config.id).await {
+            "reconnecting"
+        } else if config.status == "error" {
+            "failed"
+        } else {
+            "stopped"
+        };
+        members.push(GroupMemberStatus {
+            config_id: config.id,
+            name: config.name,
+            state: state.to_string(),
+            last_error: info.and_then(|info| info.last_error),
+        });
+    }
+    let count = |state: &str| members.iter().filter(|member| member.state == state).count();
+
+    Ok(Json(ApiResponse::ok(ConnectionGroupStatus {
+        group_id: group.id,
+        name: group.name,
+        total: members.len(),
+        connected: count("connected"),
+        reconnecting: count("reconnecting"),
+        failed: count("failed"),
+        stopped: count("stopped"),
+        members,
+    })))
+}
+
+async fn run_group(
+    pool: &SqlitePool,
+    id: &str,
+    operation: Operation,
+    payload: Option<Json<BulkConnectionRequest>>,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    fetch_group(pool, id).await?;
+    let config_ids = fetch_members(pool, id).await?.into_iter().map(|config| config.id).collect();
+    let options = payload.map(|Json(options)| options).unwrap_or_default();
+    Ok(Json(ApiResponse::ok(run_bulk(pool, config_ids, operation, &options).await)))
+}
+
+async fn run_list(
+    pool: &SqlitePool,
+    operation: Operation,
+    options: BulkConnectionRequest,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    let Some(config_ids) = options.config_ids.clone().filter(|ids| !ids.is_empty()) else {
+        tracing::warn!("Bulk connection request without config_ids");
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    Ok(Json(ApiResponse::ok(run_bulk(pool, config_ids, operation, &options).await)))
+}
+
+// 启动分组内全部连接
+pub async fn start_group(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    payload: Option<Json<BulkConnectionRequest>>,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    run_group(&state.pool, &id, Operation::Start, payload).await
+}
+
+// 停止分组内全部连接
+pub async fn stop_group(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    payload: Option<Json<BulkConnectionRequest>>,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    run_group(&state.pool, &id, Operation::Stop, payload).await
+}
+
+// 重启分组内全部连接
+pub async fn restart_group(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    payload: Option<Json<BulkConnectionRequest>>,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    run_group(&state.pool, &id, Operation::Restart, payload).await
+}
+
+// 批量启动指定的连接
+pub async fn bulk_start(
+    State(state): State<AppState>,
+    Json(payload): Json<BulkConnectionRequest>,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    run_list(&state.pool, Operation::Start, payload).await
+}
+
+// 批量停止指定的连接
+pub async fn bulk_stop(
+    State(state): State<AppState>,
+    Json(payload): Json<BulkConnectionRequest>,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    run_list(&state.pool, Operation::Stop, payload).await
+}
+
+// 批量重启指定的连接
+pub async fn bulk_restart(
+    State(state): State<AppState>,
+    Json(payload): Json<BulkConnectionRequest>,
+) -> Result<Json<ApiResponse<BulkConnectionResult>>, StatusCode> {
+    run_list(&state.pool, Operation::Restart, payload).await
+}
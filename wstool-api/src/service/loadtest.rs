@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
+use futures::stream::BoxStream;
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tokio::sync::{watch, RwLock};
+use tokio::time::{Instant, Interval};
+use tokio_tungstenite::tungstenite::protocol::Message;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{
+    ApiResponse, LatencyStats, LoadTest, LoadTestReport, LoadTestRequest, SendMessageRequest, WebSocketConfig,
+};
+use crate::service::mock_server::render;
+use crate::service::websocket_manager::{open_connection, transient_config, OpenedConnection};
+use crate::utils::json;
+
+const MAX_CONNECTIONS: u32 = 5_000;
+const MAX_DURATION_SECS: u64 = 3_600;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+// 爬坡和发送速率的范围（每秒）
+const MIN_RATE: f64 = 0.001;
+const MAX_RATE: f64 = 1_000_000.0;
+// 每个连接最多跟踪的未回复消息数
+const MAX_PENDING: usize = 10_000;
+// 超过该时间仍未回复的消息不再等待
+const PENDING_TIMEOUT: Duration = Duration::from_secs(60);
+// 延迟直方图：从 1µs 起按 1% 的相对精度对数分桶，覆盖到一小时以上
+const HISTOGRAM_MIN_MS: f64 = 0.001;
+const HISTOGRAM_GROWTH: f64 = 1.01;
+const HISTOGRAM_BUCKETS: usize = 2_300;
+
+// 运行中的压测任务
+struct Job {
+    id: String,
+    started: Instant,
+    stopped: AtomicBool,
+    stop: watch::Sender<bool>,
+    progress: watch::Sender<LoadTestReport>,
+    stats: Stats,
+}
+
+#[derive(Default)]
+struct Stats {
+    opened: AtomicU64,
+    active: AtomicU64,
+    connect_failures: AtomicU64,
+    sent: AtomicU64,
+    received: AtomicU64,
+    errors: AtomicU64,
+    closes: AtomicU64,
+    connect_ms: Mutex<Histogram>,
+    rtt_ms: Mutex<Histogram>,
+}
+
+// 固定内存的延迟分布，记录和汇总都不随样本数增长
+struct Histogram {
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; HISTOGRAM_BUCKETS],
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: 0.0,
+        }
+    }
+}
+
+impl Histogram {
+    fn bucket(value: f64) -> usize {
+        if value <= HISTOGRAM_MIN_MS {
+            return 0;
+        }
+        (((value / HISTOGRAM_MIN_MS).ln() / HISTOGRAM_GROWTH.ln()) as usize).min(HISTOGRAM_BUCKETS - 1)
+    }
+
+    fn record(&mut self, value: f64) {
+        self.buckets[Self::bucket(value)] += 1;
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    // 最近秩法取分位数，返回所在桶的中点（限制在实际最小、最大值之间）
+    fn summarize(&self) -> LatencyStats {
+        if self.count == 0 {
+            return LatencyStats::default();
+        }
+        let percentile = |p: f64| {
+            let rank = (((p / 100.0) * self.count as f64).ceil() as u64).max(1);
+            let mut seen = 0;
+            let index = self
+                .buckets
+                .iter()
+                .position(|&count| {
+                    seen += count;
+                    seen >= rank
+                })
+                .unwrap_or(HISTOGRAM_BUCKETS - 1);
+            (HISTOGRAM_MIN_MS * HISTOGRAM_GROWTH.powf(index as f64 + 0.5)).clamp(self.min, self.max)
+        };
+        LatencyStats {
+            count: self.count,
+            min: self.min,
+            mean: self.sum / self.count as f64,
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p99: percentile(99.0),
+            max: self.max,
+        }
+    }
+}
+
+impl Job {
+    fn snapshot(&self, status: &str) -> LoadTestReport {
+        let stats = &self.stats;
+        LoadTestReport {
+            id: self.id.clone(),
+            status: status.to_string(),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            connections_opened: stats.opened.load(Ordering::Relaxed),
+            connections_active: stats.active.load(Ordering::Relaxed),
+            connect_failures: stats.connect_failures.load(Ordering::Relaxed),
+            messages_sent: stats.sent.load(Ordering::Relaxed),
+            messages_received: stats.received.load(Ordering::Relaxed),
+            errors: stats.errors.load(Ordering::Relaxed),
+            closes: stats.closes.load(Ordering::Relaxed),
+            connect_ms: stats.connect_ms.lock().unwrap().summarize(),
+            rtt_ms: stats.rtt_ms.lock().unwrap().summarize(),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LOAD_TESTS: RwLock<HashMap<String, Arc<Job>>> = RwLock::new(HashMap::new());
+}
+
+fn validate(request: &LoadTestRequest) -> Result<(), String> {
+    if request.connections == 0 || request.connections > MAX_CONNECTIONS {
+        return Err(format!("connections must be between 1 and {}", MAX_CONNECTIONS));
+    }
+    if request.duration_secs == 0 || request.duration_secs > MAX_DURATION_SECS {
+        return Err(format!("duration_secs must be between 1 and {}", MAX_DURATION_SECS));
+    }
+    for (name, rate) in [("ramp_up_per_sec", request.ramp_up_per_sec), ("messages_per_sec", request.messages_per_sec)] {
+        if rate.is_some_and(|r| !(MIN_RATE..=MAX_RATE).contains(&r)) {
+            return Err(format!("{} must be between {} and {}", name, MIN_RATE, MAX_RATE));
+        }
+    }
+    if request.messages_per_sec.is_some() && request.message.is_none() {
+        return Err("messages_per_sec requires a message template".to_string());
+    }
+    Ok(())
+}
+
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+// 从消息中提取关联ID
+fn correlation_key(content: &str, path: Option<&str>) -> Option<String> {
+    let value = serde_json::from_str::<Value>(content).ok()?;
+    json::lookup(&value, path?).map(json::value_to_string)
+}
+
+// 待回复的消息（关联ID -> 发送时间），数量有上限
+struct Pending {
+    sent_at: HashMap<String, Instant>,
+    swept_at: Instant,
+}
+
+impl Pending {
+    fn new(now: Instant) -> Self {
+        Self { sent_at: HashMap::new(), swept_at: now }
+    }
+
+    // 达到上限时丢弃超时未回复的（每秒至多清理一次），仍满则不再跟踪
+    fn track(&mut self, key: String, now: Instant) {
+        if self.sent_at.len() >= MAX_PENDING && now.duration_since(self.swept_at) >= PROGRESS_INTERVAL {
+            self.sent_at.retain(|_, sent_at| now.duration_since(*sent_at) < PENDING_TIMEOUT);
+            self.swept_at = now;
+        }
+        if self.sent_at.len() < MAX_PENDING {
+            self.sent_at.insert(key, now);
+        }
+    }
+
+    fn take(&mut self, key: &str) -> Option<Instant> {
+        self.sent_at.remove(key)
+    }
+}
+
+// 单个压测连接：按速率发送模板消息，并按关联ID统计往返延迟
+async fn run_connection(
+    job: Arc<Job>,
+    config: Arc<WebSocketConfig>,
+    params: Arc<LoadTestRequest>,
+    index: u32,
+    deadline: Instant,
+) {
+    let mut stop = job.stop.subscribe();
+    let stats = &job.stats;
+
+    let connect_started = Instant::now();
+    let opened = tokio::select! {
+        result = open_connection(&config) => result,
+        _ = stop.changed() => return,
+        _ = tokio::time::sleep_until(deadline) => return,
+    };
+    let OpenedConnection { stream, mut adapter, opening } = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            tracing::debug!("Load test {} connection {} failed: {}", job.id, index, e);
+            stats.connect_failures.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+    };
+    stats.connect_ms.lock().unwrap().record(connect_started.elapsed().as_secs_f64() * 1000.0);
+    stats.opened.fetch_add(1, Ordering::Relaxed);
+    stats.active.fetch_add(1, Ordering::Relaxed);
+
+    let (mut sink, mut source) = stream.split();
+    let mut healthy = true;
+    for frame in opening {
+        if sink.send(frame).await.is_err() {
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+            healthy = false;
+            break;
+        }
+    }
+
+    let session = index.to_string();
+    let mut seq = 0u64;
+    let mut pending = Pending::new(Instant::now());
+    let mut sender = params
+        .messages_per_sec
+        .map(|rate| tokio::time::interval(Duration::from_secs_f64(1.0 / rate)));
+    let mut heartbeat = adapter.heartbeat_interval().map(tokio::time::interval);
+
+    while healthy {
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => break,
+            _ = stop.changed() => break,
+            _ = tick(&mut sender) => {
+                seq += 1;
+                let message = render(params.message.as_deref().unwrap_or_default(), seq, &session, None);
+                let key = correlation_key(&message, params.correlation_path.as_deref());
+                let request = SendMessageRequest {
+                    config_id: config.id.clone(),
+                    message,
+                    custom_headers: None,
+                    topic: params.topic.clone(),
+                    event: params.event.clone(),
+                    qos: None,
+                    retain: None,
+                };
+                let frames = match adapter.encode_outgoing(&request) {
+                    Ok(frames) => frames,
+                    Err(_) => {
+                        stats.errors.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                };
+                for frame in frames {
+                    if sink.send(frame).await.is_err() {
+                        stats.errors.fetch_add(1, Ordering::Relaxed);
+                        healthy = false;
+                        break;
+                    }
+                }
+                if !healthy {
+                    break;
+                }
+                stats.sent.fetch_add(1, Ordering::Relaxed);
+                if let Some(key) = key {
+                    pending.track(key, Instant::now());
+                }
+            }
+            _ = tick(&mut heartbeat) => {
+                for frame in adapter.heartbeat() {
+                    let _ = sink.send(frame).await;
+                }
+            }
+            message = source.next() => match message {
+                None | Some(Ok(Message::Close(_))) => {
+                    stats.closes.fetch_add(1, Ordering::Relaxed);
+                    healthy = false;
+                }
+                Some(Err(_)) => {
+                    stats.errors.fetch_add(1, Ordering::Relaxed);
+                    healthy = false;
+                }
+                Some(Ok(message)) => match adapter.decode_incoming(message) {
+                    Ok(decoded) => {
+                        for reply in decoded.replies {
+                            let _ = sink.send(reply).await;
+                        }
+                        for inbound in decoded.messages {
+                            stats.received.fetch_add(1, Ordering::Relaxed);
+                            let sent_at = correlation_key(&inbound.content, params.correlation_path.as_deref())
+                                .and_then(|key| pending.take(&key));
+                            if let Some(sent_at) = sent_at {
+                                stats.rtt_ms.lock().unwrap().record(sent_at.elapsed().as_secs_f64() * 1000.0);
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        stats.errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                },
+            },
+        }
+    }
+
+    let _ = sink.close().await;
+    stats.active.fetch_sub(1, Ordering::Relaxed);
+}
+
+// 压测主流程：按爬坡速率建立连接，定时推送进度，结束后保存报告
+async fn run_job(pool: SqlitePool, job: Arc<Job>, config: WebSocketConfig, params: LoadTestRequest) {
+    let config = Arc::new(config);
+    let params = Arc::new(params);
+    let deadline = job.started + Duration::from_secs(params.duration_secs);
+    let mut stop = job.stop.subscribe();
+
+    let reporter = {
+        let job = job.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(PROGRESS_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let _ = job.progress.send(job.snapshot("running"));
+            }
+        })
+    };
+
+    let mut workers = Vec::with_capacity(params.connections as usize);
+    for index in 0..params.connections {
+        if let Some(rate) = params.ramp_up_per_sec {
+            let start_at = job.started + Duration::from_secs_f64(index as f64 / rate);
+            tokio::select! {
+                _ = tokio::time::sleep_until(start_at) => {}
+                _ = tokio::time::sleep_until(deadline) => break,
+                _ = stop.changed() => break,
+            }
+        }
+        workers.push(tokio::spawn(run_connection(
+            job.clone(),
+            config.clone(),
+            params.clone(),
+            index,
+            deadline,
+        )));
+    }
+    for worker in workers {
+        let _ = worker.await;
+    }
+    reporter.abort();
+
+    let status = if job.stopped.load(Ordering::Relaxed) { "stopped" } else { "completed" };
+    let report = job.snapshot(status);
+    let _ = job.progress.send(report.clone());
+
+    if let Err(e) = sqlx::query("UPDATE t_websocket_loadtest SET status = ?, finished_at = ?, report = ? WHERE id = ?")
+        .bind(status)
+        .bind(chrono::Utc::now().timestamp())
+        .bind(serde_json::to_string(&report).unwrap_or_default())
+        .bind(&job.id)
+        .execute(&pool)
+        .await
+    {
+        tracing::error!("Failed to save load test report: {}", e);
+    }
+
+    LOAD_TESTS.write().await.remove(&job.id);
+    tracing::info!("Load test {} {}", job.id, status);
+}
+
+async fn fetch_load_test(pool: &SqlitePool, id: &str) -> Result<LoadTest, StatusCode> {
+    match sqlx::query_as::<_, LoadTest>("SELECT * FROM t_websocket_loadtest WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(load_test) => Ok(load_test),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch load test: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn stored_report(load_test: &LoadTest) -> LoadTestReport {
+    load_test
+        .report
+        .as_deref()
+        .and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_else(|| LoadTestReport {
+            id: load_test.id.clone(),
+            status: load_test.status.clone(),
+            ..Default::default()
+        })
+}
+
+// 启动压测任务
+pub async fn start_load_test(
+    State(state): State<AppState>,
+    Json(payload): Json<LoadTestRequest>,
+) -> Result<Json<ApiResponse<LoadTest>>, StatusCode> {
+    if let Err(e) = validate(&payload) {
+        tracing::warn!("Invalid load test: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // 使用已保存配置（协议、headers），或按URL构造临时的原始连接配置
+    let now = chrono::Utc::now().timestamp();
+    let mut config = match &payload.config_id {
+        Some(config_id) => match sqlx::query_as::<_, WebSocketConfig>(
+            "SELECT * FROM t_websocket_config WHERE id = ?"
+        )
+        .bind(config_id)
+        .fetch_one(&state.pool)
+        .await
+        {
+            Ok(config) => config,
+            Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+            Err(e) => {
+                tracing::error!("Failed to fetch websocket config: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        },
+        None => transient_config("loadtest", ""),
+    };
+    if let Some(ws_url) = &payload.ws_url {
+        config.ws_url = ws_url.clone();
+    }
+    if config.ws_url.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let load_test = LoadTest {
+        id: Uuid::new_v4().to_string(),
+        ws_url: config.ws_url.clone(),
+        params: serde_json::to_string(&payload).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        status: "running".to_string(),
+        started_at: now,
+        finished_at: None,
+        report: None,
+    };
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_loadtest (id, ws_url, params, status, started_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&load_test.id)
+    .bind(&load_test.ws_url)
+    .bind(&load_test.params)
+    .bind(&load_test.status)
+    .bind(load_test.started_at)
+    .execute(&state.pool)
+    .await
+    {
+        tracing::error!("Failed to create load test: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let (stop, _) = watch::channel(false);
+    let (progress, _) = watch::channel(LoadTestReport {
+        id: load_test.id.clone(),
+        status: "running".to_string(),
+        ..Default::default()
+    });
+    let job = Arc::new(Job {
+        id: load_test.id.clone(),
+        started: Instant::now(),
+        stopped: AtomicBool::new(false),
+        stop,
+        progress,
+        stats: Stats::default(),
+    });
+    LOAD_TESTS.write().await.insert(job.id.clone(), job.clone());
+    tokio::spawn(run_job(state.pool.clone(), job, config, payload));
+
+    Ok(Json(ApiResponse::ok(load_test)))
+}
+
+// 获取压测任务列表
+pub async fn list_load_tests(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<LoadTest>>>, StatusCode> {
+    match sqlx::query_as::<_, LoadTest>("SELECT * FROM t_websocket_loadtest ORDER BY started_at DESC LIMIT 100")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(load_tests) => Ok(Json(ApiResponse::ok(load_tests))),
+        Err(e) => {
+            tracing::error!("Failed to fetch load tests: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取压测报告（运行中返回实时数据）
+pub async fn get_load_test(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<LoadTestReport>>, StatusCode> {
+    if let Some(job) = LOAD_TESTS.read().await.get(&id) {
+        return Ok(Json(ApiResponse::ok(job.snapshot("running"))));
+    }
+    let load_test = fetch_load_test(&state.pool, &id).await?;
+    Ok(Json(ApiResponse::ok(stored_report(&load_test))))
+}
+
+// 以 SSE 推送压测进度，任务结束后推送最终报告并关闭
+pub async fn stream_load_test(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Sse<BoxStream<'static, Result<Event, Infallible>>>, StatusCode> {
+    let to_event = |report: &LoadTestReport| {
+        Ok(Event::default().event("progress").data(serde_json::to_string(report).unwrap_or_default()))
+    };
+
+    let receiver = LOAD_TESTS.read().await.get(&id).map(|job| job.progress.subscribe());
+    let stream = match receiver {
+        Some(receiver) => futures::stream::unfold((receiver, true, false), move |(mut receiver, first, done)| async move {
+            if done || (!first && receiver.changed().await.is_err()) {
+                return None;
+            }
+            let report = receiver.borrow_and_update().clone();
+            let finished = report.status != "running";
+            Some((to_event(&report), (receiver, false, finished)))
+        })
+        .boxed(),
+        None => {
+            let load_test = fetch_load_test(&state.pool, &id).await?;
+            futures::stream::once(futures::future::ready(to_event(&stored_report(&load_test)))).boxed()
+        }
+    };
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+// 停止压测任务
+pub async fn stop_load_test(
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let jobs = LOAD_TESTS.read().await;
+    let job = jobs.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    job.stopped.store(true, Ordering::Relaxed);
+    let _ = job.stop.send(true);
+    Ok(Json(ApiResponse::ok(())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_within_bucket_precision() {
+        let mut histogram = Histogram::default();
+        for value in 1..=1000 {
+            histogram.record(value as f64);
+        }
+        let stats = histogram.summarize();
+        assert_eq!((stats.count, stats.min, stats.max), (1000, 1.0, 1000.0));
+        assert!((stats.mean - 500.5).abs() < 1e-9);
+        for (actual, expected) in [(stats.p50, 500.0), (stats.p90, 900.0), (stats.p99, 990.0)] {
+            assert!((actual - expected).abs() / expected < 0.01, "{} vs {}", actual, expected);
+        }
+        // 超出范围的值落在首尾桶，但最小、最大值仍准确
+        histogram.record(0.0);
+        histogram.record(1e12);
+        let stats = histogram.summarize();
+        assert_eq!((stats.min, stats.max), (0.0, 1e12));
+    }
+
+    #[test]
+    fn caps_pending_replies() {
+        let start = Instant::now();
+        let mut pending = Pending::new(start);
+        for key in 0..MAX_PENDING + 10 {
+            pending.track(key.to_string(), start);
+        }
+        assert_eq!(pending.sent_at.len(), MAX_PENDING);
+        assert_eq!(pending.take("0"), Some(start));
+
+        // 超时未回复的在下次满载时被清理
+        pending.track("late".to_string(), start);
+        pending.track("fresh".to_string(), start + PENDING_TIMEOUT);
+        assert_eq!(pending.sent_at.len(), 1);
+        assert!(pending.take("fresh").is_some());
+    }
+
+    #[test]
+    fn rejects_rates_out_of_range() {
+        let request = |rate: f64| LoadTestRequest {
+            ws_url: None,
+            config_id: None,
+            connections: 1,
+            ramp_up_per_sec: None,
+            messages_per_sec: Some(rate),
+            duration_secs: 1,
+            message: Some("ping".to_string()),
+            topic: None,
+            event: None,
+            correlation_path: None,
+        };
+        assert!(validate(&request(10.0)).is_ok());
+        for rate in [0.0, 1e-9, f64::NAN, f64::INFINITY, 1e7] {
+            assert!(validate(&request(rate)).is_err(), "{}", rate);
+        }
+    }
+}
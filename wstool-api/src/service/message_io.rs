@@ -0,0 +1,556 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{ApiResponse, MessageImportResult, WebSocketConfig, WebSocketMessage};
+
+// 导出时每积累这么多字节发送一次
+const EXPORT_CHUNK_SIZE: usize = 64 * 1024;
+
+// 与 WebSocketMessage 的字段顺序一致，csv_row 按结构体序列化
+const CSV_COLUMNS: [&str; 16] = [
+    "id", "config_id", "message_type", "content", "timestamp", "status",
+    "error_message", "topic", "event", "session_id", "timestamp_us",
+    "valid", "validation_error", "raw_content", "qos", "retain",
+];
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Ndjson,
+    Csv,
+    Har,
+}
+
+impl Format {
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Ndjson => "application/x-ndjson",
+            Format::Csv => "text/csv; charset=utf-8",
+            Format::Har => "application/json",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Ndjson => "ndjson",
+            Format::Csv => "csv",
+            Format::Har => "har",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: Format,
+    pub from: Option<i64>, // Unix seconds, inclusive
+    pub to: Option<i64>, // Unix seconds, inclusive
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub format: Format,
+}
+
+// 导入的单条消息，缺省字段使用默认值
+#[derive(Debug, Deserialize)]
+struct ImportedMessage {
+    message_type: String,
+    content: String,
+    timestamp: Option<i64>,
+    timestamp_us: Option<i64>,
+    status: Option<String>,
+    error_message: Option<String>,
+    topic: Option<String>,
+    event: Option<String>,
+    session_id: Option<String>,
+    valid: Option<bool>,
+    validation_error: Option<String>,
+    raw_content: Option<String>,
+    qos: Option<i64>,
+    retain: Option<bool>,
+}
+
+fn timestamp_us(message: &WebSocketMessage) -> i64 {
+    message.timestamp_us.unwrap_or(message.timestamp * 1_000_000)
+}
+
+fn csv_header() -> String {
+    let mut header = CSV_COLUMNS.join(",");
+    header.push_str("\r\n");
+    header
+}
+
+fn csv_row(message: &WebSocketMessage) -> Result<String, csv::Error> {
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+    writer.serialize(message)?;
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// HAR 文档开头：一个 websocket 条目，消息放在 Chrome 的 _webSocketMessages 扩展字段中
+fn har_prefix(config: &WebSocketConfig, started_at_us: Option<i64>) -> String {
+    let started = started_at_us
+        .and_then(chrono::DateTime::from_timestamp_micros)
+        .unwrap_or_else(chrono::Utc::now)
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let entry = json!({
+        "startedDateTime": started,
+        "time": 0,
+        "request": {
+            "method": "GET",
+            "url": config.ws_url,
+            "httpVersion": "HTTP/1.1",
+            "headers": [],
+            "queryString": [],
+            "cookies": [],
+            "headersSize": -1,
+            "bodySize": 0
+        },
+        "response": {
+            "status": 101,
+            "statusText": "Switching Protocols",
+            "httpVersion": "HTTP/1.1",
+            "headers": [],
+            "cookies": [],
+            "content": { "size": 0, "mimeType": "x-unknown" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": 0
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": 0, "receive": 0 },
+        "_resourceType": "websocket"
+    });
+    let entry = entry.to_string();
+    // 去掉条目末尾的 "}"，后续逐条写入 _webSocketMessages
+    format!(
+        "{{\"log\":{{\"version\":\"1.2\",\"creator\":{{\"name\":\"wstool\",\"version\":\"{}\"}},\"pages\":[],\"entries\":[{},\"_webSocketMessages\":[",
+        env!("CARGO_PKG_VERSION"),
+        &entry[..entry.len() - 1]
+    )
+}
+
+fn har_message(message: &WebSocketMessage) -> String {
+    json!({
+        "type": if message.message_type == "sent" { "send" } else { "receive" },
+        "time": timestamp_us(message) as f64 / 1_000_000.0,
+        "opcode": 1,
+        "data": message.content,
+    })
+    .to_string()
+}
+
+async fn fetch_config(pool: &SqlitePool, config_id: &str) -> Result<WebSocketConfig, StatusCode> {
+    match sqlx::query_as::<_, WebSocketConfig>("SELECT * FROM t_websocket_config WHERE id = ?")
+        .bind(config_id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(config) => Ok(config),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket config: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 逐行读取消息并分块写入通道，不在内存中保留全部结果
+async fn write_export(
+    pool: SqlitePool,
+    config: WebSocketConfig,
+    query: ExportQuery,
+    tx: mpsc::Sender<Result<String, std::io::Error>>,
+) {
+    let first_us: Option<i64> = if query.format == Format::Har {
+        sqlx::query_scalar(
+            r#"
+            SELECT MIN(COALESCE(timestamp_us, timestamp * 1000000)) FROM t_websocket_message
+            WHERE config_id = ? AND (? IS NULL OR timestamp >= ?) AND (? IS NULL OR timestamp <= ?)
+            "#
+        )
+        .bind(&config.id)
+        .bind(query.from)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(query.to)
+        .fetch_one(&pool)
+        .await
+        .unwrap_or(None)
+    } else {
+        None
+    };
+
+    let mut buffer = match query.format {
+        Format::Ndjson => String::new(),
+        Format::Csv => csv_header(),
+        Format::Har => har_prefix(&config, first_us),
+    };
+
+    let mut rows = sqlx::query_as::<_, WebSocketMessage>(
+        r#"
+        SELECT * FROM t_websocket_message
+        WHERE config_id = ? AND (? IS NULL OR timestamp >= ?) AND (? IS NULL OR timestamp <= ?)
+        ORDER BY COALESCE(timestamp_us, timestamp * 1000000), rowid
+        "#
+    )
+    .bind(&config.id)
+    .bind(query.from)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(query.to)
+    .fetch(&pool);
+
+    let mut first = true;
+    while let Some(row) = rows.next().await {
+        let message = match row {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::error!("Failed to export websocket messages: {}", e);
+                let _ = tx.send(Err(std::io::Error::other(e))).await;
+                return;
+            }
+        };
+        match query.format {
+            Format::Ndjson => {
+                buffer.push_str(&serde_json::to_string(&message).unwrap_or_default());
+                buffer.push('\n');
+            }
+            Format::Csv => match csv_row(&message) {
+                Ok(line) => buffer.push_str(&line),
+                Err(e) => {
+                    let _ = tx.send(Err(std::io::Error::other(e))).await;
+                    return;
+                }
+            },
+            Format::Har => {
+                if !first {
+                    buffer.push(',');
+                }
+                buffer.push_str(&har_message(&message));
+            }
+        }
+        first = false;
+
+        if buffer.len() >= EXPORT_CHUNK_SIZE && tx.send(Ok(std::mem::take(&mut buffer))).await.is_err() {
+            // 客户端已断开
+            return;
+        }
+    }
+
+    if query.format == Format::Har {
+        buffer.push_str("]}]}}");
+    }
+    if !buffer.is_empty() {
+        let _ = tx.send(Ok(buffer)).await;
+    }
+}
+
+// 按时间范围流式导出消息历史（NDJSON / CSV / HAR）
+pub async fn export_messages(
+    Path(config_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let config = fetch_config(&state.pool, &config_id).await?;
+    let format = query.format;
+
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(write_export(state.pool.clone(), config, query, tx));
+    let stream = futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) });
+
+    let disposition = format!("attachment; filename=\"messages-{}.{}\"", config_id, format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+fn parse_ndjson(body: &str) -> Result<Vec<ImportedMessage>, String> {
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {}", index + 1, e)))
+        .collect()
+}
+
+fn parse_csv(body: &str) -> Result<Vec<ImportedMessage>, String> {
+    let mut reader = csv::Reader::from_reader(body.as_bytes());
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(index, row)| row.map_err(|e| format!("row {}: {}", index + 1, e)))
+        .collect()
+}
+
+// 读取 HAR 中所有 websocket 条目的 _webSocketMessages
+fn parse_har(body: &str) -> Result<Vec<ImportedMessage>, String> {
+    let har: Value = serde_json::from_str(body).map_err(|e| format!("invalid HAR: {}", e))?;
+    let entries = har
+        .pointer("/log/entries")
+        .and_then(Value::as_array)
+        .ok_or("HAR has no log.entries")?;
+
+    let mut messages = Vec::new();
+    for entry in entries {
+        let Some(frames) = entry.get("_webSocketMessages").and_then(Value::as_array) else {
+            continue;
+        };
+        for frame in frames {
+            let message_type = match frame.get("type").and_then(Value::as_str) {
+                Some("send") => "sent",
+                Some("receive") => "received",
+                other => return Err(format!("invalid websocket message type: {:?}", other)),
+            };
+            let time = frame.get("time").and_then(Value::as_f64).ok_or("websocket message without time")?;
+            let timestamp_us = (time * 1_000_000.0).round() as i64;
+            messages.push(ImportedMessage {
+                message_type: message_type.to_string(),
+                content: frame.get("data").and_then(Value::as_str).unwrap_or_default().to_string(),
+                timestamp: Some(timestamp_us.div_euclid(1_000_000)),
+                timestamp_us: Some(timestamp_us),
+                status: None,
+                error_message: None,
+                topic: None,
+                event: None,
+                session_id: None,
+                valid: None,
+                validation_error: None,
+                raw_content: None,
+                qos: None,
+                retain: None,
+            });
+        }
+    }
+    Ok(messages)
+}
+
+// 导入消息历史到指定配置下（整体在一个事务中完成）
+pub async fn import_messages(
+    Path(config_id): Path<String>,
+    Query(query): Query<ImportQuery>,
+    State(state): State<AppState>,
+    body: String,
+) -> Result<Json<ApiResponse<MessageImportResult>>, StatusCode> {
+    fetch_config(&state.pool, &config_id).await?;
+
+    let parsed = match query.format {
+        Format::Ndjson => parse_ndjson(&body),
+        Format::Csv => parse_csv(&body),
+        Format::Har => parse_har(&body),
+    };
+    let messages = parsed.map_err(|e| {
+        tracing::warn!("Invalid message import: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    for message in &messages {
+        let valid_type = matches!(message.message_type.as_str(), "sent" | "received");
+        let valid_status = message.status.as_deref().is_none_or(|s| matches!(s, "success" | "failed" | "pending"));
+        let has_time = message.timestamp.is_some() || message.timestamp_us.is_some();
+        if !valid_type || !valid_status || !has_time {
+            tracing::warn!("Invalid message import: {:?}", message);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = state.pool.begin().await?;
+        for message in &messages {
+            let timestamp = message
+                .timestamp
+                .or(message.timestamp_us.map(|us| us.div_euclid(1_000_000)))
+                .unwrap_or_default();
+            sqlx::query(
+                r#"
+                INSERT INTO t_websocket_message
+                (id, config_id, message_type, content, timestamp, status, error_message, topic, event, session_id, timestamp_us, valid, validation_error, raw_content, qos, retain)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&config_id)
+            .bind(&message.message_type)
+            .bind(&message.content)
+            .bind(timestamp)
+            .bind(message.status.as_deref().unwrap_or("success"))
+            .bind(&message.error_message)
+            .bind(&message.topic)
+            .bind(&message.event)
+            .bind(&message.session_id)
+            .bind(message.timestamp_us)
+            .bind(message.valid)
+            .bind(&message.validation_error)
+            .bind(&message.raw_content)
+            .bind(message.qos)
+            .bind(message.retain)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(messages.len() as u64)
+    }
+    .await;
+
+    match result {
+        Ok(imported) => Ok(Json(ApiResponse::ok(MessageImportResult { imported }))),
+        Err(e) => {
+            tracing::error!("Failed to import websocket messages: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    async fn test_state() -> AppState {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::migrate(&pool).await.unwrap();
+        for id in ["source", "target"] {
+            sqlx::query(
+                "INSERT INTO t_websocket_config (id, name, ws_url, config_type, created_at, updated_at) VALUES (?, 'test', 'ws://127.0.0.1', 'subscriber', 0, 0)"
+            )
+            .bind(id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        AppState { pool }
+    }
+
+    fn message(content: &str, timestamp_us: i64) -> WebSocketMessage {
+        WebSocketMessage {
+            id: Uuid::new_v4().to_string(),
+            config_id: "source".to_string(),
+            message_type: "sent".to_string(),
+            content: content.to_string(),
+            timestamp: timestamp_us / 1_000_000,
+            status: "pending".to_string(),
+            error_message: None,
+            topic: Some("sensors/out".to_string()),
+            event: None,
+            session_id: Some("session-1".to_string()),
+            timestamp_us: Some(timestamp_us),
+            valid: None,
+            validation_error: None,
+            raw_content: None,
+            qos: Some(1),
+            retain: Some(true),
+        }
+    }
+
+    async fn insert(pool: &SqlitePool, message: &WebSocketMessage) {
+        sqlx::query(
+            r#"
+            INSERT INTO t_websocket_message
+            (id, config_id, message_type, content, timestamp, status, error_message, topic, event, session_id, timestamp_us, valid, validation_error, raw_content, qos, retain)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&message.id)
+        .bind(&message.config_id)
+        .bind(&message.message_type)
+        .bind(&message.content)
+        .bind(message.timestamp)
+        .bind(&message.status)
+        .bind(&message.error_message)
+        .bind(&message.topic)
+        .bind(&message.event)
+        .bind(&message.session_id)
+        .bind(message.timestamp_us)
+        .bind(message.valid)
+        .bind(&message.validation_error)
+        .bind(&message.raw_content)
+        .bind(message.qos)
+        .bind(message.retain)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn messages(pool: &SqlitePool, config_id: &str) -> Vec<WebSocketMessage> {
+        sqlx::query_as("SELECT * FROM t_websocket_message WHERE config_id = ? ORDER BY timestamp_us")
+            .bind(config_id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    // 导出后再导入到另一个配置，除 id 和 config_id 外所有字段保持不变
+    async fn round_trip(format: Format) {
+        let state = test_state().await;
+        let mut received = message("{\"price\": \"1,5\",\n\"note\": \"say \\\"hi\\\"\"}", 1_700_000_000_123_456);
+        received.message_type = "received".to_string();
+        received.status = "success".to_string();
+        received.event = Some("next".to_string());
+        received.valid = Some(false);
+        received.validation_error = Some("/price: not a number".to_string());
+        received.raw_content = Some("raw,\"payload\"".to_string());
+        received.qos = None;
+        received.retain = None;
+        let originals = [message("ping", 1_700_000_000_000_001), received];
+        for original in &originals {
+            insert(&state.pool, original).await;
+        }
+
+        let response = export_messages(
+            Path("source".to_string()),
+            Query(ExportQuery { format, from: None, to: None }),
+            State(state.clone()),
+        )
+        .await
+        .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let imported = import_messages(
+            Path("target".to_string()),
+            Query(ImportQuery { format }),
+            State(state.clone()),
+            body,
+        )
+        .await
+        .unwrap();
+        assert_eq!(imported.0.data.unwrap().imported, 2);
+
+        let copies = messages(&state.pool, "target").await;
+        assert_eq!(copies.len(), originals.len());
+        for (copy, original) in copies.iter().zip(&originals) {
+            let mut copy = copy.clone();
+            copy.id = original.id.clone();
+            copy.config_id = original.config_id.clone();
+            assert_eq!(serde_json::to_value(&copy).unwrap(), serde_json::to_value(original).unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn csv_export_round_trips() {
+        round_trip(Format::Csv).await;
+    }
+
+    #[tokio::test]
+    async fn ndjson_export_round_trips() {
+        round_trip(Format::Ndjson).await;
+    }
+}
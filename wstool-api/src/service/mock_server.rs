@@ -0,0 +1,514 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{Json, Response},
+};
+use futures::{SinkExt, StreamExt};
+use rand::Rng;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{ApiResponse, MockDefinition, MockLog, NewMockDefinition, UpdateMockDefinition};
+use crate::utils::json;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum MatchKind {
+    #[default]
+    Regex,
+    Json,
+    Any,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MockRule {
+    #[serde(rename = "match", default)]
+    kind: MatchKind,
+    #[serde(default)]
+    pattern: Value,
+    response: Option<String>,
+    #[serde(default)]
+    close: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MockPush {
+    interval_ms: u64,
+    payload: String,
+    count: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct MockDisconnect {
+    after_ms: Option<u64>,
+    after_messages: Option<u64>,
+    code: Option<u16>,
+    reason: Option<String>,
+}
+
+// 解析并校验后的 mock 定义
+struct CompiledMock {
+    name: String,
+    greeting: Option<String>,
+    rules: Vec<(MockRule, Option<Regex>)>,
+    pushes: Vec<MockPush>,
+    disconnect: MockDisconnect,
+}
+
+fn parse_json<T: serde::de::DeserializeOwned + Default>(field: &str, raw: Option<&str>) -> Result<T, String> {
+    match raw {
+        Some(raw) if !raw.trim().is_empty() => {
+            serde_json::from_str(raw).map_err(|e| format!("invalid {}: {}", field, e))
+        }
+        _ => Ok(T::default()),
+    }
+}
+
+fn compile(mock: &MockDefinition) -> Result<CompiledMock, String> {
+    let rules: Vec<MockRule> = parse_json("rules", mock.rules.as_deref())?;
+    let rules = rules
+        .into_iter()
+        .map(|rule| {
+            let regex = match (rule.kind, &rule.pattern) {
+                (MatchKind::Regex, Value::String(pattern)) => {
+                    Some(Regex::new(pattern).map_err(|e| format!("invalid rule pattern: {}", e))?)
+                }
+                (MatchKind::Regex, _) => return Err("regex rule requires a string pattern".to_string()),
+                _ => None,
+            };
+            Ok((rule, regex))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let pushes: Vec<MockPush> = parse_json("pushes", mock.pushes.as_deref())?;
+    if pushes.iter().any(|push| push.interval_ms == 0) {
+        return Err("push interval_ms must be greater than 0".to_string());
+    }
+    Ok(CompiledMock {
+        name: mock.name.clone(),
+        greeting: mock.greeting.clone(),
+        rules,
+        pushes,
+        disconnect: parse_json("disconnect", mock.disconnect.as_deref())?,
+    })
+}
+
+// JSON 子集匹配：pattern 中的每个字段都必须在 value 中存在且相等
+fn json_matches(pattern: &Value, value: &Value) -> bool {
+    match (pattern, value) {
+        (Value::Object(p), Value::Object(v)) => p
+            .iter()
+            .all(|(key, expected)| v.get(key).is_some_and(|actual| json_matches(expected, actual))),
+        _ => pattern == value,
+    }
+}
+
+impl CompiledMock {
+    fn find_rule(&self, text: &str) -> Option<&MockRule> {
+        let parsed = serde_json::from_str::<Value>(text).ok();
+        self.rules
+            .iter()
+            .find(|(rule, regex)| match rule.kind {
+                MatchKind::Any => true,
+                MatchKind::Regex => regex.as_ref().is_some_and(|r| r.is_match(text)),
+                MatchKind::Json => parsed.as_ref().is_some_and(|v| json_matches(&rule.pattern, v)),
+            })
+            .map(|(rule, _)| rule)
+    }
+}
+
+/// 模板渲染：`{{now}}`（毫秒）、`{{now_secs}}`、`{{uuid}}`、`{{seq}}`、`{{session}}`、
+/// `{{random}}`、`{{random_int:MIN:MAX}}`、`{{request}}`、`{{request.a.b}}`
+pub fn render(template: &str, seq: u64, session: &str, request: Option<&str>) -> String {
+    let request_json = request.and_then(|r| serde_json::from_str::<Value>(r).ok());
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let token = rest[start + 2..start + end].trim();
+        out.push_str(&render_token(token, seq, session, request, request_json.as_ref()));
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn render_token(token: &str, seq: u64, session: &str, request: Option<&str>, request_json: Option<&Value>) -> String {
+    let now = chrono::Utc::now();
+    match token {
+        "now" => now.timestamp_millis().to_string(),
+        "now_secs" => now.timestamp().to_string(),
+        "uuid" => Uuid::new_v4().to_string(),
+        "seq" => seq.to_string(),
+        "session" => session.to_string(),
+        "random" => format!("{:.6}", rand::thread_rng().gen::<f64>()),
+        "request" => request.unwrap_or_default().to_string(),
+        _ => {
+            if let Some(range) = token.strip_prefix("random_int:") {
+                let bounds: Vec<i64> = range.split(':').filter_map(|v| v.parse().ok()).collect();
+                if let [min, max] = bounds[..] {
+                    if min <= max {
+                        return rand::thread_rng().gen_range(min..=max).to_string();
+                    }
+                }
+            }
+            if let Some(path) = token.strip_prefix("request.") {
+                return request_json
+                    .and_then(|root| json::lookup(root, path))
+                    .map(json::value_to_string)
+                    .unwrap_or_default();
+            }
+            format!("{{{{{}}}}}", token)
+        }
+    }
+}
+
+// 记录一次交互
+async fn log_exchange(pool: &SqlitePool, mock_name: &str, session_id: &str, message_type: &str, content: &str) {
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_mock_log (id, mock_name, session_id, message_type, content, timestamp)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(mock_name)
+    .bind(session_id)
+    .bind(message_type)
+    .bind(content)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool)
+    .await
+    {
+        tracing::error!("Failed to save mock log: {}", e);
+    }
+}
+
+// GET /mock/:name（WebSocket升级）
+pub async fn mock_endpoint(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let mock = fetch_mock(&state.pool, &name).await?;
+    let compiled = compile(&mock).map_err(|e| {
+        tracing::error!("Invalid mock definition {}: {}", name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(ws.on_upgrade(move |socket| run_mock(socket, compiled, state.pool)))
+}
+
+async fn run_mock(socket: WebSocket, mock: CompiledMock, pool: SqlitePool) {
+    let session_id = Uuid::new_v4().to_string();
+    let (mut sender, mut receiver) = socket.split();
+    log_exchange(&pool, &mock.name, &session_id, "event", "open").await;
+
+    if let Some(greeting) = &mock.greeting {
+        let text = render(greeting, 0, &session_id, None);
+        if sender.send(Message::Text(text.clone())).await.is_err() {
+            return;
+        }
+        log_exchange(&pool, &mock.name, &session_id, "sent", &text).await;
+    }
+
+    // 每个定时推送一个任务，统一经通道写出
+    let (push_tx, mut push_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let push_tasks: Vec<_> = mock
+        .pushes
+        .iter()
+        .cloned()
+        .map(|push| {
+            let tx = push_tx.clone();
+            let session_id = session_id.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(Duration::from_millis(push.interval_ms));
+                ticker.tick().await;
+                let mut seq = 0u64;
+                while push.count.is_none_or(|count| seq < count) {
+                    ticker.tick().await;
+                    seq += 1;
+                    if tx.send(render(&push.payload, seq, &session_id, None)).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(push_tx);
+
+    let disconnect_after = mock.disconnect.after_ms.map(Duration::from_millis);
+    let disconnect_timer = async {
+        match disconnect_after {
+            Some(after) => tokio::time::sleep(after).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(disconnect_timer);
+
+    let mut received = 0u64;
+    let mut close = false;
+    loop {
+        tokio::select! {
+            incoming = receiver.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Binary(data))) => String::from_utf8_lossy(&data).into_owned(),
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(_)) => continue,
+                };
+                received += 1;
+                log_exchange(&pool, &mock.name, &session_id, "received", &text).await;
+
+                if let Some(rule) = mock.find_rule(&text) {
+                    if let Some(response) = &rule.response {
+                        let reply = render(response, received, &session_id, Some(&text));
+                        if sender.send(Message::Text(reply.clone())).await.is_err() {
+                            break;
+                        }
+                        log_exchange(&pool, &mock.name, &session_id, "sent", &reply).await;
+                    }
+                    if rule.close {
+                        close = true;
+                        break;
+                    }
+                }
+                if mock.disconnect.after_messages.is_some_and(|limit| received >= limit) {
+                    close = true;
+                    break;
+                }
+            }
+            Some(payload) = push_rx.recv() => {
+                if sender.send(Message::Text(payload.clone())).await.is_err() {
+                    break;
+                }
+                log_exchange(&pool, &mock.name, &session_id, "sent", &payload).await;
+            }
+            _ = &mut disconnect_timer => {
+                close = true;
+                break;
+            }
+        }
+    }
+
+    for task in push_tasks {
+        task.abort();
+    }
+
+    // 脚本化断开：发送关闭帧
+    if close {
+        let frame = CloseFrame {
+            code: mock.disconnect.code.unwrap_or(1000),
+            reason: Cow::Owned(mock.disconnect.reason.clone().unwrap_or_default()),
+        };
+        let _ = sender.send(Message::Close(Some(frame))).await;
+        log_exchange(&pool, &mock.name, &session_id, "event", "scripted disconnect").await;
+    }
+    log_exchange(&pool, &mock.name, &session_id, "event", "close").await;
+}
+
+async fn fetch_mock(pool: &SqlitePool, name: &str) -> Result<MockDefinition, StatusCode> {
+    match sqlx::query_as::<_, MockDefinition>("SELECT * FROM t_websocket_mock WHERE name = ?")
+        .bind(name)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(mock) => Ok(mock),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch mock definition: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取mock定义列表
+pub async fn list_mocks(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<MockDefinition>>>, StatusCode> {
+    match sqlx::query_as::<_, MockDefinition>("SELECT * FROM t_websocket_mock ORDER BY created_at DESC")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(mocks) => Ok(Json(ApiResponse::ok(mocks))),
+        Err(e) => {
+            tracing::error!("Failed to fetch mock definitions: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个mock定义
+pub async fn get_mock(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<MockDefinition>>, StatusCode> {
+    fetch_mock(&state.pool, &name).await.map(|mock| Json(ApiResponse::ok(mock)))
+}
+
+// 创建mock定义
+pub async fn create_mock(
+    State(state): State<AppState>,
+    Json(payload): Json<NewMockDefinition>,
+) -> Result<Json<ApiResponse<MockDefinition>>, StatusCode> {
+    let now = chrono::Utc::now().timestamp();
+    let mock = MockDefinition {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name,
+        description: payload.description,
+        greeting: payload.greeting,
+        rules: payload.rules,
+        pushes: payload.pushes,
+        disconnect: payload.disconnect,
+        created_at: now,
+        updated_at: now,
+    };
+    if mock.name.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = compile(&mock) {
+        tracing::warn!("Invalid mock definition: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match sqlx::query(
+        r#"
+        INSERT INTO t_websocket_mock (id, name, description, greeting, rules, pushes, disconnect, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&mock.id)
+    .bind(&mock.name)
+    .bind(&mock.description)
+    .bind(&mock.greeting)
+    .bind(&mock.rules)
+    .bind(&mock.pushes)
+    .bind(&mock.disconnect)
+    .bind(mock.created_at)
+    .bind(mock.updated_at)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::ok(mock))),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => Err(StatusCode::CONFLICT),
+        Err(e) => {
+            tracing::error!("Failed to create mock definition: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 更新mock定义（未提供的字段保持不变）
+pub async fn update_mock(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateMockDefinition>,
+) -> Result<Json<ApiResponse<MockDefinition>>, StatusCode> {
+    let mut mock = fetch_mock(&state.pool, &name).await?;
+    if payload.description.is_some() {
+        mock.description = payload.description;
+    }
+    if payload.greeting.is_some() {
+        mock.greeting = payload.greeting;
+    }
+    if payload.rules.is_some() {
+        mock.rules = payload.rules;
+    }
+    if payload.pushes.is_some() {
+        mock.pushes = payload.pushes;
+    }
+    if payload.disconnect.is_some() {
+        mock.disconnect = payload.disconnect;
+    }
+    mock.updated_at = chrono::Utc::now().timestamp();
+    if let Err(e) = compile(&mock) {
+        tracing::warn!("Invalid mock definition: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match sqlx::query(
+        r#"
+        UPDATE t_websocket_mock
+        SET description = ?, greeting = ?, rules = ?, pushes = ?, disconnect = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&mock.description)
+    .bind(&mock.greeting)
+    .bind(&mock.rules)
+    .bind(&mock.pushes)
+    .bind(&mock.disconnect)
+    .bind(mock.updated_at)
+    .bind(&mock.id)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::ok(mock))),
+        Err(e) => {
+            tracing::error!("Failed to update mock definition: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除mock定义
+pub async fn delete_mock(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_mock WHERE name = ?")
+        .bind(&name)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(Json(ApiResponse::ok(()))),
+        Err(e) => {
+            tracing::error!("Failed to delete mock definition: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取mock交互日志
+pub async fn get_mock_logs(
+    Path(name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<MockLog>>>, StatusCode> {
+    let limit: i64 = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(100).min(1000);
+    let session_id = params.get("session_id");
+
+    match sqlx::query_as::<_, MockLog>(
+        r#"
+        SELECT * FROM t_websocket_mock_log
+        WHERE mock_name = ? AND (? IS NULL OR session_id = ?)
+        ORDER BY timestamp DESC, rowid DESC LIMIT ?
+        "#
+    )
+    .bind(&name)
+    .bind(session_id)
+    .bind(session_id)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(logs) => Ok(Json(ApiResponse::ok(logs))),
+        Err(e) => {
+            tracing::error!("Failed to fetch mock logs: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -7,4 +7,22 @@ pub mod binlog;
 pub mod websocket;
 pub mod websocket_manager;
 pub mod websocket_actions;
-
+pub mod protocol;
+pub mod mock_server;
+pub mod scenario;
+pub mod loadtest;
+pub mod recording;
+pub mod message_io;
+pub mod retention;
+pub mod schema_validation;
+pub mod transform;
+pub mod bridge;
+pub mod webhook;
+pub mod alert;
+pub mod outbox;
+pub mod sequence;
+pub mod orderbook;
+pub mod candle;
+pub mod state_store;
+pub mod connection_group;
+pub mod analytics;
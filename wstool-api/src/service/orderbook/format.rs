@@ -0,0 +1,266 @@
+use serde_json::Value;
+
+// 交易所深度数据格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    // depthUpdate 增量（U/u/pu）、depthN 部分深度快照及 REST 深度快照
+    Binance,
+    // books/books5/bbo-tbt 等频道：action 为 snapshot 或 update，带 seqId/prevSeqId 和 checksum
+    Okx,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthKind {
+    Snapshot,
+    Delta,
+}
+
+// 一次深度推送（某个交易对的快照或增量）
+#[derive(Debug, Clone)]
+pub struct DepthEvent {
+    pub symbol: String,
+    pub channel: Option<String>, // OKX 频道名，重建时重新订阅
+    pub kind: DepthKind,
+    pub first_id: Option<i64>, // Binance U
+    pub final_id: Option<i64>, // Binance u / lastUpdateId，OKX seqId
+    pub prev_id: Option<i64>, // Binance 合约 pu，OKX prevSeqId
+    pub bids: Vec<(String, String)>, // 价格、数量（数量为 0 表示删除该档）
+    pub asks: Vec<(String, String)>,
+    pub checksum: Option<i32>,
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn number(value: Option<&Value>) -> Option<i64> {
+    match value? {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+// [[price, size, ...], ...]
+fn levels(value: Option<&Value>) -> Vec<(String, String)> {
+    value
+        .and_then(Value::as_array)
+        .map(|rows| {
+            rows.iter()
+                .filter_map(|row| {
+                    let row = row.as_array()?;
+                    Some((text(row.first()?)?, text(row.get(1)?)?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "binance" => Ok(Format::Binance),
+            "okx" => Ok(Format::Okx),
+            other => Err(format!("unsupported order book format: {}", other)),
+        }
+    }
+
+    // 从一帧消息中取出深度推送，不是深度数据时返回空
+    pub fn events(self, frame: &Value) -> Vec<DepthEvent> {
+        match self {
+            Format::Binance => binance_events(frame),
+            Format::Okx => okx_events(frame),
+        }
+    }
+}
+
+fn binance_events(frame: &Value) -> Vec<DepthEvent> {
+    // 组合流：{"stream": "btcusdt@depth", "data": {...}}
+    let (data, stream) = match (frame.get("data"), frame.get("stream").and_then(Value::as_str)) {
+        (Some(data), Some(stream)) => (data, Some(stream)),
+        _ => (frame, None),
+    };
+    if data.get("e").and_then(Value::as_str) == Some("depthUpdate") {
+        let Some(symbol) = data.get("s").and_then(Value::as_str) else {
+            return Vec::new();
+        };
+        return vec![DepthEvent {
+            symbol: symbol.to_uppercase(),
+            channel: None,
+            kind: DepthKind::Delta,
+            first_id: number(data.get("U")),
+            final_id: number(data.get("u")),
+            prev_id: number(data.get("pu")),
+            bids: levels(data.get("b")),
+            asks: levels(data.get("a")),
+            checksum: None,
+        }];
+    }
+    // 部分深度快照不带交易对，从流名称中取
+    let symbol = data
+        .get("s")
+        .and_then(Value::as_str)
+        .or_else(|| stream.and_then(|stream| stream.split('@').next()));
+    match symbol {
+        Some(symbol) => binance_snapshot(symbol, data).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+// {"lastUpdateId": 1027024, "bids": [...], "asks": [...]}（部分深度推送及 REST 快照）
+pub fn binance_snapshot(symbol: &str, data: &Value) -> Option<DepthEvent> {
+    let final_id = number(data.get("lastUpdateId"))?;
+    if data.get("bids").is_none() && data.get("asks").is_none() {
+        return None;
+    }
+    Some(DepthEvent {
+        symbol: symbol.to_uppercase(),
+        channel: None,
+        kind: DepthKind::Snapshot,
+        first_id: None,
+        final_id: Some(final_id),
+        prev_id: None,
+        bids: levels(data.get("bids")),
+        asks: levels(data.get("asks")),
+        checksum: None,
+    })
+}
+
+fn okx_events(frame: &Value) -> Vec<DepthEvent> {
+    let arg = frame.get("arg");
+    let channel = arg.and_then(|arg| arg.get("channel")).and_then(Value::as_str);
+    let symbol = arg.and_then(|arg| arg.get("instId")).and_then(Value::as_str);
+    let (Some(channel), Some(symbol)) = (channel, symbol) else {
+        return Vec::new();
+    };
+    if !channel.starts_with("books") && channel != "bbo-tbt" {
+        return Vec::new();
+    }
+    // 没有 action 的频道（books5、bbo-tbt）每次推送都是完整快照
+    let kind = match frame.get("action").and_then(Value::as_str) {
+        Some("update") => DepthKind::Delta,
+        _ => DepthKind::Snapshot,
+    };
+    frame
+        .get("data")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| DepthEvent {
+                    symbol: symbol.to_uppercase(),
+                    channel: Some(channel.to_string()),
+                    kind,
+                    first_id: None,
+                    final_id: number(item.get("seqId")),
+                    prev_id: number(item.get("prevSeqId")).filter(|prev| *prev >= 0),
+                    bids: levels(item.get("bids")),
+                    asks: levels(item.get("asks")),
+                    checksum: number(item.get("checksum")).and_then(|checksum| i32::try_from(checksum).ok()),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// OKX 校验和：前 25 档按 bid、ask 交替拼接 "价格:数量"，取 CRC32 的有符号值
+pub fn okx_checksum<'a>(
+    bids: impl Iterator<Item = (&'a str, &'a str)>,
+    asks: impl Iterator<Item = (&'a str, &'a str)>,
+    levels: usize,
+) -> i32 {
+    let bids: Vec<_> = bids.take(levels).collect();
+    let asks: Vec<_> = asks.take(levels).collect();
+    let mut parts = Vec::new();
+    for i in 0..bids.len().max(asks.len()) {
+        if let Some((price, size)) = bids.get(i) {
+            parts.push(*price);
+            parts.push(*size);
+        }
+        if let Some((price, size)) = asks.get(i) {
+            parts.push(*price);
+            parts.push(*size);
+        }
+    }
+    crc32fast::hash(parts.join(":").as_bytes()) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINANCE_DEPTH: &str = include_str!("../../../tests/fixtures/orderbook/binance_btcusdt_depth.jsonl");
+    const BINANCE_SNAPSHOT: &str = include_str!("../../../tests/fixtures/orderbook/binance_btcusdt_snapshot_100.json");
+    const OKX_BOOKS: &str = include_str!("../../../tests/fixtures/orderbook/okx_btc_usdt_books.jsonl");
+
+    fn frames(fixture: &str) -> Vec<Value> {
+        fixture.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn parses_binance_depth_updates() {
+        let frames = frames(BINANCE_DEPTH);
+        // 订阅应答不是深度数据
+        assert!(Format::Binance.events(&frames[0]).is_empty());
+
+        let events = Format::Binance.events(&frames[2]);
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.symbol, "BTCUSDT");
+        assert_eq!(event.kind, DepthKind::Delta);
+        assert_eq!((event.first_id, event.final_id, event.prev_id), (Some(100), Some(102), None));
+        assert_eq!(event.bids, [("30000.10000000".to_string(), "0.00000000".to_string())]);
+        assert_eq!(event.asks, [("30000.20000000".to_string(), "1.20000000".to_string())]);
+    }
+
+    #[test]
+    fn parses_binance_snapshots() {
+        let snapshot: Value = serde_json::from_str(BINANCE_SNAPSHOT).unwrap();
+        let event = binance_snapshot("btcusdt", &snapshot).unwrap();
+        assert_eq!(event.symbol, "BTCUSDT");
+        assert_eq!(event.kind, DepthKind::Snapshot);
+        assert_eq!(event.final_id, Some(100));
+        assert_eq!((event.bids.len(), event.asks.len()), (3, 3));
+
+        // 部分深度推送不带交易对，从流名称中取
+        let frame = serde_json::json!({ "stream": "ethusdt@depth5", "data": snapshot });
+        let events = Format::Binance.events(&frame);
+        assert_eq!(events[0].symbol, "ETHUSDT");
+        assert!(binance_snapshot("BTCUSDT", &serde_json::json!({ "lastUpdateId": 1 })).is_none());
+    }
+
+    #[test]
+    fn parses_okx_books() {
+        let frames = frames(OKX_BOOKS);
+        assert!(Format::Okx.events(&frames[0]).is_empty());
+
+        let snapshot = &Format::Okx.events(&frames[1])[0];
+        assert_eq!(snapshot.symbol, "BTC-USDT");
+        assert_eq!(snapshot.channel.as_deref(), Some("books"));
+        assert_eq!(snapshot.kind, DepthKind::Snapshot);
+        // prevSeqId 为 -1 表示没有上一条
+        assert_eq!((snapshot.final_id, snapshot.prev_id), (Some(1000), None));
+        assert_eq!(snapshot.bids[0], ("30000.1".to_string(), "1.5".to_string()));
+
+        let update = &Format::Okx.events(&frames[2])[0];
+        assert_eq!(update.kind, DepthKind::Delta);
+        assert_eq!((update.final_id, update.prev_id), (Some(1001), Some(1000)));
+    }
+
+    #[test]
+    fn computes_okx_checksums() {
+        let frames = frames(OKX_BOOKS);
+        let snapshot = &Format::Okx.events(&frames[1])[0];
+        let checksum = okx_checksum(
+            snapshot.bids.iter().map(|(price, size)| (price.as_str(), size.as_str())),
+            snapshot.asks.iter().map(|(price, size)| (price.as_str(), size.as_str())),
+            25,
+        );
+        assert_eq!(Some(checksum), snapshot.checksum);
+        assert_eq!(okx_checksum(std::iter::once(("3366.1", "7")), std::iter::once(("3366.8", "9")), 25), crc32fast::hash(b"3366.1:7:3366.8:9") as i32);
+    }
+}
@@ -0,0 +1,662 @@
+mod format;
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::{Mutex, RwLock};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::models::{ApiResponse, OrderBookLevel, OrderBookSettings, OrderBookSummary, OrderBookView, WebSocketConfig};
+use crate::service::protocol::preset::{self, ProtocolPreset};
+use format::{DepthEvent, DepthKind, Format};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// 快照拉取失败或衔接不上时，等待一段时间再重新拉取
+const SNAPSHOT_RETRY_DELAY: Duration = Duration::from_secs(3);
+// 等待快照期间最多缓存的增量数量，超出时丢弃最早的
+const BUFFER_LIMIT: usize = 1000;
+// OKX 校验和覆盖的档位数
+const OKX_CHECKSUM_LEVELS: usize = 25;
+const DEFAULT_DEPTH: usize = 20;
+
+const SYNCING: &str = "syncing";
+const LIVE: &str = "live";
+const DISCONNECTED: &str = "disconnected";
+
+type SharedBook = Arc<Mutex<Book>>;
+
+lazy_static::lazy_static! {
+    // 配置ID -> 交易对 -> 订单簿
+    static ref BOOKS: RwLock<HashMap<String, HashMap<String, SharedBook>>> = RwLock::new(HashMap::new());
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("failed to build order book http client");
+}
+
+// 价格键：按数值排序
+#[derive(Debug, Clone, Copy)]
+struct Price(f64);
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+// 一侧的档位，保留交易所推送的原始价格/数量字符串（OKX 校验和需要）
+type Side = BTreeMap<Price, OrderBookLevel>;
+
+fn update_side(side: &mut Side, levels: Vec<(String, String)>) -> Result<(), String> {
+    for (price, size) in levels {
+        let key = price.parse::<f64>().map_err(|_| format!("invalid price: {}", price))?;
+        let amount = size.parse::<f64>().map_err(|_| format!("invalid size: {}", size))?;
+        if amount == 0.0 {
+            side.remove(&Price(key));
+        } else {
+            side.insert(Price(key), OrderBookLevel { price, size });
+        }
+    }
+    Ok(())
+}
+
+fn volume<'a>(levels: impl Iterator<Item = &'a OrderBookLevel>) -> f64 {
+    levels.filter_map(|level| level.size.parse::<f64>().ok()).fold(0.0, |total, size| total + size)
+}
+
+// 一个交易对的 L2 订单簿
+struct Book {
+    bids: Side,
+    asks: Side,
+    status: &'static str,
+    last_id: Option<i64>,
+    bridged: bool, // 快照之后是否已应用过增量
+    updated_at: Option<i64>,
+    invalid_count: i64,
+    last_error: Option<String>,
+    buffer: VecDeque<DepthEvent>, // 等待快照时缓存的增量
+    fetching: bool,
+}
+
+impl Book {
+    fn new() -> Self {
+        Self {
+            bids: Side::new(),
+            asks: Side::new(),
+            status: SYNCING,
+            last_id: None,
+            bridged: false,
+            updated_at: None,
+            invalid_count: 0,
+            last_error: None,
+            buffer: VecDeque::new(),
+            fetching: false,
+        }
+    }
+
+    // 应用一次推送；返回 Err 表示订单簿已失效（序号缺口、买卖价交叉或校验和不一致）
+    fn apply(&mut self, event: DepthEvent, format: Format, max_levels: Option<usize>) -> Result<(), String> {
+        match event.kind {
+            DepthKind::Snapshot => {
+                self.bids.clear();
+                self.asks.clear();
+                update_side(&mut self.bids, event.bids)?;
+                update_side(&mut self.asks, event.asks)?;
+                self.last_id = event.final_id;
+                self.bridged = false;
+                self.status = LIVE;
+                self.updated_at = Some(chrono::Utc::now().timestamp_millis());
+                self.validate(format, event.checksum)?;
+                // 快照之前缓存的增量：旧的跳过，其余按顺序应用
+                for delta in std::mem::take(&mut self.buffer) {
+                    self.apply_delta(delta, format)?;
+                }
+            }
+            DepthKind::Delta if self.status != LIVE => {
+                if self.buffer.len() >= BUFFER_LIMIT {
+                    self.buffer.pop_front();
+                }
+                self.buffer.push_back(event);
+                return Ok(());
+            }
+            DepthKind::Delta => self.apply_delta(event, format)?,
+        }
+        self.trim(max_levels);
+        Ok(())
+    }
+
+    fn apply_delta(&mut self, event: DepthEvent, format: Format) -> Result<(), String> {
+        if let Some(last) = self.last_id {
+            // 已包含在快照或之前的增量中
+            if event.final_id.is_some_and(|id| id <= last) {
+                return Ok(());
+            }
+            let gap = match (event.prev_id, event.first_id) {
+                // 快照后的第一条增量按 first_id 衔接，之后要求上一序号连续
+                (Some(prev), first) if self.bridged || first.is_none() => {
+                    (prev != last).then(|| format!("sequence gap: expected previous {}, got {}", last, prev))
+                }
+                (_, Some(first)) => {
+                    (first > last + 1).then(|| format!("sequence gap: expected update {}, got {}", last + 1, first))
+                }
+                _ => None,
+            };
+            if let Some(gap) = gap {
+                return Err(gap);
+            }
+        }
+        update_side(&mut self.bids, event.bids)?;
+        update_side(&mut self.asks, event.asks)?;
+        if event.final_id.is_some() {
+            self.last_id = event.final_id;
+        }
+        self.bridged = true;
+        self.updated_at = Some(chrono::Utc::now().timestamp_millis());
+        self.validate(format, event.checksum)
+    }
+
+    fn validate(&self, format: Format, checksum: Option<i32>) -> Result<(), String> {
+        if let (Some((bid, _)), Some((ask, _))) = (self.bids.last_key_value(), self.asks.first_key_value()) {
+            if bid >= ask {
+                return Err(format!("crossed book: bid {} >= ask {}", bid.0, ask.0));
+            }
+        }
+        if let (Format::Okx, Some(expected)) = (format, checksum) {
+            let actual = format::okx_checksum(
+                self.bids.values().rev().map(|level| (level.price.as_str(), level.size.as_str())),
+                self.asks.values().map(|level| (level.price.as_str(), level.size.as_str())),
+                OKX_CHECKSUM_LEVELS,
+            );
+            if actual != expected {
+                return Err(format!("checksum mismatch: expected {}, got {}", expected, actual));
+            }
+        }
+        Ok(())
+    }
+
+    // 只保留最优的 max_levels 档
+    fn trim(&mut self, max_levels: Option<usize>) {
+        let Some(max_levels) = max_levels else {
+            return;
+        };
+        while self.bids.len() > max_levels {
+            self.bids.pop_first();
+        }
+        while self.asks.len() > max_levels {
+            self.asks.pop_last();
+        }
+    }
+
+    // 订单簿失效：记录原因并清空，等待新的快照
+    fn invalidate(&mut self, reason: String) {
+        self.invalid_count += 1;
+        self.last_error = Some(reason);
+        self.bids.clear();
+        self.asks.clear();
+        self.buffer.clear();
+        self.last_id = None;
+        self.bridged = false;
+        self.status = SYNCING;
+    }
+
+    fn summary(&self, depth: usize) -> OrderBookSummary {
+        let best_bid = self.bids.last_key_value().map(|(price, _)| price.0);
+        let best_ask = self.asks.first_key_value().map(|(price, _)| price.0);
+        let (spread, mid) = match (best_bid, best_ask) {
+            (Some(bid), Some(ask)) => (Some(ask - bid), Some((ask + bid) / 2.0)),
+            _ => (None, None),
+        };
+        let bid_volume = volume(self.bids.values().rev().take(depth));
+        let ask_volume = volume(self.asks.values().take(depth));
+        let total = bid_volume + ask_volume;
+        OrderBookSummary {
+            best_bid,
+            best_ask,
+            spread,
+            mid,
+            spread_bps: spread.zip(mid).filter(|(_, mid)| *mid > 0.0).map(|(spread, mid)| spread / mid * 10_000.0),
+            bid_volume,
+            ask_volume,
+            imbalance: (total > 0.0).then(|| (bid_volume - ask_volume) / total),
+        }
+    }
+
+    // levels 为返回的档位数，summary_depth 为摘要统计的档位数
+    fn view(&self, config_id: &str, symbol: &str, levels: usize, summary_depth: usize) -> OrderBookView {
+        OrderBookView {
+            config_id: config_id.to_string(),
+            symbol: symbol.to_string(),
+            status: self.status.to_string(),
+            last_update_id: self.last_id,
+            updated_at: self.updated_at,
+            invalid_count: self.invalid_count,
+            last_error: self.last_error.clone(),
+            bids: self.bids.values().rev().take(levels).cloned().collect(),
+            asks: self.asks.values().take(levels).cloned().collect(),
+            summary: self.summary(summary_depth),
+        }
+    }
+}
+
+// 按配置维护订单簿：解析深度推送并更新，失效时重新拉取快照或重新订阅
+pub struct OrderBookFeed {
+    config_id: String,
+    format: Format,
+    snapshot_url: Option<String>,
+    max_levels: Option<usize>,
+    preset: Option<Box<dyn ProtocolPreset>>,
+    next_id: u64,
+}
+
+impl OrderBookFeed {
+    // 按配置中的 orderbook 创建，未配置时返回 None
+    pub fn from_config(config: &WebSocketConfig) -> Result<Option<Self>, String> {
+        match config.orderbook.as_deref() {
+            Some(raw) if !raw.trim().is_empty() => {
+                let settings: OrderBookSettings =
+                    serde_json::from_str(raw).map_err(|e| format!("invalid orderbook: {}", e))?;
+                Self::new(config, &settings).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn new(config: &WebSocketConfig, settings: &OrderBookSettings) -> Result<Self, String> {
+        let format = Format::parse(settings.format.as_deref().unwrap_or(&config.protocol))?;
+        if let Some(max_levels) = settings.max_levels {
+            let min_levels = if format == Format::Okx { OKX_CHECKSUM_LEVELS } else { 1 };
+            if max_levels < min_levels {
+                return Err(format!("max_levels must be at least {}", min_levels));
+            }
+        }
+        let snapshot_url = settings.snapshot_url.clone().filter(|url| !url.trim().is_empty());
+        if snapshot_url.is_some() && format != Format::Binance {
+            return Err("snapshot_url is only used by the binance format".to_string());
+        }
+        Ok(Self {
+            config_id: config.id.clone(),
+            format,
+            snapshot_url,
+            max_levels: settings.max_levels,
+            preset: preset::find_preset(&config.protocol),
+            next_id: 0,
+        })
+    }
+
+    // 连接建立时丢弃该配置上一次连接的订单簿
+    pub async fn reset(&self) {
+        BOOKS.write().await.remove(&self.config_id);
+    }
+
+    // 处理一条接收消息，返回需要发送的帧（OKX 订单簿失效时重新订阅以获取新快照）
+    pub async fn handle(&mut self, content: &str) -> Vec<Message> {
+        let Ok(frame) = serde_json::from_str::<Value>(content) else {
+            return Vec::new();
+        };
+        let mut frames = Vec::new();
+        for event in self.format.events(&frame) {
+            let symbol = event.symbol.clone();
+            let channel = event.channel.clone();
+            let shared = book(&self.config_id, &symbol).await;
+            let mut book = shared.lock().await;
+            if let Err(reason) = book.apply(event, self.format, self.max_levels) {
+                tracing::warn!("Order book {} of {} is invalid, rebuilding: {}", symbol, self.config_id, reason);
+                book.invalidate(reason);
+                if let Some(channel) = channel {
+                    frames.extend(self.resubscribe(&symbol, &channel));
+                }
+            }
+            // Binance 增量流需要 REST 快照作为起点
+            if book.status == SYNCING && !book.buffer.is_empty() && !book.fetching {
+                if let Some(url) = &self.snapshot_url {
+                    book.fetching = true;
+                    let url = url.replace("{symbol}", &symbol);
+                    spawn_snapshot(shared.clone(), url, symbol, self.config_id.clone(), self.max_levels);
+                }
+            }
+        }
+        frames
+    }
+
+    fn resubscribe(&mut self, symbol: &str, channel: &str) -> Vec<Message> {
+        let Some(preset) = &self.preset else {
+            return Vec::new();
+        };
+        let symbols = [symbol.to_string()];
+        let channels = [channel.to_string()];
+        self.next_id += 1;
+        let unsubscribe = preset.unsubscribe_message(&symbols, &channels, self.next_id);
+        self.next_id += 1;
+        let subscribe = preset.subscribe_message(&symbols, &channels, self.next_id);
+        vec![Message::Text(unsubscribe.to_string()), Message::Text(subscribe.to_string())]
+    }
+}
+
+async fn book(config_id: &str, symbol: &str) -> SharedBook {
+    if let Some(book) = BOOKS.read().await.get(config_id).and_then(|books| books.get(symbol)) {
+        return book.clone();
+    }
+    BOOKS
+        .write()
+        .await
+        .entry(config_id.to_string())
+        .or_default()
+        .entry(symbol.to_string())
+        .or_insert_with(|| Arc::new(Mutex::new(Book::new())))
+        .clone()
+}
+
+async fn fetch_snapshot(url: &str, symbol: &str) -> Result<DepthEvent, String> {
+    let response = CLIENT.get(url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let body: Value = response.json().await.map_err(|e| e.to_string())?;
+    format::binance_snapshot(symbol, &body).ok_or_else(|| "snapshot has no lastUpdateId".to_string())
+}
+
+// 后台拉取 REST 快照并与缓存的增量衔接
+fn spawn_snapshot(shared: SharedBook, url: String, symbol: String, config_id: String, max_levels: Option<usize>) {
+    tokio::spawn(async move {
+        let snapshot = fetch_snapshot(&url, &symbol).await;
+        let mut book = shared.lock().await;
+        match snapshot.map(|snapshot| book.apply(snapshot, Format::Binance, max_levels)) {
+            Ok(Ok(())) => {
+                book.fetching = false;
+                return;
+            }
+            // 快照与缓存的增量衔接不上
+            Ok(Err(reason)) => {
+                tracing::warn!("Order book {} of {} is invalid, rebuilding: {}", symbol, config_id, reason);
+                book.invalidate(reason);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch order book snapshot for {}: {}", symbol, e);
+                book.last_error = Some(format!("snapshot: {}", e));
+            }
+        }
+        drop(book);
+        // 等待一段时间，之后收到的增量会再次触发拉取
+        tokio::time::sleep(SNAPSHOT_RETRY_DELAY).await;
+        shared.lock().await.fetching = false;
+    });
+}
+
+// 连接断开：保留订单簿供查看，状态标记为 disconnected
+pub async fn close(config_id: &str) {
+    if let Some(books) = BOOKS.read().await.get(config_id) {
+        for book in books.values() {
+            book.lock().await.status = DISCONNECTED;
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DepthQuery {
+    pub depth: Option<usize>,
+}
+
+// 获取配置下全部订单簿（默认不返回档位，只返回摘要）
+pub async fn list_books(
+    Path(config_id): Path<String>,
+    Query(query): Query<DepthQuery>,
+) -> Result<Json<ApiResponse<Vec<OrderBookView>>>, StatusCode> {
+    let books: Vec<(String, SharedBook)> = match BOOKS.read().await.get(&config_id) {
+        Some(books) => books.iter().map(|(symbol, book)| (symbol.clone(), book.clone())).collect(),
+        None => Vec::new(),
+    };
+    let levels = query.depth.unwrap_or(0);
+    let mut views = Vec::with_capacity(books.len());
+    for (symbol, book) in books {
+        views.push(book.lock().await.view(&config_id, &symbol, levels, DEFAULT_DEPTH));
+    }
+    views.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    Ok(Json(ApiResponse::ok(views)))
+}
+
+async fn find_book(config_id: &str, symbol: &str) -> Result<SharedBook, StatusCode> {
+    BOOKS
+        .read()
+        .await
+        .get(config_id)
+        .and_then(|books| books.get(&symbol.to_uppercase()))
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// 获取订单簿前 depth 档及摘要
+pub async fn get_book(
+    Path((config_id, symbol)): Path<(String, String)>,
+    Query(query): Query<DepthQuery>,
+) -> Result<Json<ApiResponse<OrderBookView>>, StatusCode> {
+    let book = find_book(&config_id, &symbol).await?;
+    let depth = query.depth.unwrap_or(DEFAULT_DEPTH);
+    let view = book.lock().await.view(&config_id, &symbol.to_uppercase(), depth, depth);
+    Ok(Json(ApiResponse::ok(view)))
+}
+
+// 获取订单簿摘要：最优买卖价、价差、中间价及前 depth 档的买卖量失衡
+pub async fn get_summary(
+    Path((config_id, symbol)): Path<(String, String)>,
+    Query(query): Query<DepthQuery>,
+) -> Result<Json<ApiResponse<OrderBookSummary>>, StatusCode> {
+    let book = find_book(&config_id, &symbol).await?;
+    let summary = book.lock().await.summary(query.depth.unwrap_or(DEFAULT_DEPTH));
+    Ok(Json(ApiResponse::ok(summary)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::get as get_route, Router};
+
+    const BINANCE_DEPTH: &str = include_str!("../../../tests/fixtures/orderbook/binance_btcusdt_depth.jsonl");
+    const BINANCE_SNAPSHOT_100: &str = include_str!("../../../tests/fixtures/orderbook/binance_btcusdt_snapshot_100.json");
+    const BINANCE_SNAPSHOT_200: &str = include_str!("../../../tests/fixtures/orderbook/binance_btcusdt_snapshot_200.json");
+    const OKX_BOOKS: &str = include_str!("../../../tests/fixtures/orderbook/okx_btc_usdt_books.jsonl");
+
+    fn config(id: &str, protocol: &str) -> WebSocketConfig {
+        WebSocketConfig {
+            id: id.to_string(),
+            name: "orderbook".to_string(),
+            description: None,
+            ws_url: String::new(),
+            config_type: "subscriber".to_string(),
+            headers: None,
+            auth_token: None,
+            message_template: None,
+            auto_reconnect: false,
+            status: "inactive".to_string(),
+            protocol: protocol.to_string(),
+            protocol_options: None,
+            filters: None,
+            message_schema: None,
+            transforms: None,
+            outbox: None,
+            sequence: None,
+            orderbook: None,
+            candles: None,
+            state_store: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn levels(levels: &[OrderBookLevel]) -> Vec<(&str, &str)> {
+        levels.iter().map(|level| (level.price.as_str(), level.size.as_str())).collect()
+    }
+
+    fn events(format: Format, line: &str) -> Vec<DepthEvent> {
+        format.events(&serde_json::from_str(line).unwrap())
+    }
+
+    async fn view(config_id: &str, symbol: &str) -> OrderBookView {
+        find_book(config_id, symbol).await.unwrap().lock().await.view(config_id, symbol, DEFAULT_DEPTH, DEFAULT_DEPTH)
+    }
+
+    // 等待后台快照拉取完成
+    async fn wait_live(config_id: &str, symbol: &str) -> OrderBookView {
+        for _ in 0..100 {
+            let view = view(config_id, symbol).await;
+            if view.status == LIVE {
+                return view;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("order book {} did not become live", symbol);
+    }
+
+    #[test]
+    fn applies_binance_deltas_after_snapshot() {
+        let frames: Vec<&str> = BINANCE_DEPTH.lines().collect();
+        let snapshot = format::binance_snapshot("BTCUSDT", &serde_json::from_str(BINANCE_SNAPSHOT_100).unwrap()).unwrap();
+        let mut book = Book::new();
+        book.apply(snapshot, Format::Binance, None).unwrap();
+        // 第一条增量已包含在快照中，其余两条衔接快照
+        for frame in &frames[1..4] {
+            for event in events(Format::Binance, frame) {
+                book.apply(event, Format::Binance, None).unwrap();
+            }
+        }
+
+        let view = book.view("config", "BTCUSDT", DEFAULT_DEPTH, DEFAULT_DEPTH);
+        assert_eq!(view.status, LIVE);
+        assert_eq!(view.last_update_id, Some(104));
+        // 数量为 0 的 30000.1 买档和 30000.3 卖档被删除
+        assert_eq!(
+            levels(&view.bids),
+            [("30000.05000000", "0.70000000"), ("30000.00000000", "2.00000000"), ("29999.90000000", "0.50000000")]
+        );
+        assert_eq!(levels(&view.asks), [("30000.20000000", "1.20000000"), ("30000.40000000", "0.80000000")]);
+
+        let gap = events(Format::Binance, frames[4]).remove(0);
+        let error = book.apply(gap, Format::Binance, None).unwrap_err();
+        assert!(error.contains("sequence gap"), "{}", error);
+    }
+
+    #[test]
+    fn applies_okx_updates_with_checksums() {
+        let frames: Vec<&str> = OKX_BOOKS.lines().collect();
+        let mut book = Book::new();
+        for frame in &frames[1..4] {
+            for event in events(Format::Okx, frame) {
+                book.apply(event, Format::Okx, None).unwrap();
+            }
+        }
+
+        let view = book.view("config", "BTC-USDT", DEFAULT_DEPTH, DEFAULT_DEPTH);
+        assert_eq!(view.last_update_id, Some(1002));
+        assert_eq!(levels(&view.bids), [("30000.05", "0.7"), ("30000", "2"), ("29999.9", "0.5")]);
+        assert_eq!(levels(&view.asks), [("30000.2", "1.2"), ("30000.4", "0.8")]);
+
+        let gap = events(Format::Okx, frames[4]).remove(0);
+        let error = book.apply(gap, Format::Okx, None).unwrap_err();
+        assert!(error.contains("sequence gap"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn resubscribes_okx_book_after_gap() {
+        let config = config("orderbook-okx-test", "okx");
+        let settings = OrderBookSettings { format: None, snapshot_url: None, max_levels: None };
+        let mut feed = OrderBookFeed::new(&config, &settings).unwrap();
+        let frames: Vec<&str> = OKX_BOOKS.lines().collect();
+        for frame in &frames[..4] {
+            assert!(feed.handle(frame).await.is_empty());
+        }
+        assert_eq!(view(&config.id, "BTC-USDT").await.status, LIVE);
+
+        // 序号缺口：清空订单簿并重新订阅以获取新快照
+        let replies = feed.handle(frames[4]).await;
+        let replies: Vec<Value> = replies
+            .into_iter()
+            .map(|reply| serde_json::from_str(&reply.into_text().unwrap()).unwrap())
+            .collect();
+        let args = serde_json::json!([{ "channel": "books", "instId": "BTC-USDT" }]);
+        assert_eq!(replies, [
+            serde_json::json!({ "op": "unsubscribe", "args": args }),
+            serde_json::json!({ "op": "subscribe", "args": args }),
+        ]);
+        let view = view(&config.id, "BTC-USDT").await;
+        assert_eq!(view.status, SYNCING);
+        assert_eq!(view.invalid_count, 1);
+        assert!(view.bids.is_empty() && view.asks.is_empty());
+
+        assert!(feed.handle(frames[5]).await.is_empty());
+        let view = self::view(&config.id, "BTC-USDT").await;
+        assert_eq!(view.status, LIVE);
+        assert_eq!(view.last_update_id, Some(2000));
+        assert_eq!(levels(&view.bids), [("30001", "1"), ("30000.9", "2")]);
+        assert_eq!(levels(&view.asks), [("30001.1", "0.5"), ("30001.2", "1")]);
+    }
+
+    async fn serve_snapshots(
+        State(snapshots): State<Arc<Mutex<VecDeque<&'static str>>>>,
+        Query(query): Query<HashMap<String, String>>,
+    ) -> String {
+        assert_eq!(query.get("symbol").map(String::as_str), Some("BTCUSDT"));
+        snapshots.lock().await.pop_front().expect("unexpected snapshot request").to_string()
+    }
+
+    #[tokio::test]
+    async fn refetches_binance_snapshot_after_gap() {
+        // 本地 REST 端点依次返回两份快照
+        let snapshots = Arc::new(Mutex::new(VecDeque::from([BINANCE_SNAPSHOT_100, BINANCE_SNAPSHOT_200])));
+        let router = Router::new().route("/depth", get_route(serve_snapshots)).with_state(snapshots.clone());
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let snapshot_url = format!("http://{}/depth?symbol={{symbol}}&limit=1000", tcp.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(tcp, router).await.unwrap() });
+
+        let config = config("orderbook-binance-test", "binance");
+        let settings = OrderBookSettings { format: None, snapshot_url: Some(snapshot_url), max_levels: None };
+        let mut feed = OrderBookFeed::new(&config, &settings).unwrap();
+        let frames: Vec<&str> = BINANCE_DEPTH.lines().collect();
+
+        // 第一条增量触发快照拉取
+        assert!(feed.handle(frames[0]).await.is_empty());
+        assert!(feed.handle(frames[1]).await.is_empty());
+        wait_live(&config.id, "BTCUSDT").await;
+        for frame in &frames[2..4] {
+            feed.handle(frame).await;
+        }
+        let view = view(&config.id, "BTCUSDT").await;
+        assert_eq!(view.last_update_id, Some(104));
+        assert_eq!(levels(&view.asks), [("30000.20000000", "1.20000000"), ("30000.40000000", "0.80000000")]);
+
+        feed.handle(frames[4]).await;
+        let view = self::view(&config.id, "BTCUSDT").await;
+        assert_eq!(view.status, SYNCING);
+        assert_eq!(view.invalid_count, 1);
+        assert!(view.last_error.unwrap().contains("sequence gap"));
+
+        // 失效后的增量重新拉取快照，并在其之上应用
+        feed.handle(frames[5]).await;
+        let view = wait_live(&config.id, "BTCUSDT").await;
+        assert_eq!(view.last_update_id, Some(201));
+        assert_eq!(levels(&view.bids), [("30001.00000000", "1.50000000"), ("30000.90000000", "2.00000000")]);
+        assert_eq!(levels(&view.asks), [("30001.20000000", "1.00000000")]);
+        assert!(snapshots.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{ApiResponse, OutboxSettings, SendMessageRequest, WebSocketConfig, WebSocketMessage};
+use crate::service::websocket_manager::WEBSOCKET_MANAGER;
+
+// 每次从数据库取出的待发送消息数量
+const FLUSH_BATCH: i64 = 100;
+
+lazy_static::lazy_static! {
+    // 配置ID -> 发送锁，保证同一配置的待发送消息按顺序逐条发送
+    static ref FLUSH_LOCKS: Mutex<HashMap<String, Arc<Mutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+// 解析配置中的发件箱设置，未启用时返回 None
+pub fn settings(config: &WebSocketConfig) -> Result<Option<OutboxSettings>, String> {
+    match config.outbox.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => {
+            let settings: OutboxSettings = serde_json::from_str(raw).map_err(|e| format!("invalid outbox: {}", e))?;
+            check(&settings)?;
+            Ok(Some(settings))
+        }
+        _ => Ok(None),
+    }
+}
+
+pub fn check(settings: &OutboxSettings) -> Result<(), String> {
+    if settings.ttl_secs.is_some_and(|ttl| ttl <= 0) {
+        return Err("ttl_secs must be positive".to_string());
+    }
+    if settings.max_queue.is_some_and(|max| max <= 0) {
+        return Err("max_queue must be positive".to_string());
+    }
+    Ok(())
+}
+
+async fn pending_count(pool: &SqlitePool, config_id: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM t_websocket_message WHERE config_id = ? AND status = 'pending' AND session_id IS NULL")
+        .bind(config_id)
+        .fetch_one(pool)
+        .await
+}
+
+// 是否还有待发送的消息（有则新消息也要排队，保持发送顺序）
+pub async fn has_pending(pool: &SqlitePool, config_id: &str) -> Result<bool, StatusCode> {
+    pending_count(pool, config_id).await.map(|count| count > 0).map_err(|e| {
+        tracing::error!("Failed to count pending messages: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// 把发送请求存为 pending 消息（连同 qos、retain）；队列已满时返回 429
+// 数量检查和写入在同一条语句中完成，并发入队也不会超过 max_queue
+pub async fn enqueue(pool: &SqlitePool, request: &SendMessageRequest, settings: &OutboxSettings) -> Result<String, StatusCode> {
+    let id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now();
+    let result = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_message
+        (id, config_id, message_type, content, timestamp, status, topic, event, timestamp_us, qos, retain)
+        SELECT ?, ?, 'sent', ?, ?, 'pending', ?, ?, ?, ?, ?
+        WHERE ? IS NULL OR (
+            SELECT COUNT(*) FROM t_websocket_message WHERE config_id = ? AND status = 'pending' AND session_id IS NULL
+        ) < ?
+        "#
+    )
+    .bind(&id)
+    .bind(&request.config_id)
+    .bind(&request.message)
+    .bind(now.timestamp())
+    .bind(&request.topic)
+    .bind(&request.event)
+    .bind(now.timestamp_micros())
+    .bind(request.qos.map(i64::from))
+    .bind(request.retain)
+    .bind(settings.max_queue)
+    .bind(&request.config_id)
+    .bind(settings.max_queue)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to queue message: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if result.rows_affected() == 0 {
+        tracing::warn!("Outbox of {} is full", request.config_id);
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+    Ok(id)
+}
+
+// 在后台发送待发送消息
+pub fn spawn_flush(pool: SqlitePool, config_id: String) {
+    tokio::spawn(async move { flush(&pool, &config_id).await });
+}
+
+async fn fail(pool: &SqlitePool, id: &str, error: String) {
+    if let Err(e) = sqlx::query("UPDATE t_websocket_message SET status = 'failed', error_message = ? WHERE id = ?")
+        .bind(error)
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        tracing::error!("Failed to update pending message: {}", e);
+    }
+}
+
+async fn set_session(pool: &SqlitePool, id: &str, session_id: Option<&str>) {
+    if let Err(e) = sqlx::query("UPDATE t_websocket_message SET session_id = ? WHERE id = ?")
+        .bind(session_id)
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        tracing::error!("Failed to update pending message: {}", e);
+    }
+}
+
+// 把尚未交给连接的消息放回发件箱
+pub async fn release(pool: &SqlitePool, id: &str) {
+    set_session(pool, id, None).await;
+}
+
+// 按入队顺序把待发送消息交给连接：过期的标记为 failed，连接断开时停止，剩余消息等下次连接
+// 消息写出后由发送任务更新为 success 或 failed
+pub async fn flush(pool: &SqlitePool, config_id: &str) {
+    let lock = FLUSH_LOCKS.lock().await.entry(config_id.to_string()).or_default().clone();
+    let _guard = lock.lock().await;
+
+    let ttl = match sqlx::query_as::<_, WebSocketConfig>("SELECT * FROM t_websocket_config WHERE id = ?")
+        .bind(config_id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(config) => settings(&config).ok().flatten().and_then(|settings| settings.ttl_secs),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket config: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        let pending = match sqlx::query_as::<_, WebSocketMessage>(
+            r#"
+            SELECT * FROM t_websocket_message
+            WHERE config_id = ? AND status = 'pending' AND session_id IS NULL
+            ORDER BY timestamp_us, rowid LIMIT ?
+            "#
+        )
+        .bind(config_id)
+        .bind(FLUSH_BATCH)
+        .fetch_all(pool)
+        .await
+        {
+            Ok(pending) => pending,
+            Err(e) => {
+                tracing::error!("Failed to fetch pending messages: {}", e);
+                return;
+            }
+        };
+        if pending.is_empty() {
+            return;
+        }
+
+        for message in pending {
+            let now = chrono::Utc::now().timestamp();
+            if ttl.is_some_and(|ttl| now - message.timestamp > ttl) {
+                fail(pool, &message.id, "expired".to_string()).await;
+                continue;
+            }
+
+            let Some(info) = WEBSOCKET_MANAGER.get_connection_status(config_id).await.filter(|info| info.is_connected) else {
+                return;
+            };
+            // 交给连接前写入会话ID，写出失败重新排队时会清空
+            set_session(pool, &message.id, Some(&info.session_id)).await;
+            let request = SendMessageRequest {
+                config_id: config_id.to_string(),
+                message: message.content.clone(),
+                custom_headers: None,
+                topic: message.topic.clone(),
+                event: message.event.clone(),
+                qos: message.qos.and_then(|qos| u8::try_from(qos).ok()),
+                retain: message.retain,
+            };
+            match WEBSOCKET_MANAGER.send_message(request, Some(message.id.clone())).await {
+                Ok(()) => {}
+                // 连接在发送前断开：放回发件箱
+                Err(_) if WEBSOCKET_MANAGER.get_connection_status(config_id).await.is_none() => {
+                    release(pool, &message.id).await;
+                    return;
+                }
+                Err(e) => fail(pool, &message.id, e.to_string()).await,
+            }
+        }
+    }
+}
+
+// 取消待发送消息（标记为 failed），message_id 为空时取消全部
+pub async fn cancel(pool: &SqlitePool, config_id: &str, message_id: Option<&str>, reason: &str) -> Result<u64, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE t_websocket_message SET status = 'failed', error_message = ?
+        WHERE config_id = ? AND status = 'pending' AND session_id IS NULL AND (? IS NULL OR id = ?)
+        "#
+    )
+    .bind(reason)
+    .bind(config_id)
+    .bind(message_id)
+    .bind(message_id)
+    .execute(pool)
+    .await
+    .map(|result| result.rows_affected())
+}
+
+// 获取配置的待发送消息（按发送顺序）
+pub async fn list_pending(
+    Path(config_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<WebSocketMessage>>>, StatusCode> {
+    match sqlx::query_as::<_, WebSocketMessage>(
+        "SELECT * FROM t_websocket_message WHERE config_id = ? AND status = 'pending' AND session_id IS NULL ORDER BY timestamp_us, rowid"
+    )
+    .bind(&config_id)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(messages) => Ok(Json(ApiResponse::ok(messages))),
+        Err(e) => {
+            tracing::error!("Failed to fetch pending messages: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 取消配置的全部待发送消息，返回取消的数量
+pub async fn cancel_all(
+    Path(config_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<u64>>, StatusCode> {
+    match cancel(&state.pool, &config_id, None, "cancelled").await {
+        Ok(cancelled) => Ok(Json(ApiResponse::ok(cancelled))),
+        Err(e) => {
+            tracing::error!("Failed to cancel pending messages: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 取消单条待发送消息
+pub async fn cancel_message(
+    Path((config_id, message_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match cancel(&state.pool, &config_id, Some(&message_id), "cancelled").await {
+        Ok(0) => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(Json(ApiResponse::ok(()))),
+        Err(e) => {
+            tracing::error!("Failed to cancel pending message: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
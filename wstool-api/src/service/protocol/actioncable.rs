@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use super::{AdapterResult, Decoded, Inbound, ProtocolAdapter};
+use crate::models::{SendMessageRequest, WebSocketConfig};
+
+/// Rails ActionCable 客户端
+///
+/// filters 为频道标识列表，可以是对象（`{"channel": "PricesChannel", "symbol": "BTC"}`）
+/// 或已序列化的标识字符串。消息按标识字符串作为 topic 保存。
+pub struct ActionCableAdapter {
+    welcomed: bool,
+    // 收到 welcome 之前排队的订阅
+    queued: Vec<String>,
+    confirmed: HashSet<String>,
+}
+
+impl ActionCableAdapter {
+    pub fn new(_config: &WebSocketConfig, _options: &Value) -> AdapterResult<Self> {
+        Ok(Self {
+            welcomed: false,
+            queued: Vec::new(),
+            confirmed: HashSet::new(),
+        })
+    }
+
+    fn subscribe_all(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let items = match filters {
+            Value::Array(items) => items.clone(),
+            Value::Null => Vec::new(),
+            other => vec![other.clone()],
+        };
+        for item in items {
+            self.queued.push(identifier(&item)?);
+        }
+        if !self.welcomed {
+            return Ok(Vec::new());
+        }
+        Ok(self.flush_queued())
+    }
+
+    // 取消订阅已确认的频道，尚未发送的订阅直接移出队列
+    fn unsubscribe_all(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let items = match filters {
+            Value::Array(items) => items.clone(),
+            Value::Null => Vec::new(),
+            other => vec![other.clone()],
+        };
+        let mut frames = Vec::new();
+        for item in items {
+            let identifier = identifier(&item)?;
+            self.queued.retain(|queued| *queued != identifier);
+            if self.confirmed.remove(&identifier) {
+                frames.push(Message::Text(
+                    json!({ "command": "unsubscribe", "identifier": identifier }).to_string(),
+                ));
+            }
+        }
+        Ok(frames)
+    }
+
+    fn flush_queued(&mut self) -> Vec<Message> {
+        self.queued
+            .drain(..)
+            .map(|identifier| {
+                Message::Text(json!({ "command": "subscribe", "identifier": identifier }).to_string())
+            })
+            .collect()
+    }
+}
+
+impl ProtocolAdapter for ActionCableAdapter {
+    fn subprotocol(&self) -> Option<&'static str> {
+        Some("actioncable-v1-json")
+    }
+
+    fn on_open(&mut self, filters: Option<&Value>) -> AdapterResult<Vec<Message>> {
+        match filters {
+            Some(filters) => self.subscribe_all(filters),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn subscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        self.subscribe_all(filters)
+    }
+
+    fn unsubscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        self.unsubscribe_all(filters)
+    }
+
+    // topic 为频道标识时按 message 命令发送，event 作为 action；否则原样发送
+    fn encode_outgoing(&mut self, request: &SendMessageRequest) -> AdapterResult<Vec<Message>> {
+        let Some(topic) = &request.topic else {
+            return Ok(vec![Message::Text(request.message.clone())]);
+        };
+        let identifier = match serde_json::from_str::<Value>(topic) {
+            Ok(value) if value.is_object() => identifier(&value)?,
+            _ => topic.clone(),
+        };
+        if !self.confirmed.contains(&identifier) {
+            return Err(format!("ActionCable channel not subscribed: {}", identifier).into());
+        }
+        let mut data: Value = serde_json::from_str(&request.message)?;
+        if let (Some(action), Some(obj)) = (&request.event, data.as_object_mut()) {
+            obj.insert("action".to_string(), Value::String(action.clone()));
+        }
+        Ok(vec![Message::Text(
+            json!({ "command": "message", "identifier": identifier, "data": data.to_string() }).to_string(),
+        )])
+    }
+
+    fn decode_incoming(&mut self, message: Message) -> AdapterResult<Decoded> {
+        let raw = match message {
+            Message::Text(text) => text,
+            _ => return Ok(Decoded::default()),
+        };
+        // 非 JSON 帧不断开连接，原样保存
+        let Ok(envelope) = serde_json::from_str::<Value>(&raw) else {
+            tracing::warn!("Received non-JSON ActionCable frame: {}", raw);
+            return Ok(Decoded::message(None, raw));
+        };
+        let identifier = envelope.get("identifier").and_then(Value::as_str).map(str::to_string);
+
+        let mut decoded = Decoded::default();
+        match envelope.get("type").and_then(Value::as_str) {
+            Some("welcome") => {
+                self.welcomed = true;
+                decoded.replies = self.flush_queued();
+            }
+            Some("ping") => {}
+            Some("disconnect") => {
+                let reason = envelope.get("reason").and_then(Value::as_str).unwrap_or("unknown");
+                return Err(format!("ActionCable server disconnected: {}", reason).into());
+            }
+            Some(kind @ ("confirm_subscription" | "reject_subscription")) => {
+                if let Some(identifier) = &identifier {
+                    if kind == "confirm_subscription" {
+                        self.confirmed.insert(identifier.clone());
+                    } else {
+                        self.confirmed.remove(identifier);
+                        tracing::warn!("ActionCable subscription rejected: {}", identifier);
+                    }
+                }
+                decoded.messages.push(Inbound {
+                    topic: identifier,
+                    event: Some(kind.to_string()),
+                    content: raw,
+                });
+            }
+            _ => {
+                // 频道广播：只保存 message 部分
+                let content = envelope
+                    .get("message")
+                    .map(Value::to_string)
+                    .unwrap_or(raw);
+                decoded.messages.push(Inbound {
+                    topic: identifier,
+                    event: Some("message".to_string()),
+                    content,
+                });
+            }
+        }
+        Ok(decoded)
+    }
+}
+
+// 频道标识需要是 JSON 字符串；对象形式会被序列化
+fn identifier(value: &Value) -> AdapterResult<String> {
+    match value {
+        Value::String(identifier) => Ok(identifier.clone()),
+        Value::Object(obj) if obj.contains_key("channel") => Ok(value.to_string()),
+        other => Err(format!("Invalid ActionCable channel identifier: {}", other).into()),
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use super::{AdapterResult, Decoded, Inbound, ProtocolAdapter};
+use crate::models::{SendMessageRequest, WebSocketConfig};
+
+// 新协议（graphql-ws 库）与旧协议（subscriptions-transport-ws 库）
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Variant {
+    TransportWs,
+    Legacy,
+}
+
+/// GraphQL over WebSocket 订阅客户端
+///
+/// protocol_options 支持：`subprotocol`（"graphql-transport-ws" 默认，或旧版 "graphql-ws"）、
+/// `connection_init`（握手 payload）、`ping_interval`（秒，仅新协议）。
+/// filters 为订阅列表：`[{"id": "prices", "query": "...", "variables": {...}}]`，
+/// 每个操作 id 对应消息表中的一个 topic。
+pub struct GraphqlAdapter {
+    variant: Variant,
+    init_payload: Value,
+    ping_interval: Option<Duration>,
+    acknowledged: bool,
+    // 等待 connection_ack 的操作：(payload, 是否来自发送请求)
+    pending: VecDeque<(Value, bool)>,
+    // 已发送且未结束的操作：(操作 id, payload)
+    active: Vec<(String, Value)>,
+    next_id: u64,
+}
+
+impl GraphqlAdapter {
+    pub fn new(config: &WebSocketConfig, options: &Value) -> AdapterResult<Self> {
+        let variant = match options.get("subprotocol").and_then(Value::as_str) {
+            None | Some("graphql-transport-ws") => Variant::TransportWs,
+            Some("graphql-ws") => Variant::Legacy,
+            Some(other) => return Err(format!("Unsupported GraphQL subprotocol: {}", other).into()),
+        };
+
+        let mut init_payload = options
+            .get("connection_init")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        if !init_payload.is_object() {
+            return Err("connection_init must be a JSON object".into());
+        }
+        // 未显式配置时，把 auth_token 作为 Authorization 放进握手 payload
+        if let (Some(token), Some(payload)) = (
+            config.auth_token.as_deref().filter(|t| !t.is_empty()),
+            init_payload.as_object_mut(),
+        ) {
+            payload
+                .entry("Authorization")
+                .or_insert_with(|| Value::String(format!("Bearer {}", token)));
+        }
+
+        let ping_interval = options
+            .get("ping_interval")
+            .and_then(Value::as_u64)
+            .filter(|secs| *secs > 0 && variant == Variant::TransportWs)
+            .map(Duration::from_secs);
+
+        Ok(Self {
+            variant,
+            init_payload,
+            ping_interval,
+            acknowledged: false,
+            pending: VecDeque::new(),
+            active: Vec::new(),
+            next_id: 0,
+        })
+    }
+
+    // 生成订阅帧；连接未确认前先排队，收到 connection_ack 后统一发送
+    fn start_operations(&mut self, filters: &Value, send: bool) -> AdapterResult<Vec<Message>> {
+        for operation in parse_operations(filters)? {
+            self.pending.push_back((operation, send));
+        }
+        if !self.acknowledged {
+            return Ok(Vec::new());
+        }
+        Ok(self.flush_pending().0)
+    }
+
+    // 返回订阅帧及其中来自发送请求的帧下标
+    fn flush_pending(&mut self) -> (Vec<Message>, Vec<usize>) {
+        let mut frames = Vec::new();
+        let mut sent = Vec::new();
+        while let Some((mut operation, send)) = self.pending.pop_front() {
+            let id = match operation.get("id").and_then(Value::as_str) {
+                Some(id) => id.to_string(),
+                None => {
+                    self.next_id += 1;
+                    self.next_id.to_string()
+                }
+            };
+            if let Some(obj) = operation.as_object_mut() {
+                obj.remove("id");
+            }
+            let kind = match self.variant {
+                Variant::TransportWs => "subscribe",
+                Variant::Legacy => "start",
+            };
+            if send {
+                sent.push(frames.len());
+            }
+            frames.push(text(json!({ "id": id, "type": kind, "payload": operation })));
+            self.active.push((id, operation));
+        }
+        (frames, sent)
+    }
+
+    // 结束 filters 中的操作：已发送的按 id（未指定 id 时按 payload）匹配并发送结束帧，未发送的移出队列
+    fn stop_operations(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let kind = match self.variant {
+            Variant::TransportWs => "complete",
+            Variant::Legacy => "stop",
+        };
+        let mut frames = Vec::new();
+        for mut operation in parse_operations(filters)? {
+            self.pending.retain(|(pending, _)| *pending != operation);
+            let id = operation.as_object_mut().and_then(|obj| obj.remove("id"));
+            let position = match id.as_ref().and_then(Value::as_str) {
+                Some(id) => self.active.iter().position(|(active, _)| active == id),
+                None => self.active.iter().position(|(_, payload)| *payload == operation),
+            };
+            if let Some(position) = position {
+                let (id, _) = self.active.remove(position);
+                frames.push(text(json!({ "id": id, "type": kind })));
+            }
+        }
+        Ok(frames)
+    }
+}
+
+impl ProtocolAdapter for GraphqlAdapter {
+    fn subprotocol(&self) -> Option<&'static str> {
+        Some(match self.variant {
+            Variant::TransportWs => "graphql-transport-ws",
+            Variant::Legacy => "graphql-ws",
+        })
+    }
+
+    fn on_open(&mut self, filters: Option<&Value>) -> AdapterResult<Vec<Message>> {
+        let mut frames = vec![text(json!({
+            "type": "connection_init",
+            "payload": self.init_payload,
+        }))];
+        if let Some(filters) = filters {
+            frames.extend(self.start_operations(filters, false)?);
+        }
+        Ok(frames)
+    }
+
+    fn subscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        self.start_operations(filters, false)
+    }
+
+    fn unsubscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        self.stop_operations(filters)
+    }
+
+    // message 为 {"query": ..., "variables": ...} 时启动一个新操作（topic 作为操作 id），否则原样发送
+    fn encode_outgoing(&mut self, request: &SendMessageRequest) -> AdapterResult<Vec<Message>> {
+        match serde_json::from_str::<Value>(&request.message) {
+            Ok(mut operation) if operation.get("query").is_some() => {
+                if let (Some(topic), Some(obj)) = (&request.topic, operation.as_object_mut()) {
+                    obj.insert("id".to_string(), Value::String(topic.clone()));
+                }
+                self.start_operations(&operation, true)
+            }
+            _ => Ok(vec![Message::Text(request.message.clone())]),
+        }
+    }
+
+    fn decode_incoming(&mut self, message: Message) -> AdapterResult<Decoded> {
+        let raw = match message {
+            Message::Text(text) => text,
+            _ => return Ok(Decoded::default()),
+        };
+        // 非 JSON 帧不断开连接，原样保存
+        let Ok(envelope) = serde_json::from_str::<Value>(&raw) else {
+            tracing::warn!("Received non-JSON GraphQL frame: {}", raw);
+            return Ok(Decoded::message(None, raw));
+        };
+        let kind = envelope.get("type").and_then(Value::as_str).unwrap_or_default();
+        let id = envelope.get("id").and_then(Value::as_str).map(str::to_string);
+        let payload = envelope.get("payload").map(Value::to_string);
+
+        let mut decoded = Decoded::default();
+        match kind {
+            "connection_ack" => {
+                self.acknowledged = true;
+                (decoded.replies, decoded.flushed) = self.flush_pending();
+            }
+            "ping" => decoded.replies.push(text(json!({ "type": "pong" }))),
+            "pong" | "ka" => {}
+            "connection_error" => {
+                return Err(format!("GraphQL connection error: {}", payload.unwrap_or_default()).into());
+            }
+            // 旧协议的 data 与新协议的 next 统一记为 next
+            "next" | "data" | "error" | "complete" => {
+                if matches!(kind, "error" | "complete") {
+                    self.active.retain(|(active, _)| Some(active) != id.as_ref());
+                }
+                let event = if kind == "data" { "next" } else { kind };
+                decoded.messages.push(Inbound {
+                    topic: id,
+                    event: Some(event.to_string()),
+                    content: payload.unwrap_or(raw),
+                });
+            }
+            other => {
+                tracing::debug!("Ignoring GraphQL message type {}", other);
+            }
+        }
+        Ok(decoded)
+    }
+
+    fn heartbeat_interval(&self) -> Option<Duration> {
+        self.ping_interval
+    }
+
+    fn heartbeat(&mut self) -> Vec<Message> {
+        vec![text(json!({ "type": "ping" }))]
+    }
+}
+
+// 解析订阅列表：单个对象或对象数组，每项至少包含 query
+fn parse_operations(filters: &Value) -> AdapterResult<Vec<Value>> {
+    let items = match filters {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        other => vec![other.clone()],
+    };
+    for item in &items {
+        if item.get("query").and_then(Value::as_str).is_none() {
+            return Err(format!("GraphQL subscription requires a query: {}", item).into());
+        }
+    }
+    Ok(items)
+}
+
+fn text(value: Value) -> Message {
+    Message::Text(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> WebSocketConfig {
+        WebSocketConfig {
+            id: "graphql-test".to_string(),
+            name: "graphql".to_string(),
+            description: None,
+            ws_url: String::new(),
+            config_type: "sender".to_string(),
+            headers: None,
+            auth_token: None,
+            message_template: None,
+            auto_reconnect: false,
+            status: "inactive".to_string(),
+            protocol: "graphql-ws".to_string(),
+            protocol_options: None,
+            filters: None,
+            message_schema: None,
+            transforms: None,
+            outbox: None,
+            sequence: None,
+            orderbook: None,
+            candles: None,
+            state_store: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn frame(value: Value) -> Message {
+        Message::Text(value.to_string())
+    }
+
+    // connection_ack 之前发送的操作排队，确认后随应答发出并标出下标
+    #[test]
+    fn sends_queued_before_ack_are_flushed() {
+        let mut adapter = GraphqlAdapter::new(&config(), &json!({})).unwrap();
+        let filters = json!([{ "id": "prices", "query": "subscription { prices }" }]);
+        assert_eq!(adapter.on_open(Some(&filters)).unwrap().len(), 1);
+        let request = SendMessageRequest {
+            config_id: "graphql-test".to_string(),
+            message: json!({ "query": "subscription { trades }" }).to_string(),
+            custom_headers: None,
+            topic: Some("trades".to_string()),
+            event: None,
+            qos: None,
+            retain: None,
+        };
+        assert!(adapter.encode_outgoing(&request).unwrap().is_empty());
+
+        let decoded = adapter.decode_incoming(frame(json!({ "type": "connection_ack" }))).unwrap();
+        assert_eq!(decoded.replies.len(), 2);
+        assert_eq!(decoded.flushed, [1]);
+        let Message::Text(sent) = &decoded.replies[1] else { panic!("expected a text frame") };
+        let sent: Value = serde_json::from_str(sent).unwrap();
+        assert_eq!(sent["id"], "trades");
+        assert_eq!(sent["type"], "subscribe");
+
+        // 确认之后直接编码
+        assert_eq!(adapter.encode_outgoing(&request).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn non_json_frames_are_kept() {
+        let mut adapter = GraphqlAdapter::new(&config(), &json!({})).unwrap();
+        let decoded = adapter.decode_incoming(Message::Text("not json".to_string())).unwrap();
+        assert_eq!(decoded.messages.len(), 1);
+        assert_eq!(decoded.messages[0].content, "not json");
+    }
+}
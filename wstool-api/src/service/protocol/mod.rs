@@ -0,0 +1,163 @@
+pub mod mqtt;
+pub mod graphql;
+pub mod phoenix;
+pub mod actioncable;
+pub mod preset;
+
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::models::{SendMessageRequest, WebSocketConfig};
+
+pub type AdapterResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// 解码后的一条业务消息（topic 为空表示原始 WebSocket 帧，event 为协议层事件类型）
+#[derive(Debug, Clone)]
+pub struct Inbound {
+    pub topic: Option<String>,
+    pub event: Option<String>,
+    pub content: String,
+}
+
+// 一个入站帧的解码结果：需要回写给服务端的帧 + 需要落库的消息
+#[derive(Debug, Default)]
+pub struct Decoded {
+    pub replies: Vec<Message>,
+    pub messages: Vec<Inbound>,
+    // replies 中之前排队的发送请求（encode_outgoing 返回空）所在的下标，按排队顺序
+    pub flushed: Vec<usize>,
+}
+
+impl Decoded {
+    pub fn message(topic: Option<String>, content: String) -> Self {
+        Self { messages: vec![Inbound { topic, event: None, content }], ..Self::default() }
+    }
+}
+
+/// 协议适配器：在 WebSocket 传输层之上实现具体的应用协议（握手、订阅、心跳、编解码）
+pub trait ProtocolAdapter: Send {
+    // 握手时声明的子协议（Sec-WebSocket-Protocol）
+    fn subprotocol(&self) -> Option<&'static str> {
+        None
+    }
+
+    // 连接建立后立即发送的帧（协议握手 + 配置中保存的订阅）
+    fn on_open(&mut self, filters: Option<&Value>) -> AdapterResult<Vec<Message>>;
+
+    // 在已有连接上追加订阅
+    fn subscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>>;
+
+    // 取消 filters 中的订阅并清除对应的订阅状态
+    fn unsubscribe(&mut self, _filters: &Value) -> AdapterResult<Vec<Message>> {
+        Ok(Vec::new())
+    }
+
+    // 重新订阅（如序号缺口后重新获取快照）：先取消再订阅，避免服务端忽略或拒绝重复订阅
+    fn resubscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let mut frames = self.unsubscribe(filters)?;
+        frames.extend(self.subscribe(filters)?);
+        Ok(frames)
+    }
+
+    // 把发送请求编码为 WebSocket 帧；返回空表示已在适配器内排队，之后随 decode_incoming 的 replies 发出
+    fn encode_outgoing(&mut self, request: &SendMessageRequest) -> AdapterResult<Vec<Message>>;
+
+    // 解码收到的 WebSocket 帧
+    fn decode_incoming(&mut self, message: Message) -> AdapterResult<Decoded>;
+
+    // 心跳间隔，None 表示不需要应用层心跳
+    fn heartbeat_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    fn heartbeat(&mut self) -> Vec<Message> {
+        Vec::new()
+    }
+
+    // 定时检查的间隔（如延迟重新订阅），None 表示不需要
+    fn timer_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    // 定时检查，返回到期需要发送的帧
+    fn on_timer(&mut self) -> Vec<Message> {
+        Vec::new()
+    }
+}
+
+// 原始 WebSocket：文本帧原样收发
+pub struct RawAdapter;
+
+impl ProtocolAdapter for RawAdapter {
+    fn on_open(&mut self, _filters: Option<&Value>) -> AdapterResult<Vec<Message>> {
+        Ok(Vec::new())
+    }
+
+    fn subscribe(&mut self, _filters: &Value) -> AdapterResult<Vec<Message>> {
+        Ok(Vec::new())
+    }
+
+    fn encode_outgoing(&mut self, request: &SendMessageRequest) -> AdapterResult<Vec<Message>> {
+        Ok(vec![Message::Text(request.message.clone())])
+    }
+
+    fn decode_incoming(&mut self, message: Message) -> AdapterResult<Decoded> {
+        match message {
+            Message::Text(text) => Ok(Decoded::message(None, text)),
+            _ => Ok(Decoded::default()),
+        }
+    }
+}
+
+// 根据配置的 protocol 字段创建适配器
+pub fn build_adapter(config: &WebSocketConfig) -> AdapterResult<Box<dyn ProtocolAdapter>> {
+    let options = parse_options(config)?;
+    match config.protocol.as_str() {
+        "raw" => Ok(Box::new(RawAdapter)),
+        "mqtt" => Ok(Box::new(mqtt::MqttAdapter::new(config, &options)?)),
+        "graphql-ws" => Ok(Box::new(graphql::GraphqlAdapter::new(config, &options)?)),
+        "phoenix" => Ok(Box::new(phoenix::PhoenixAdapter::new(config, &options)?)),
+        "actioncable" => Ok(Box::new(actioncable::ActionCableAdapter::new(config, &options)?)),
+        // 交易所预设（binance、okx、coinbase）
+        other => match preset::find_preset(other) {
+            Some(preset) => Ok(Box::new(preset::PresetAdapter::new(preset))),
+            None => Err(format!("Unsupported protocol: {}", other).into()),
+        },
+    }
+}
+
+// 解析 protocol_options（JSON 对象），为空时返回空对象
+pub fn parse_options(config: &WebSocketConfig) -> AdapterResult<Value> {
+    match config.protocol_options.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => {
+            let value: Value = serde_json::from_str(raw)?;
+            if !value.is_object() {
+                return Err("protocol_options must be a JSON object".into());
+            }
+            Ok(value)
+        }
+        _ => Ok(Value::Object(Default::default())),
+    }
+}
+
+// 解析 headers（JSON 对象字符串）为键值对
+pub fn parse_headers(headers: Option<&str>) -> AdapterResult<Vec<(String, String)>> {
+    let raw = match headers {
+        Some(raw) if !raw.trim().is_empty() => raw,
+        _ => return Ok(Vec::new()),
+    };
+    let value: Value = serde_json::from_str(raw)?;
+    let object = value.as_object().ok_or("headers must be a JSON object")?;
+    Ok(object
+        .iter()
+        .map(|(k, v)| {
+            let v = match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            (k.clone(), v)
+        })
+        .collect())
+}
@@ -0,0 +1,768 @@
+use std::time::Duration;
+
+use base64::Engine;
+use serde_json::Value;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use uuid::Uuid;
+
+use super::{parse_headers, AdapterResult, Decoded, Inbound, ProtocolAdapter};
+use crate::models::{SendMessageRequest, WebSocketConfig};
+
+// MQTT 控制报文类型
+const CONNECT: u8 = 1;
+const CONNACK: u8 = 2;
+const PUBLISH: u8 = 3;
+const PUBACK: u8 = 4;
+const PUBREC: u8 = 5;
+const PUBREL: u8 = 6;
+const PUBCOMP: u8 = 7;
+const SUBSCRIBE: u8 = 8;
+const SUBACK: u8 = 9;
+const UNSUBACK: u8 = 11;
+const PINGREQ: u8 = 12;
+const PINGRESP: u8 = 13;
+const DISCONNECT: u8 = 14;
+
+/// MQTT 3.1.1 / 5 over WebSocket 客户端
+///
+/// protocol_options 支持：`version`（"3.1.1" 或 "5"）、`client_id`、`keep_alive`（秒）、
+/// `clean_session`、`username`。CONNECT 凭据优先取 `auth_token`（`user:pass` 或仅密码），
+/// 其次取 headers 中的 `Authorization: Basic ...`。
+pub struct MqttAdapter {
+    level: u8,
+    client_id: String,
+    keep_alive: u16,
+    clean_session: bool,
+    username: Option<String>,
+    password: Option<Vec<u8>>,
+    next_packet_id: u16,
+    buffer: Vec<u8>,
+}
+
+// 一条订阅：主题过滤器 + QoS
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicFilter {
+    pub topic: String,
+    pub qos: u8,
+}
+
+impl MqttAdapter {
+    pub fn new(config: &WebSocketConfig, options: &Value) -> AdapterResult<Self> {
+        let level = match options.get("version") {
+            None => 4,
+            Some(Value::String(v)) if v == "3.1.1" || v == "4" => 4,
+            Some(Value::String(v)) if v == "5" || v == "5.0" => 5,
+            Some(Value::Number(n)) if n.as_u64() == Some(4) => 4,
+            Some(Value::Number(n)) if n.as_u64() == Some(5) => 5,
+            Some(other) => return Err(format!("Unsupported MQTT version: {}", other).into()),
+        };
+        let client_id = options
+            .get("client_id")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("wstool-{}", &Uuid::new_v4().simple().to_string()[..12]));
+        let keep_alive = options
+            .get("keep_alive")
+            .and_then(Value::as_u64)
+            .unwrap_or(60)
+            .min(u16::MAX as u64) as u16;
+        let clean_session = options
+            .get("clean_session")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+
+        let (mut username, password) = credentials(config)?;
+        if username.is_none() {
+            username = options.get("username").and_then(Value::as_str).map(str::to_string);
+        }
+        // 协议要求：有密码时必须带用户名
+        if password.is_some() && username.is_none() {
+            username = Some(String::new());
+        }
+
+        Ok(Self {
+            level,
+            client_id,
+            keep_alive,
+            clean_session,
+            username,
+            password,
+            next_packet_id: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    fn packet_id(&mut self) -> u16 {
+        self.next_packet_id = self.next_packet_id.wrapping_add(1);
+        if self.next_packet_id == 0 {
+            self.next_packet_id = 1;
+        }
+        self.next_packet_id
+    }
+
+    fn is_v5(&self) -> bool {
+        self.level == 5
+    }
+
+    fn connect_packet(&self) -> AdapterResult<Vec<u8>> {
+        let mut body = Vec::new();
+        write_str(&mut body, "MQTT")?;
+        body.push(self.level);
+        let mut flags = 0u8;
+        if self.username.is_some() {
+            flags |= 0x80;
+        }
+        if self.password.is_some() {
+            flags |= 0x40;
+        }
+        if self.clean_session {
+            flags |= 0x02;
+        }
+        body.push(flags);
+        body.extend_from_slice(&self.keep_alive.to_be_bytes());
+        if self.is_v5() {
+            write_varint(&mut body, 0);
+        }
+        write_str(&mut body, &self.client_id)?;
+        if let Some(username) = &self.username {
+            write_str(&mut body, username)?;
+        }
+        if let Some(password) = &self.password {
+            write_bytes(&mut body, password)?;
+        }
+        Ok(packet(CONNECT << 4, &body))
+    }
+
+    fn subscribe_packet(&mut self, filters: &[TopicFilter]) -> AdapterResult<Vec<u8>> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&self.packet_id().to_be_bytes());
+        if self.is_v5() {
+            write_varint(&mut body, 0);
+        }
+        for filter in filters {
+            write_str(&mut body, &filter.topic)?;
+            body.push(filter.qos);
+        }
+        Ok(packet((SUBSCRIBE << 4) | 0x02, &body))
+    }
+
+    fn publish_packet(&mut self, topic: &str, payload: &[u8], qos: u8, retain: bool) -> AdapterResult<Vec<u8>> {
+        let mut body = Vec::new();
+        write_str(&mut body, topic)?;
+        if qos > 0 {
+            body.extend_from_slice(&self.packet_id().to_be_bytes());
+        }
+        if self.is_v5() {
+            write_varint(&mut body, 0);
+        }
+        body.extend_from_slice(payload);
+        let mut header = (PUBLISH << 4) | (qos << 1);
+        if retain {
+            header |= 0x01;
+        }
+        Ok(packet(header, &body))
+    }
+
+    // 处理一个完整的控制报文
+    fn handle_packet(&mut self, header: u8, body: &[u8], decoded: &mut Decoded) -> AdapterResult<()> {
+        let mut reader = Reader::new(body);
+        match header >> 4 {
+            CONNACK => {
+                reader.u8()?;
+                let code = reader.u8()?;
+                if code != 0 {
+                    return Err(format!("MQTT connection refused, code {:#04x}", code).into());
+                }
+            }
+            PUBLISH => {
+                let qos = (header >> 1) & 0x03;
+                let topic = reader.string()?;
+                let packet_id = if qos > 0 { Some(reader.u16()?) } else { None };
+                if self.is_v5() {
+                    let len = reader.varint()?;
+                    reader.skip(len)?;
+                }
+                let payload = reader.rest();
+                decoded.messages.push(Inbound {
+                    topic: Some(topic),
+                    event: None,
+                    content: String::from_utf8_lossy(payload).into_owned(),
+                });
+                match (qos, packet_id) {
+                    (1, Some(id)) => decoded.replies.push(ack(PUBACK << 4, id)),
+                    (2, Some(id)) => decoded.replies.push(ack(PUBREC << 4, id)),
+                    _ => {}
+                }
+            }
+            PUBREL => {
+                let id = reader.u16()?;
+                decoded.replies.push(ack(PUBCOMP << 4, id));
+            }
+            SUBACK => {
+                reader.u16()?;
+                if self.is_v5() {
+                    let len = reader.varint()?;
+                    reader.skip(len)?;
+                }
+                for code in reader.rest() {
+                    if *code >= 0x80 {
+                        tracing::warn!("MQTT subscription rejected, code {:#04x}", code);
+                    }
+                }
+            }
+            DISCONNECT => {
+                let code = reader.u8().unwrap_or(0);
+                return Err(format!("MQTT broker disconnected, reason {:#04x}", code).into());
+            }
+            PUBACK | PUBREC | PUBCOMP | UNSUBACK | PINGRESP => {}
+            other => {
+                tracing::debug!("Ignoring MQTT packet type {}", other);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProtocolAdapter for MqttAdapter {
+    fn subprotocol(&self) -> Option<&'static str> {
+        Some("mqtt")
+    }
+
+    fn on_open(&mut self, filters: Option<&Value>) -> AdapterResult<Vec<Message>> {
+        // MQTT 允许在收到 CONNACK 之前继续发送 SUBSCRIBE
+        let mut frames = vec![Message::Binary(self.connect_packet()?)];
+        if let Some(filters) = filters {
+            frames.extend(self.subscribe(filters)?);
+        }
+        Ok(frames)
+    }
+
+    fn subscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let filters = parse_filters(filters)?;
+        if filters.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(vec![Message::Binary(self.subscribe_packet(&filters)?)])
+    }
+
+    fn encode_outgoing(&mut self, request: &SendMessageRequest) -> AdapterResult<Vec<Message>> {
+        let topic = request
+            .topic
+            .as_deref()
+            .filter(|t| !t.is_empty())
+            .ok_or("MQTT publish requires a topic")?;
+        let qos = request.qos.unwrap_or(0);
+        if qos > 1 {
+            return Err("MQTT publish supports QoS 0 and 1 only".into());
+        }
+        let retain = request.retain.unwrap_or(false);
+        let frame = self.publish_packet(topic, request.message.as_bytes(), qos, retain)?;
+        Ok(vec![Message::Binary(frame)])
+    }
+
+    fn decode_incoming(&mut self, message: Message) -> AdapterResult<Decoded> {
+        let data = match message {
+            Message::Binary(data) => data,
+            Message::Text(text) => text.into_bytes(),
+            _ => return Ok(Decoded::default()),
+        };
+        // 一个 WebSocket 帧可能包含多个 MQTT 报文，也可能只是报文的一部分
+        self.buffer.extend_from_slice(&data);
+        let mut decoded = Decoded::default();
+        loop {
+            let Some((header, start, len)) = frame_bounds(&self.buffer)? else {
+                break;
+            };
+            let body = self.buffer[start..start + len].to_vec();
+            self.buffer.drain(..start + len);
+            self.handle_packet(header, &body, &mut decoded)?;
+        }
+        Ok(decoded)
+    }
+
+    fn heartbeat_interval(&self) -> Option<Duration> {
+        if self.keep_alive == 0 {
+            return None;
+        }
+        Some(Duration::from_secs((self.keep_alive as u64 / 2).max(1)))
+    }
+
+    fn heartbeat(&mut self) -> Vec<Message> {
+        vec![Message::Binary(vec![PINGREQ << 4, 0])]
+    }
+}
+
+// 解析订阅过滤器：["a/b", {"topic": "c/#", "qos": 1}]
+pub fn parse_filters(filters: &Value) -> AdapterResult<Vec<TopicFilter>> {
+    let items = match filters {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        other => vec![other.clone()],
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::String(topic) => Ok(TopicFilter { topic, qos: 0 }),
+            Value::Object(obj) => {
+                let topic = obj
+                    .get("topic")
+                    .and_then(Value::as_str)
+                    .ok_or("MQTT filter requires a topic")?
+                    .to_string();
+                let qos = obj.get("qos").and_then(Value::as_u64).unwrap_or(0);
+                if qos > 1 {
+                    return Err("MQTT subscriptions support QoS 0 and 1 only".into());
+                }
+                Ok(TopicFilter { topic, qos: qos as u8 })
+            }
+            other => Err(format!("Invalid MQTT filter: {}", other).into()),
+        })
+        .collect()
+}
+
+// 从 auth_token 或 headers 中提取 CONNECT 用户名和密码
+fn credentials(config: &WebSocketConfig) -> AdapterResult<(Option<String>, Option<Vec<u8>>)> {
+    if let Some(token) = config.auth_token.as_deref().filter(|t| !t.is_empty()) {
+        return Ok(match token.split_once(':') {
+            Some((user, pass)) => (Some(user.to_string()), Some(pass.as_bytes().to_vec())),
+            None => (None, Some(token.as_bytes().to_vec())),
+        });
+    }
+    for (name, value) in parse_headers(config.headers.as_deref())? {
+        if !name.eq_ignore_ascii_case("authorization") {
+            continue;
+        }
+        if let Some(encoded) = value.strip_prefix("Basic ") {
+            let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.trim())?;
+            let decoded = String::from_utf8(decoded)?;
+            if let Some((user, pass)) = decoded.split_once(':') {
+                return Ok((Some(user.to_string()), Some(pass.as_bytes().to_vec())));
+            }
+        }
+    }
+    Ok((None, None))
+}
+
+// 在缓冲区中定位一个完整报文：返回 (固定头, 报文体起始位置, 报文体长度)
+fn frame_bounds(buffer: &[u8]) -> AdapterResult<Option<(u8, usize, usize)>> {
+    if buffer.len() < 2 {
+        return Ok(None);
+    }
+    let mut len = 0usize;
+    let mut shift = 0;
+    let mut pos = 1;
+    loop {
+        let Some(byte) = buffer.get(pos) else {
+            return Ok(None);
+        };
+        len |= ((byte & 0x7F) as usize) << shift;
+        pos += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 21 {
+            return Err("Malformed MQTT remaining length".into());
+        }
+    }
+    if buffer.len() < pos + len {
+        return Ok(None);
+    }
+    Ok(Some((buffer[0], pos, len)))
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 5);
+    out.push(header);
+    write_varint(&mut out, body.len());
+    out.extend_from_slice(body);
+    out
+}
+
+fn ack(header: u8, packet_id: u16) -> Message {
+    Message::Binary(packet(header, &packet_id.to_be_bytes()))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let mut byte = (value % 128) as u8;
+        value /= 128;
+        if value > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, value: &str) -> AdapterResult<()> {
+    write_bytes(out, value.as_bytes())
+}
+
+// 字符串和二进制字段以 2 字节长度开头，超长时报错而不是截断
+fn write_bytes(out: &mut Vec<u8>, value: &[u8]) -> AdapterResult<()> {
+    let len = u16::try_from(value.len())
+        .map_err(|_| format!("MQTT field is {} bytes, the limit is {}", value.len(), u16::MAX))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(value);
+    Ok(())
+}
+
+// 报文体读取器
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> AdapterResult<&'a [u8]> {
+        if self.pos + n > self.data.len() {
+            return Err("Truncated MQTT packet".into());
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> AdapterResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> AdapterResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn string(&mut self) -> AdapterResult<String> {
+        let len = self.u16()? as usize;
+        Ok(String::from_utf8(self.take(len)?.to_vec())?)
+    }
+
+    fn varint(&mut self) -> AdapterResult<usize> {
+        let mut value = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7F) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+            if shift > 21 {
+                return Err("Malformed MQTT variable byte integer".into());
+            }
+        }
+    }
+
+    fn skip(&mut self, n: usize) -> AdapterResult<()> {
+        self.take(n).map(|_| ())
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let slice = &self.data[self.pos..];
+        self.pos = self.data.len();
+        slice
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+    use super::*;
+
+    fn config(auth_token: Option<&str>) -> WebSocketConfig {
+        WebSocketConfig {
+            id: "mqtt-test".to_string(),
+            name: "mqtt".to_string(),
+            description: None,
+            ws_url: String::new(),
+            config_type: "subscriber".to_string(),
+            headers: None,
+            auth_token: auth_token.map(str::to_string),
+            message_template: None,
+            auto_reconnect: false,
+            status: "inactive".to_string(),
+            protocol: "mqtt".to_string(),
+            protocol_options: None,
+            filters: None,
+            message_schema: None,
+            transforms: None,
+            outbox: None,
+            sequence: None,
+            orderbook: None,
+            candles: None,
+            state_store: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    // 握手时确认并回应 mqtt 子协议
+    #[allow(clippy::result_large_err)]
+    fn accept_mqtt(request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+        assert_eq!(request.headers().get("Sec-WebSocket-Protocol").unwrap(), "mqtt");
+        response.headers_mut().insert("Sec-WebSocket-Protocol", HeaderValue::from_static("mqtt"));
+        Ok(response)
+    }
+
+    // 进程内的 MQTT broker 替身：CONNECT 回 CONNACK，SUBSCRIBE 回 SUBACK 并推送一条 QoS 1 消息（拆成两个帧），
+    // 收到的每个报文 (固定头, 报文体) 都转给测试
+    async fn spawn_broker(level: u8, payload: &'static str) -> (String, mpsc::UnboundedReceiver<(u8, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (packets, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_hdr_async(stream, accept_mqtt).await.unwrap();
+            let mut buffer = Vec::new();
+            while let Some(Ok(message)) = ws.next().await {
+                buffer.extend_from_slice(&message.into_data());
+                while let Some((header, start, len)) = frame_bounds(&buffer).unwrap() {
+                    let body = buffer[start..start + len].to_vec();
+                    buffer.drain(..start + len);
+                    let mut replies = Vec::new();
+                    match header >> 4 {
+                        CONNECT => {
+                            let mut body = vec![0, 0];
+                            if level == 5 {
+                                // 属性：Topic Alias Maximum = 10
+                                body.extend_from_slice(&[3, 0x22, 0, 10]);
+                            }
+                            replies.push(packet(CONNACK << 4, &body));
+                        }
+                        SUBSCRIBE => {
+                            let mut reader = Reader::new(&body);
+                            let packet_id = reader.u16().unwrap();
+                            if level == 5 {
+                                let len = reader.varint().unwrap();
+                                reader.skip(len).unwrap();
+                            }
+                            let topic = reader.string().unwrap();
+                            let qos = reader.u8().unwrap();
+                            let mut suback = packet_id.to_be_bytes().to_vec();
+                            if level == 5 {
+                                suback.push(0);
+                            }
+                            suback.push(qos);
+                            replies.push(packet(SUBACK << 4, &suback));
+
+                            let mut publish = Vec::new();
+                            write_str(&mut publish, &topic.replace('#', "temp")).unwrap();
+                            publish.extend_from_slice(&7u16.to_be_bytes());
+                            if level == 5 {
+                                // 属性：Payload Format Indicator = 1，Content Type = "application/json"
+                                let mut properties = vec![0x01, 1, 0x03];
+                                write_str(&mut properties, "application/json").unwrap();
+                                write_varint(&mut publish, properties.len());
+                                publish.extend_from_slice(&properties);
+                            }
+                            publish.extend_from_slice(payload.as_bytes());
+                            let publish = packet((PUBLISH << 4) | 0x02, &publish);
+                            let (head, tail) = publish.split_at(publish.len() / 2);
+                            replies.push(head.to_vec());
+                            replies.push(tail.to_vec());
+                        }
+                        PUBLISH if (header >> 1) & 0x03 == 1 => {
+                            let mut reader = Reader::new(&body);
+                            reader.string().unwrap();
+                            replies.push(packet(PUBACK << 4, &reader.u16().unwrap().to_be_bytes()));
+                        }
+                        _ => {}
+                    }
+                    let _ = packets.send((header, body));
+                    for reply in replies {
+                        ws.send(Message::Binary(reply)).await.unwrap();
+                    }
+                }
+            }
+        });
+        (url, received)
+    }
+
+    // 连接替身 broker，按适配器的要求回写帧，直到收到 count 条消息
+    async fn run_client(adapter: &mut MqttAdapter, url: &str, filters: &Value, count: usize) -> Vec<Inbound> {
+        let mut request = url.into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", HeaderValue::from_static(adapter.subprotocol().unwrap()));
+        let (mut ws, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+        for frame in adapter.on_open(Some(filters)).unwrap() {
+            ws.send(frame).await.unwrap();
+        }
+        let mut messages = Vec::new();
+        while messages.len() < count {
+            let frame = ws.next().await.unwrap().unwrap();
+            let decoded = adapter.decode_incoming(frame).unwrap();
+            for reply in decoded.replies {
+                ws.send(reply).await.unwrap();
+            }
+            messages.extend(decoded.messages);
+        }
+        let request = SendMessageRequest {
+            config_id: "mqtt-test".to_string(),
+            message: "ping".to_string(),
+            custom_headers: None,
+            topic: Some("sensors/out".to_string()),
+            event: None,
+            qos: Some(1),
+            retain: Some(true),
+        };
+        for frame in adapter.encode_outgoing(&request).unwrap() {
+            ws.send(frame).await.unwrap();
+        }
+        // 等待 broker 的 PUBACK，确认发送的报文已被处理
+        let puback = ws.next().await.unwrap().unwrap();
+        assert!(adapter.decode_incoming(puback).unwrap().messages.is_empty());
+        messages
+    }
+
+    fn next_packet(received: &mut mpsc::UnboundedReceiver<(u8, Vec<u8>)>) -> (u8, Vec<u8>) {
+        received.try_recv().expect("broker received no packet")
+    }
+
+    #[tokio::test]
+    async fn v311_connect_subscribe_and_publish() {
+        let (url, mut received) = spawn_broker(4, r#"{"temp":21.5}"#).await;
+        let options = json!({ "client_id": "wstool-test", "keep_alive": 30 });
+        let mut adapter = MqttAdapter::new(&config(Some("user:secret")), &options).unwrap();
+        let filters = json!([{ "topic": "sensors/#", "qos": 1 }]);
+
+        let messages = run_client(&mut adapter, &url, &filters, 1).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].topic.as_deref(), Some("sensors/temp"));
+        assert_eq!(messages[0].content, r#"{"temp":21.5}"#);
+
+        let (header, body) = next_packet(&mut received);
+        assert_eq!(header >> 4, CONNECT);
+        let mut reader = Reader::new(&body);
+        assert_eq!(reader.string().unwrap(), "MQTT");
+        assert_eq!(reader.u8().unwrap(), 4);
+        assert_eq!(reader.u8().unwrap(), 0x80 | 0x40 | 0x02);
+        assert_eq!(reader.u16().unwrap(), 30);
+        assert_eq!(reader.string().unwrap(), "wstool-test");
+        assert_eq!(reader.string().unwrap(), "user");
+        assert_eq!(reader.string().unwrap(), "secret");
+
+        let (header, body) = next_packet(&mut received);
+        assert_eq!(header, (SUBSCRIBE << 4) | 0x02);
+        let mut reader = Reader::new(&body);
+        assert_eq!(reader.u16().unwrap(), 1);
+        assert_eq!(reader.string().unwrap(), "sensors/#");
+        assert_eq!(reader.u8().unwrap(), 1);
+
+        // 收到 QoS 1 的 PUBLISH 后回复 PUBACK（报文 ID 与 broker 发送的一致）
+        let (header, body) = next_packet(&mut received);
+        assert_eq!(header >> 4, PUBACK);
+        assert_eq!(body, 7u16.to_be_bytes());
+
+        let (header, body) = next_packet(&mut received);
+        assert_eq!(header, (PUBLISH << 4) | (1 << 1) | 0x01);
+        let mut reader = Reader::new(&body);
+        assert_eq!(reader.string().unwrap(), "sensors/out");
+        assert_eq!(reader.u16().unwrap(), 2);
+        assert_eq!(reader.rest(), b"ping");
+    }
+
+    #[tokio::test]
+    async fn v5_skips_properties() {
+        let (url, mut received) = spawn_broker(5, "21.5").await;
+        let mut adapter = MqttAdapter::new(&config(None), &json!({ "version": "5" })).unwrap();
+
+        let messages = run_client(&mut adapter, &url, &json!(["sensors/temp"]), 1).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].topic.as_deref(), Some("sensors/temp"));
+        assert_eq!(messages[0].content, "21.5");
+
+        let (header, body) = next_packet(&mut received);
+        assert_eq!(header >> 4, CONNECT);
+        let mut reader = Reader::new(&body);
+        reader.string().unwrap();
+        assert_eq!(reader.u8().unwrap(), 5);
+        assert_eq!(reader.u8().unwrap(), 0x02);
+        reader.u16().unwrap();
+        assert_eq!(reader.varint().unwrap(), 0);
+
+        let (header, body) = next_packet(&mut received);
+        assert_eq!(header >> 4, SUBSCRIBE);
+        let mut reader = Reader::new(&body);
+        reader.u16().unwrap();
+        assert_eq!(reader.varint().unwrap(), 0);
+        assert_eq!(reader.string().unwrap(), "sensors/temp");
+        assert_eq!(reader.u8().unwrap(), 0);
+
+        let (header, _) = next_packet(&mut received);
+        assert_eq!(header >> 4, PUBACK);
+
+        // v5 的 PUBLISH 在报文 ID 之后带属性长度
+        let (_, body) = next_packet(&mut received);
+        let mut reader = Reader::new(&body);
+        reader.string().unwrap();
+        reader.u16().unwrap();
+        assert_eq!(reader.varint().unwrap(), 0);
+        assert_eq!(reader.rest(), b"ping");
+    }
+
+    #[test]
+    fn reassembles_packets_split_across_frames() {
+        let mut adapter = MqttAdapter::new(&config(None), &json!({})).unwrap();
+        let mut body = Vec::new();
+        write_str(&mut body, "a/b").unwrap();
+        body.extend_from_slice(&"x".repeat(300).into_bytes());
+        let publish = packet(PUBLISH << 4, &body);
+        let connack = packet(CONNACK << 4, &[0, 0]);
+
+        // CONNACK 与 PUBLISH 在同一个流中，PUBLISH 的剩余长度（两个字节）被拆到两个帧
+        let mut stream = connack.clone();
+        stream.extend_from_slice(&publish);
+        let split = connack.len() + 2;
+        let first = adapter.decode_incoming(Message::Binary(stream[..split].to_vec())).unwrap();
+        assert!(first.messages.is_empty());
+        let second = adapter.decode_incoming(Message::Binary(stream[split..].to_vec())).unwrap();
+        assert_eq!(second.messages.len(), 1);
+        assert_eq!(second.messages[0].topic.as_deref(), Some("a/b"));
+        assert_eq!(second.messages[0].content.len(), 300);
+
+        // 一个帧中的多个报文
+        let mut two = publish.clone();
+        two.extend_from_slice(&publish);
+        let decoded = adapter.decode_incoming(Message::Binary(two)).unwrap();
+        assert_eq!(decoded.messages.len(), 2);
+    }
+
+    #[test]
+    fn refused_connack_is_an_error() {
+        let mut adapter = MqttAdapter::new(&config(None), &json!({})).unwrap();
+        let refused = packet(CONNACK << 4, &[0, 0x05]);
+        assert!(adapter.decode_incoming(Message::Binary(refused)).is_err());
+    }
+
+    // 超过 2 字节长度上限的主题报错，而不是截断长度前缀
+    #[test]
+    fn oversized_topic_is_an_error() {
+        let mut adapter = MqttAdapter::new(&config(None), &json!({})).unwrap();
+        let request = |topic: String| SendMessageRequest {
+            config_id: "mqtt-test".to_string(),
+            message: "{}".to_string(),
+            custom_headers: None,
+            topic: Some(topic),
+            event: None,
+            qos: None,
+            retain: None,
+        };
+        assert!(adapter.encode_outgoing(&request("a".repeat(u16::MAX as usize))).is_ok());
+        assert!(adapter.encode_outgoing(&request("a".repeat(u16::MAX as usize + 1))).is_err());
+    }
+}
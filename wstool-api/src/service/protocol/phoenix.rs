@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use super::{AdapterResult, Decoded, Inbound, ProtocolAdapter};
+use crate::models::{SendMessageRequest, WebSocketConfig};
+
+// 一个未收到 phx_reply 的请求
+#[derive(Debug, Clone)]
+enum Pending {
+    Join(String),
+    Heartbeat,
+    Push(String),
+}
+
+// 频道出错或关闭后重新加入的等待时间（与 Phoenix JS 客户端一致），之后保持最后一项
+const REJOIN_BACKOFF: [Duration; 4] = [
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+
+// 一个等待重新加入的 topic
+#[derive(Debug, Clone)]
+struct Rejoin {
+    attempts: usize,
+    due: Option<Instant>,
+}
+
+// 解析后的消息信封
+struct Envelope {
+    join_ref: Option<String>,
+    msg_ref: Option<String>,
+    topic: String,
+    event: String,
+    payload: Value,
+}
+
+/// Phoenix Channels 客户端
+///
+/// protocol_options 支持：`vsn`（"2.0.0" 默认使用数组格式，"1.0.0" 使用对象格式）、
+/// `heartbeat_interval`（秒，默认 30）。filters 为要加入的 topic 列表：
+/// `["room:lobby", {"topic": "prices:BTC", "payload": {...}}]`，频道出错或关闭后按退避重新加入。
+pub struct PhoenixAdapter {
+    array_format: bool,
+    heartbeat_interval: Duration,
+    next_ref: u64,
+    pending: HashMap<String, Pending>,
+    // topic -> join_ref
+    joined: HashMap<String, String>,
+    // 订阅过滤器中的 topic -> 加入 payload
+    saved: HashMap<String, Value>,
+    rejoin: HashMap<String, Rejoin>,
+    auth_token: Option<String>,
+}
+
+impl PhoenixAdapter {
+    pub fn new(config: &WebSocketConfig, options: &Value) -> AdapterResult<Self> {
+        let array_format = match options.get("vsn").and_then(Value::as_str) {
+            None | Some("2.0.0") => true,
+            Some("1.0.0") => false,
+            Some(other) => return Err(format!("Unsupported Phoenix serializer version: {}", other).into()),
+        };
+        let heartbeat_interval = Duration::from_secs(
+            options
+                .get("heartbeat_interval")
+                .and_then(Value::as_u64)
+                .unwrap_or(30)
+                .max(1),
+        );
+        Ok(Self {
+            array_format,
+            heartbeat_interval,
+            next_ref: 0,
+            pending: HashMap::new(),
+            joined: HashMap::new(),
+            saved: HashMap::new(),
+            rejoin: HashMap::new(),
+            auth_token: config.auth_token.clone().filter(|t| !t.is_empty()),
+        })
+    }
+
+    fn make_ref(&mut self) -> String {
+        self.next_ref += 1;
+        self.next_ref.to_string()
+    }
+
+    fn encode(&self, join_ref: Option<&str>, msg_ref: &str, topic: &str, event: &str, payload: &Value) -> Message {
+        let frame = if self.array_format {
+            json!([join_ref, msg_ref, topic, event, payload])
+        } else {
+            json!({ "topic": topic, "event": event, "payload": payload, "ref": msg_ref, "join_ref": join_ref })
+        };
+        Message::Text(frame.to_string())
+    }
+
+    fn join(&mut self, topic: &str, payload: Value) -> Message {
+        let msg_ref = self.make_ref();
+        self.pending.insert(msg_ref.clone(), Pending::Join(topic.to_string()));
+        self.joined.insert(topic.to_string(), msg_ref.clone());
+        self.encode(Some(&msg_ref), &msg_ref, topic, "phx_join", &payload)
+    }
+
+    fn join_all(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let mut frames = Vec::new();
+        for (topic, mut payload) in parse_topics(filters)? {
+            if let (Some(token), Some(obj)) = (&self.auth_token, payload.as_object_mut()) {
+                obj.entry("token").or_insert_with(|| Value::String(token.clone()));
+            }
+            self.saved.insert(topic.clone(), payload.clone());
+            self.rejoin.remove(&topic);
+            frames.push(self.join(&topic, payload));
+        }
+        Ok(frames)
+    }
+
+    // 离开已加入的 topic（phx_leave 使用原来的 join_ref）
+    fn leave_all(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let mut frames = Vec::new();
+        for (topic, _) in parse_topics(filters)? {
+            self.saved.remove(&topic);
+            self.rejoin.remove(&topic);
+            if let Some(join_ref) = self.joined.remove(&topic) {
+                let msg_ref = self.make_ref();
+                frames.push(self.encode(Some(&join_ref), &msg_ref, &topic, "phx_leave", &json!({})));
+            }
+        }
+        Ok(frames)
+    }
+
+    // 按退避时间安排重新加入
+    fn schedule_rejoin(&mut self, topic: &str) {
+        let rejoin = self
+            .rejoin
+            .entry(topic.to_string())
+            .or_insert(Rejoin { attempts: 0, due: None });
+        let delay = REJOIN_BACKOFF[rejoin.attempts.min(REJOIN_BACKOFF.len() - 1)];
+        rejoin.attempts += 1;
+        rejoin.due = Some(Instant::now() + delay);
+        tracing::info!("Rejoining Phoenix topic {} in {:?}", topic, delay);
+    }
+
+    // 拆解 v2 [join_ref, ref, topic, event, payload] 与 v1 对象两种信封格式
+    fn parse(&self, envelope: Value) -> AdapterResult<Envelope> {
+        let as_string = |v: Option<&Value>| v.and_then(Value::as_str).map(str::to_string);
+        match envelope {
+            Value::Array(parts) if parts.len() == 5 => Ok(Envelope {
+                join_ref: as_string(parts.first()),
+                msg_ref: as_string(parts.get(1)),
+                topic: as_string(parts.get(2)).unwrap_or_default(),
+                event: as_string(parts.get(3)).unwrap_or_default(),
+                payload: parts[4].clone(),
+            }),
+            Value::Object(obj) => Ok(Envelope {
+                join_ref: as_string(obj.get("join_ref")),
+                msg_ref: as_string(obj.get("ref")),
+                topic: as_string(obj.get("topic")).unwrap_or_default(),
+                event: as_string(obj.get("event")).unwrap_or_default(),
+                payload: obj.get("payload").cloned().unwrap_or(Value::Null),
+            }),
+            other => Err(format!("Invalid Phoenix message: {}", other).into()),
+        }
+    }
+}
+
+impl ProtocolAdapter for PhoenixAdapter {
+    fn on_open(&mut self, filters: Option<&Value>) -> AdapterResult<Vec<Message>> {
+        match filters {
+            Some(filters) => self.join_all(filters),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn subscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        self.join_all(filters)
+    }
+
+    fn unsubscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        self.leave_all(filters)
+    }
+
+    // topic + event 时按 channel push 发送，message 为 JSON payload；否则原样发送
+    fn encode_outgoing(&mut self, request: &SendMessageRequest) -> AdapterResult<Vec<Message>> {
+        let (Some(topic), Some(event)) = (&request.topic, &request.event) else {
+            return Ok(vec![Message::Text(request.message.clone())]);
+        };
+        let join_ref = self
+            .joined
+            .get(topic)
+            .cloned()
+            .ok_or_else(|| format!("Phoenix topic not joined: {}", topic))?;
+        let payload: Value = serde_json::from_str(&request.message)?;
+        let msg_ref = self.make_ref();
+        self.pending.insert(msg_ref.clone(), Pending::Push(topic.clone()));
+        Ok(vec![self.encode(Some(&join_ref), &msg_ref, topic, event, &payload)])
+    }
+
+    fn decode_incoming(&mut self, message: Message) -> AdapterResult<Decoded> {
+        let raw = match message {
+            Message::Text(text) => text,
+            _ => return Ok(Decoded::default()),
+        };
+        // 无法解析的帧不断开连接，原样保存
+        let parsed = serde_json::from_str::<Value>(&raw).ok().map(|envelope| self.parse(envelope));
+        let Some(Ok(Envelope { join_ref, msg_ref, topic, event, payload })) = parsed else {
+            tracing::warn!("Received invalid Phoenix frame: {}", raw);
+            return Ok(Decoded::message(None, raw));
+        };
+
+        let mut decoded = Decoded::default();
+        match event.as_str() {
+            "phx_reply" => {
+                let pending = msg_ref.as_ref().and_then(|r| self.pending.remove(r));
+                let ok = payload.get("status").and_then(Value::as_str) == Some("ok");
+                match pending {
+                    Some(Pending::Heartbeat) => {}
+                    Some(Pending::Join(topic)) => {
+                        if ok {
+                            self.rejoin.remove(&topic);
+                        } else {
+                            self.joined.remove(&topic);
+                            tracing::warn!("Phoenix join rejected for {}: {}", topic, payload);
+                            // 重新加入被拒绝时继续按退避重试
+                            if self.rejoin.contains_key(&topic) {
+                                self.schedule_rejoin(&topic);
+                            }
+                        }
+                        decoded.messages.push(Inbound {
+                            topic: Some(topic),
+                            event: Some(if ok { "joined" } else { "join_error" }.to_string()),
+                            content: payload.to_string(),
+                        });
+                    }
+                    Some(Pending::Push(topic)) => decoded.messages.push(Inbound {
+                        topic: Some(topic),
+                        event: Some("reply".to_string()),
+                        content: payload.to_string(),
+                    }),
+                    None => {}
+                }
+            }
+            "phx_error" | "phx_close" => {
+                // 已离开并重新加入的 topic 会收到旧 join_ref 的 phx_close，不影响新的加入
+                if join_ref.is_none() || self.joined.get(&topic) == join_ref.as_ref() {
+                    self.joined.remove(&topic);
+                    if self.saved.contains_key(&topic) {
+                        self.schedule_rejoin(&topic);
+                    }
+                }
+                decoded.messages.push(Inbound {
+                    topic: Some(topic),
+                    event: Some(event),
+                    content: payload.to_string(),
+                });
+            }
+            _ => decoded.messages.push(Inbound {
+                topic: Some(topic),
+                event: Some(event),
+                content: payload.to_string(),
+            }),
+        }
+        Ok(decoded)
+    }
+
+    fn heartbeat_interval(&self) -> Option<Duration> {
+        Some(self.heartbeat_interval)
+    }
+
+    fn heartbeat(&mut self) -> Vec<Message> {
+        // 未应答的旧心跳不再等待
+        self.pending.retain(|_, pending| !matches!(pending, Pending::Heartbeat));
+        let msg_ref = self.make_ref();
+        self.pending.insert(msg_ref.clone(), Pending::Heartbeat);
+        vec![self.encode(None, &msg_ref, "phoenix", "heartbeat", &json!({}))]
+    }
+
+    fn timer_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(1))
+    }
+
+    // 重新加入到期的 topic，使用新的 join_ref
+    fn on_timer(&mut self) -> Vec<Message> {
+        let now = Instant::now();
+        let due: Vec<String> = self
+            .rejoin
+            .iter_mut()
+            .filter(|(_, rejoin)| rejoin.due.is_some_and(|due| due <= now))
+            .map(|(topic, rejoin)| {
+                rejoin.due = None;
+                topic.clone()
+            })
+            .collect();
+        due.into_iter()
+            .filter_map(|topic| {
+                let payload = self.saved.get(&topic)?.clone();
+                Some(self.join(&topic, payload))
+            })
+            .collect()
+    }
+}
+
+// 解析要加入的 topic 列表：["room:lobby", {"topic": "prices:BTC", "payload": {...}}]
+fn parse_topics(filters: &Value) -> AdapterResult<Vec<(String, Value)>> {
+    let items = match filters {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        other => vec![other.clone()],
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::String(topic) => Ok((topic, json!({}))),
+            Value::Object(obj) => {
+                let topic = obj
+                    .get("topic")
+                    .and_then(Value::as_str)
+                    .ok_or("Phoenix filter requires a topic")?
+                    .to_string();
+                Ok((topic, obj.get("payload").cloned().unwrap_or_else(|| json!({}))))
+            }
+            other => Err(format!("Invalid Phoenix filter: {}", other).into()),
+        })
+        .collect()
+}
@@ -0,0 +1,323 @@
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use super::{AdapterResult, Decoded, Inbound, ProtocolAdapter};
+use crate::models::SendMessageRequest;
+
+// 应用层心跳：定时发送 message，服务端回复 pong（回复不落库）
+#[derive(Debug, Clone)]
+pub struct PingRule {
+    pub interval: Duration,
+    pub message: &'static str,
+    pub pong: &'static str,
+}
+
+// 交易所推送帧的分类结果
+pub enum Frame {
+    // 订阅确认、心跳应答等协议帧
+    Control,
+    // 交易所返回的错误
+    Error(String),
+    // 行情数据，topic 为流名称
+    Data(Option<String>),
+}
+
+/// 交易所公共行情流预设：地址、订阅/取消订阅格式、心跳规则和单连接流数量上限
+pub trait ProtocolPreset: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn ws_url(&self) -> &'static str;
+
+    // 单连接允许的最大流数量（交易对 x 频道）
+    fn max_streams(&self) -> usize;
+
+    fn ping(&self) -> Option<PingRule> {
+        None
+    }
+
+    fn stream_count(&self, symbols: &[String], channels: &[String]) -> usize {
+        symbols.len() * channels.len()
+    }
+
+    fn subscribe_message(&self, symbols: &[String], channels: &[String], id: u64) -> Value;
+
+    fn unsubscribe_message(&self, symbols: &[String], channels: &[String], id: u64) -> Value;
+
+    fn classify(&self, frame: &Value) -> Frame;
+}
+
+// Binance 现货组合流：{"method":"SUBSCRIBE","params":["btcusdt@trade"],"id":1}
+pub struct BinancePreset;
+
+impl BinancePreset {
+    fn streams(symbols: &[String], channels: &[String]) -> Vec<String> {
+        symbols
+            .iter()
+            .flat_map(|symbol| {
+                channels
+                    .iter()
+                    .map(move |channel| format!("{}@{}", symbol.to_lowercase(), channel))
+            })
+            .collect()
+    }
+}
+
+impl ProtocolPreset for BinancePreset {
+    fn name(&self) -> &'static str {
+        "binance"
+    }
+
+    fn ws_url(&self) -> &'static str {
+        "wss://stream.binance.com:9443/stream"
+    }
+
+    fn max_streams(&self) -> usize {
+        1024
+    }
+
+    fn subscribe_message(&self, symbols: &[String], channels: &[String], id: u64) -> Value {
+        json!({ "method": "SUBSCRIBE", "params": Self::streams(symbols, channels), "id": id })
+    }
+
+    fn unsubscribe_message(&self, symbols: &[String], channels: &[String], id: u64) -> Value {
+        json!({ "method": "UNSUBSCRIBE", "params": Self::streams(symbols, channels), "id": id })
+    }
+
+    fn classify(&self, frame: &Value) -> Frame {
+        if let Some(error) = frame.get("error") {
+            return Frame::Error(error.to_string());
+        }
+        if frame.get("id").is_some() && frame.get("result").is_some() {
+            return Frame::Control;
+        }
+        Frame::Data(frame.get("stream").and_then(Value::as_str).map(str::to_string))
+    }
+}
+
+// OKX v5 公共频道：{"op":"subscribe","args":[{"channel":"tickers","instId":"BTC-USDT"}]}
+pub struct OkxPreset;
+
+impl OkxPreset {
+    fn args(symbols: &[String], channels: &[String]) -> Vec<Value> {
+        symbols
+            .iter()
+            .flat_map(|symbol| {
+                channels
+                    .iter()
+                    .map(move |channel| json!({ "channel": channel, "instId": symbol.to_uppercase() }))
+            })
+            .collect()
+    }
+}
+
+impl ProtocolPreset for OkxPreset {
+    fn name(&self) -> &'static str {
+        "okx"
+    }
+
+    fn ws_url(&self) -> &'static str {
+        "wss://ws.okx.com:8443/ws/v5/public"
+    }
+
+    fn max_streams(&self) -> usize {
+        480
+    }
+
+    // 30 秒内无数据服务端会断开，需定时发送 "ping"
+    fn ping(&self) -> Option<PingRule> {
+        Some(PingRule { interval: Duration::from_secs(25), message: "ping", pong: "pong" })
+    }
+
+    fn subscribe_message(&self, symbols: &[String], channels: &[String], _id: u64) -> Value {
+        json!({ "op": "subscribe", "args": Self::args(symbols, channels) })
+    }
+
+    fn unsubscribe_message(&self, symbols: &[String], channels: &[String], _id: u64) -> Value {
+        json!({ "op": "unsubscribe", "args": Self::args(symbols, channels) })
+    }
+
+    fn classify(&self, frame: &Value) -> Frame {
+        match frame.get("event").and_then(Value::as_str) {
+            Some("error") => Frame::Error(frame.to_string()),
+            Some(_) => Frame::Control,
+            None => {
+                let arg = frame.get("arg");
+                let channel = arg.and_then(|a| a.get("channel")).and_then(Value::as_str);
+                let inst_id = arg.and_then(|a| a.get("instId")).and_then(Value::as_str);
+                Frame::Data(match (channel, inst_id) {
+                    (Some(channel), Some(inst_id)) => Some(format!("{}:{}", channel, inst_id)),
+                    (Some(channel), None) => Some(channel.to_string()),
+                    _ => None,
+                })
+            }
+        }
+    }
+}
+
+// Coinbase Exchange：{"type":"subscribe","product_ids":["BTC-USD"],"channels":["ticker"]}
+pub struct CoinbasePreset;
+
+impl ProtocolPreset for CoinbasePreset {
+    fn name(&self) -> &'static str {
+        "coinbase"
+    }
+
+    fn ws_url(&self) -> &'static str {
+        "wss://ws-feed.exchange.coinbase.com"
+    }
+
+    fn max_streams(&self) -> usize {
+        100
+    }
+
+    fn subscribe_message(&self, symbols: &[String], channels: &[String], _id: u64) -> Value {
+        json!({ "type": "subscribe", "product_ids": symbols, "channels": channels })
+    }
+
+    fn unsubscribe_message(&self, symbols: &[String], channels: &[String], _id: u64) -> Value {
+        json!({ "type": "unsubscribe", "product_ids": symbols, "channels": channels })
+    }
+
+    fn classify(&self, frame: &Value) -> Frame {
+        let kind = frame.get("type").and_then(Value::as_str);
+        match kind {
+            Some("subscriptions") => Frame::Control,
+            Some("error") => Frame::Error(frame.to_string()),
+            _ => {
+                let product = frame.get("product_id").and_then(Value::as_str);
+                Frame::Data(match (kind, product) {
+                    (Some(kind), Some(product)) => Some(format!("{}:{}", kind, product)),
+                    (Some(kind), None) => Some(kind.to_string()),
+                    _ => None,
+                })
+            }
+        }
+    }
+}
+
+// 预设注册表
+pub fn presets() -> Vec<Box<dyn ProtocolPreset>> {
+    vec![Box::new(BinancePreset), Box::new(OkxPreset), Box::new(CoinbasePreset)]
+}
+
+pub fn find_preset(name: &str) -> Option<Box<dyn ProtocolPreset>> {
+    presets().into_iter().find(|preset| preset.name() == name)
+}
+
+// 解析预设配置的 filters：{"symbols": [...], "channels": [...]} 或其数组
+pub fn parse_streams(filters: &Value) -> AdapterResult<Vec<(Vec<String>, Vec<String>)>> {
+    let items = match filters {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        other => vec![other.clone()],
+    };
+    let strings = |item: &Value, key: &str| -> AdapterResult<Vec<String>> {
+        let values = item
+            .get(key)
+            .and_then(Value::as_array)
+            .ok_or_else(|| format!("Preset filter requires `{}`", key))?;
+        values
+            .iter()
+            .map(|v| v.as_str().map(str::to_string).ok_or_else(|| format!("Invalid {}: {}", key, v).into()))
+            .collect()
+    };
+    items
+        .iter()
+        .map(|item| Ok((strings(item, "symbols")?, strings(item, "channels")?)))
+        .collect()
+}
+
+/// 基于预设的协议适配器：连接后自动订阅，处理心跳，并按流名称保存消息
+pub struct PresetAdapter {
+    preset: Box<dyn ProtocolPreset>,
+    ping: Option<PingRule>,
+    next_id: u64,
+    stream_count: usize,
+}
+
+impl PresetAdapter {
+    pub fn new(preset: Box<dyn ProtocolPreset>) -> Self {
+        let ping = preset.ping();
+        Self { preset, ping, next_id: 0, stream_count: 0 }
+    }
+}
+
+impl ProtocolAdapter for PresetAdapter {
+    fn on_open(&mut self, filters: Option<&Value>) -> AdapterResult<Vec<Message>> {
+        match filters {
+            Some(filters) => self.subscribe(filters),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn subscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let mut frames = Vec::new();
+        for (symbols, channels) in parse_streams(filters)? {
+            let count = self.preset.stream_count(&symbols, &channels);
+            if self.stream_count + count > self.preset.max_streams() {
+                return Err(format!(
+                    "{} allows at most {} streams per connection",
+                    self.preset.name(),
+                    self.preset.max_streams()
+                )
+                .into());
+            }
+            self.stream_count += count;
+            self.next_id += 1;
+            let message = self.preset.subscribe_message(&symbols, &channels, self.next_id);
+            frames.push(Message::Text(message.to_string()));
+        }
+        Ok(frames)
+    }
+
+    fn unsubscribe(&mut self, filters: &Value) -> AdapterResult<Vec<Message>> {
+        let mut frames = Vec::new();
+        for (symbols, channels) in parse_streams(filters)? {
+            self.stream_count = self.stream_count.saturating_sub(self.preset.stream_count(&symbols, &channels));
+            self.next_id += 1;
+            let message = self.preset.unsubscribe_message(&symbols, &channels, self.next_id);
+            frames.push(Message::Text(message.to_string()));
+        }
+        Ok(frames)
+    }
+
+    fn encode_outgoing(&mut self, request: &SendMessageRequest) -> AdapterResult<Vec<Message>> {
+        Ok(vec![Message::Text(request.message.clone())])
+    }
+
+    fn decode_incoming(&mut self, message: Message) -> AdapterResult<Decoded> {
+        let raw = match message {
+            Message::Text(text) => text,
+            _ => return Ok(Decoded::default()),
+        };
+        if self.ping.as_ref().is_some_and(|ping| raw == ping.pong) {
+            return Ok(Decoded::default());
+        }
+        let Ok(frame) = serde_json::from_str::<Value>(&raw) else {
+            return Ok(Decoded::message(None, raw));
+        };
+        let mut decoded = Decoded::default();
+        match self.preset.classify(&frame) {
+            Frame::Control => {}
+            Frame::Error(error) => {
+                tracing::warn!("{} stream error: {}", self.preset.name(), error);
+                decoded.messages.push(Inbound { topic: None, event: Some("error".to_string()), content: raw });
+            }
+            Frame::Data(topic) => decoded.messages.push(Inbound { topic, event: None, content: raw }),
+        }
+        Ok(decoded)
+    }
+
+    fn heartbeat_interval(&self) -> Option<Duration> {
+        self.ping.as_ref().map(|ping| ping.interval)
+    }
+
+    fn heartbeat(&mut self) -> Vec<Message> {
+        self.ping
+            .as_ref()
+            .map(|ping| vec![Message::Text(ping.message.to_string())])
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message as ServerMessage, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{
+    ApiResponse, CreateRecordingRequest, ImportRecordingRequest, Recording, RecordingFrame, RecordingHeader,
+    RecordingLine, ReplayRequest, ReplayStatus, SendMessageRequest, SessionSummary, WebSocketConfig,
+};
+use crate::service::websocket_manager::{open_connection, transient_config, OpenedConnection};
+
+const RECORDING_VERSION: u32 = 1;
+// 回放完成后继续接收服务端消息的时间
+const REPLAY_LINGER: Duration = Duration::from_secs(1);
+// 内存中保留的已结束回放任务数量
+const MAX_FINISHED_REPLAYS: usize = 100;
+// 回放速度系数的范围
+const MIN_SPEED: f64 = 0.01;
+const MAX_SPEED: f64 = 1000.0;
+
+struct Replay {
+    status: Mutex<ReplayStatus>,
+    stop: watch::Sender<bool>,
+}
+
+lazy_static::lazy_static! {
+    static ref REPLAYS: RwLock<HashMap<String, Arc<Replay>>> = RwLock::new(HashMap::new());
+}
+
+// 解析录制文件：首行为header，其余为按时间排序的帧
+fn parse_recording(content: &str) -> Result<(RecordingHeader, Vec<RecordingFrame>), String> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header = match lines.next().map(serde_json::from_str::<RecordingLine>) {
+        Some(Ok(RecordingLine::Header(header))) => header,
+        Some(Ok(RecordingLine::Frame(_))) | None => return Err("recording must start with a header line".to_string()),
+        Some(Err(e)) => return Err(format!("invalid header: {}", e)),
+    };
+    if header.version > RECORDING_VERSION {
+        return Err(format!("unsupported recording version {}", header.version));
+    }
+    let mut frames = Vec::new();
+    for (index, line) in lines.enumerate() {
+        match serde_json::from_str::<RecordingLine>(line) {
+            Ok(RecordingLine::Frame(frame)) => {
+                if frame.direction != "sent" && frame.direction != "received" {
+                    return Err(format!("line {}: invalid direction {}", index + 2, frame.direction));
+                }
+                frames.push(frame);
+            }
+            Ok(RecordingLine::Header(_)) => return Err(format!("line {}: unexpected header", index + 2)),
+            Err(e) => return Err(format!("line {}: {}", index + 2, e)),
+        }
+    }
+    frames.sort_by_key(|frame| frame.offset_us);
+    Ok((header, frames))
+}
+
+fn render_recording(header: &RecordingHeader, frames: &[RecordingFrame]) -> String {
+    let mut out = serde_json::to_string(&RecordingLine::Header(header.clone())).unwrap_or_default();
+    out.push('\n');
+    for frame in frames {
+        out.push_str(&serde_json::to_string(&RecordingLine::Frame(frame.clone())).unwrap_or_default());
+        out.push('\n');
+    }
+    out
+}
+
+async fn insert_recording(
+    pool: &SqlitePool,
+    header: &RecordingHeader,
+    frames: &[RecordingFrame],
+) -> Result<Recording, StatusCode> {
+    let recording = Recording {
+        id: Uuid::new_v4().to_string(),
+        name: header.name.clone(),
+        config_id: header.config_id.clone(),
+        session_id: header.session_id.clone(),
+        ws_url: header.ws_url.clone(),
+        frame_count: frames.len() as i64,
+        duration_us: frames.last().map(|frame| frame.offset_us).unwrap_or(0),
+        created_at: chrono::Utc::now().timestamp(),
+    };
+
+    match sqlx::query(
+        r#"
+        INSERT INTO t_websocket_recording
+        (id, name, config_id, session_id, ws_url, frame_count, duration_us, content, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&recording.id)
+    .bind(&recording.name)
+    .bind(&recording.config_id)
+    .bind(&recording.session_id)
+    .bind(&recording.ws_url)
+    .bind(recording.frame_count)
+    .bind(recording.duration_us)
+    .bind(render_recording(header, frames))
+    .bind(recording.created_at)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => Ok(recording),
+        Err(e) => {
+            tracing::error!("Failed to save recording: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn fetch_content(pool: &SqlitePool, id: &str) -> Result<String, StatusCode> {
+    match sqlx::query_scalar::<_, String>("SELECT content FROM t_websocket_recording WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(content) => Ok(content),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch recording: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn load_recording(pool: &SqlitePool, id: &str) -> Result<(RecordingHeader, Vec<RecordingFrame>), StatusCode> {
+    let content = fetch_content(pool, id).await?;
+    parse_recording(&content).map_err(|e| {
+        tracing::error!("Invalid recording {}: {}", id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+fn replay_speed(speed: Option<f64>) -> Result<f64, StatusCode> {
+    let speed = speed.unwrap_or(1.0);
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        tracing::warn!("Replay speed must be between {} and {}, got {}", MIN_SPEED, MAX_SPEED, speed);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(speed)
+}
+
+// 按原始时间（除以速度系数）等待到帧的发送时刻；导入的录制偏移过大时不再发送
+async fn wait_for(start: Instant, offset_us: i64, speed: f64) {
+    let deadline = Duration::try_from_secs_f64(offset_us.max(0) as f64 / 1_000_000.0 / speed)
+        .ok()
+        .and_then(|delay| start.checked_add(delay));
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+// 获取配置的会话列表（每次连接一个会话）
+pub async fn list_sessions(
+    Path(config_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<SessionSummary>>>, StatusCode> {
+    match sqlx::query_as::<_, SessionSummary>(
+        r#"
+        SELECT session_id,
+               MIN(COALESCE(timestamp_us, timestamp * 1000000)) AS started_at_us,
+               MAX(COALESCE(timestamp_us, timestamp * 1000000)) AS ended_at_us,
+               SUM(CASE WHEN message_type = 'sent' THEN 1 ELSE 0 END) AS sent,
+               SUM(CASE WHEN message_type = 'received' THEN 1 ELSE 0 END) AS received
+        FROM t_websocket_message
+        WHERE config_id = ? AND session_id IS NOT NULL
+        GROUP BY session_id
+        ORDER BY started_at_us DESC
+        LIMIT 100
+        "#
+    )
+    .bind(&config_id)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(sessions) => Ok(Json(ApiResponse::ok(sessions))),
+        Err(e) => {
+            tracing::error!("Failed to fetch sessions: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 从会话的消息记录生成录制
+pub async fn create_recording(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateRecordingRequest>,
+) -> Result<Json<ApiResponse<Recording>>, StatusCode> {
+    let rows = match sqlx::query_as::<_, (String, String, String, Option<String>, Option<String>, i64)>(
+        r#"
+        SELECT config_id, message_type, content, topic, event,
+               COALESCE(timestamp_us, timestamp * 1000000) AS ts
+        FROM t_websocket_message
+        WHERE session_id = ? AND status = 'success'
+        ORDER BY ts, rowid
+        "#
+    )
+    .bind(&payload.session_id)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to fetch session messages: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let Some((config_id, ..)) = rows.first() else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let started_at_us = rows[0].5;
+
+    let config = sqlx::query_as::<_, WebSocketConfig>("SELECT * FROM t_websocket_config WHERE id = ?")
+        .bind(config_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch websocket config: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let header = RecordingHeader {
+        version: RECORDING_VERSION,
+        name: payload
+            .name
+            .or_else(|| config.as_ref().map(|c| c.name.clone()))
+            .unwrap_or_else(|| payload.session_id.clone()),
+        ws_url: config.as_ref().map(|c| c.ws_url.clone()),
+        protocol: config.as_ref().map(|c| c.protocol.clone()),
+        config_id: Some(config_id.clone()),
+        session_id: Some(payload.session_id.clone()),
+        started_at_us,
+    };
+    let frames: Vec<RecordingFrame> = rows
+        .into_iter()
+        .map(|(_, direction, content, topic, event, ts)| RecordingFrame {
+            direction,
+            offset_us: ts - started_at_us,
+            topic,
+            event,
+            content,
+        })
+        .collect();
+
+    insert_recording(&state.pool, &header, &frames).await.map(|recording| Json(ApiResponse::ok(recording)))
+}
+
+// 导入JSONL录制文件
+pub async fn import_recording(
+    State(state): State<AppState>,
+    Json(payload): Json<ImportRecordingRequest>,
+) -> Result<Json<ApiResponse<Recording>>, StatusCode> {
+    let (mut header, frames) = parse_recording(&payload.content).map_err(|e| {
+        tracing::warn!("Invalid recording: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    if let Some(name) = payload.name {
+        header.name = name;
+    }
+    insert_recording(&state.pool, &header, &frames).await.map(|recording| Json(ApiResponse::ok(recording)))
+}
+
+// 获取录制列表
+pub async fn list_recordings(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Recording>>>, StatusCode> {
+    match sqlx::query_as::<_, Recording>(
+        r#"
+        SELECT id, name, config_id, session_id, ws_url, frame_count, duration_us, created_at
+        FROM t_websocket_recording ORDER BY created_at DESC
+        "#
+    )
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(recordings) => Ok(Json(ApiResponse::ok(recordings))),
+        Err(e) => {
+            tracing::error!("Failed to fetch recordings: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个录制
+pub async fn get_recording(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Recording>>, StatusCode> {
+    match sqlx::query_as::<_, Recording>(
+        r#"
+        SELECT id, name, config_id, session_id, ws_url, frame_count, duration_us, created_at
+        FROM t_websocket_recording WHERE id = ?
+        "#
+    )
+    .bind(&id)
+    .fetch_one(&state.pool)
+    .await
+    {
+        Ok(recording) => Ok(Json(ApiResponse::ok(recording))),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch recording: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 下载JSONL录制文件
+pub async fn download_recording(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let content = fetch_content(&state.pool, &id).await?;
+    let disposition = format!("attachment; filename=\"recording-{}.jsonl\"", id);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        content,
+    )
+        .into_response())
+}
+
+// 删除录制
+pub async fn delete_recording(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_recording WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(Json(ApiResponse::ok(()))),
+        Err(e) => {
+            tracing::error!("Failed to delete recording: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 作为客户端回放：按原始时间向目标地址发送录制中的 sent 帧
+pub async fn start_replay(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<ReplayRequest>,
+) -> Result<Json<ApiResponse<ReplayStatus>>, StatusCode> {
+    let speed = replay_speed(payload.speed)?;
+    let (header, frames) = load_recording(&state.pool, &id).await?;
+
+    let mut config = match &payload.config_id {
+        Some(config_id) => match sqlx::query_as::<_, WebSocketConfig>(
+            "SELECT * FROM t_websocket_config WHERE id = ?"
+        )
+        .bind(config_id)
+        .fetch_one(&state.pool)
+        .await
+        {
+            Ok(config) => config,
+            Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+            Err(e) => {
+                tracing::error!("Failed to fetch websocket config: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        },
+        None => transient_config("replay", ""),
+    };
+    // 回放时不自动发送保存的订阅，订阅帧已包含在录制中
+    config.filters = None;
+    match payload.ws_url.or(header.ws_url) {
+        Some(ws_url) => config.ws_url = ws_url,
+        None if payload.config_id.is_some() => {}
+        None => return Err(StatusCode::BAD_REQUEST),
+    }
+
+    let frames: Vec<RecordingFrame> = frames.into_iter().filter(|frame| frame.direction == "sent").collect();
+    let status = ReplayStatus {
+        id: Uuid::new_v4().to_string(),
+        recording_id: id,
+        target: config.ws_url.clone(),
+        speed,
+        status: "running".to_string(),
+        frames_total: frames.len() as u64,
+        frames_sent: 0,
+        frames_received: 0,
+        started_at: chrono::Utc::now().timestamp(),
+        finished_at: None,
+        error: None,
+    };
+    let (stop, _) = watch::channel(false);
+    let replay = Arc::new(Replay { status: Mutex::new(status.clone()), stop });
+
+    {
+        let mut replays = REPLAYS.write().await;
+        if replays.len() >= MAX_FINISHED_REPLAYS {
+            let mut finished = Vec::new();
+            for (replay_id, replay) in replays.iter() {
+                if replay.status.lock().await.status != "running" {
+                    finished.push(replay_id.clone());
+                }
+            }
+            for replay_id in finished {
+                replays.remove(&replay_id);
+            }
+        }
+        replays.insert(status.id.clone(), replay.clone());
+    }
+    tokio::spawn(run_replay(replay, config, frames, speed));
+
+    Ok(Json(ApiResponse::ok(status)))
+}
+
+async fn run_replay(replay: Arc<Replay>, config: WebSocketConfig, frames: Vec<RecordingFrame>, speed: f64) {
+    let mut stop = replay.stop.subscribe();
+    let result = tokio::select! {
+        result = replay_frames(&replay, &config, &frames, speed) => result,
+        _ = stop.changed() => Err("stopped".to_string()),
+    };
+
+    let mut status = replay.status.lock().await;
+    status.finished_at = Some(chrono::Utc::now().timestamp());
+    match result {
+        Ok(()) => status.status = "completed".to_string(),
+        Err(_) if *stop.borrow() => status.status = "stopped".to_string(),
+        Err(e) => {
+            tracing::warn!("Replay {} failed: {}", status.id, e);
+            status.status = "failed".to_string();
+            status.error = Some(e);
+        }
+    }
+}
+
+async fn replay_frames(
+    replay: &Replay,
+    config: &WebSocketConfig,
+    frames: &[RecordingFrame],
+    speed: f64,
+) -> Result<(), String> {
+    let OpenedConnection { stream, mut adapter, opening } =
+        open_connection(config).await.map_err(|e| format!("connect failed: {}", e))?;
+    let (mut sink, mut source) = stream.split();
+    for frame in opening {
+        sink.send(frame).await.map_err(|e| e.to_string())?;
+    }
+
+    let start = Instant::now();
+    let mut next = frames.iter();
+    let mut pending = next.next();
+    let mut linger_until: Option<Instant> = None;
+
+    loop {
+        let due = async move {
+            match (pending, linger_until) {
+                (Some(frame), _) => wait_for(start, frame.offset_us, speed).await,
+                (None, Some(until)) => tokio::time::sleep_until(until).await,
+                (None, None) => {}
+            }
+        };
+        tokio::select! {
+            _ = due => {
+                let Some(frame) = pending else {
+                    if linger_until.is_some() {
+                        break;
+                    }
+                    linger_until = Some(Instant::now() + REPLAY_LINGER);
+                    continue;
+                };
+                let request = SendMessageRequest {
+                    config_id: config.id.clone(),
+                    message: frame.content.clone(),
+                    custom_headers: None,
+                    topic: frame.topic.clone(),
+                    event: frame.event.clone(),
+                    qos: None,
+                    retain: None,
+                };
+                for message in adapter.encode_outgoing(&request).map_err(|e| e.to_string())? {
+                    sink.send(message).await.map_err(|e| e.to_string())?;
+                }
+                replay.status.lock().await.frames_sent += 1;
+                pending = next.next();
+            }
+            incoming = source.next() => match incoming {
+                None | Some(Ok(Message::Close(_))) => {
+                    if pending.is_some() {
+                        return Err("connection closed by server".to_string());
+                    }
+                    break;
+                }
+                Some(Err(e)) => return Err(e.to_string()),
+                Some(Ok(message)) => {
+                    let decoded = adapter.decode_incoming(message).map_err(|e| e.to_string())?;
+                    for reply in decoded.replies {
+                        let _ = sink.send(reply).await;
+                    }
+                    replay.status.lock().await.frames_received += decoded.messages.len() as u64;
+                }
+            },
+        }
+    }
+
+    let _ = sink.close().await;
+    Ok(())
+}
+
+// 获取回放任务状态
+pub async fn get_replay(
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<ReplayStatus>>, StatusCode> {
+    let replays = REPLAYS.read().await;
+    let replay = replays.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let status = replay.status.lock().await.clone();
+    Ok(Json(ApiResponse::ok(status)))
+}
+
+// 停止回放任务
+pub async fn stop_replay(
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let replays = REPLAYS.read().await;
+    let replay = replays.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let _ = replay.stop.send(true);
+    Ok(Json(ApiResponse::ok(())))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerReplayQuery {
+    pub speed: Option<f64>,
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+// GET /mock/recordings/:id（WebSocket升级）：作为服务端回放录制中的 received 帧
+pub async fn replay_endpoint(
+    Path(id): Path<String>,
+    Query(query): Query<ServerReplayQuery>,
+    State(state): State<AppState>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let speed = replay_speed(query.speed)?;
+    let (_, frames) = load_recording(&state.pool, &id).await?;
+    let frames: Vec<RecordingFrame> = frames.into_iter().filter(|frame| frame.direction == "received").collect();
+    Ok(ws.on_upgrade(move |socket| serve_replay(socket, frames, speed, query.repeat)))
+}
+
+async fn serve_replay(socket: WebSocket, frames: Vec<RecordingFrame>, speed: f64, repeat: bool) {
+    let (mut sender, mut receiver) = socket.split();
+
+    // 客户端发来的消息直接丢弃，断开时结束回放
+    let drain = async {
+        while let Some(Ok(message)) = receiver.next().await {
+            if matches!(message, ServerMessage::Close(_)) {
+                break;
+            }
+        }
+    };
+    let play = async {
+        loop {
+            let start = Instant::now();
+            for frame in &frames {
+                wait_for(start, frame.offset_us, speed).await;
+                if sender.send(ServerMessage::Text(frame.content.clone())).await.is_err() {
+                    return;
+                }
+            }
+            if !repeat || frames.is_empty() {
+                break;
+            }
+        }
+        let _ = sender.send(ServerMessage::Close(None)).await;
+    };
+
+    tokio::select! {
+        _ = drain => {},
+        _ = play => {},
+    }
+}
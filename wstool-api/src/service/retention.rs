@@ -0,0 +1,582 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{
+    ApiResponse, MessageArchive, RestoreResult, RetentionPolicy, RetentionReport, UpsertRetentionPolicy,
+    WebSocketMessage,
+};
+
+const GLOBAL_SCOPE: &str = "global";
+// 每批处理的过期消息数量
+const BATCH_SIZE: i64 = 5000;
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+lazy_static::lazy_static! {
+    // 后台任务与手动触发不并发执行
+    static ref RUN_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ArchiveQuery {
+    pub config_id: Option<String>,
+}
+
+fn archive_dir() -> std::path::PathBuf {
+    std::env::var("ARCHIVE_DIR")
+        .unwrap_or_else(|_| "archives".to_string())
+        .into()
+}
+
+// 启动后台保留任务，间隔由 RETENTION_INTERVAL_SECS 控制
+pub fn spawn_retention_task(pool: SqlitePool) {
+    let secs = std::env::var("RETENTION_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_INTERVAL_SECS);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(secs));
+        // 第一次 tick 立即返回，跳过以免拖慢启动
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            match run_retention(&pool).await {
+                Ok(report) if report.deleted > 0 => tracing::info!(
+                    "Retention removed {} messages ({} archived)",
+                    report.deleted,
+                    report.archived
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!("Retention run failed: {}", e),
+            }
+        }
+    });
+}
+
+// 按策略清理所有配置的过期消息：配置级策略覆盖全局策略
+pub async fn run_retention(pool: &SqlitePool) -> anyhow::Result<RetentionReport> {
+    let _guard = RUN_LOCK.lock().await;
+
+    let mut policies: HashMap<String, RetentionPolicy> =
+        sqlx::query_as::<_, RetentionPolicy>("SELECT * FROM t_websocket_retention")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|policy| (policy.scope.clone(), policy))
+            .collect();
+    let global = policies.remove(GLOBAL_SCOPE);
+
+    let config_ids: Vec<String> = sqlx::query_scalar("SELECT id FROM t_websocket_config")
+        .fetch_all(pool)
+        .await?;
+
+    let mut report = RetentionReport::default();
+    for config_id in config_ids {
+        let Some(policy) = policies.get(&config_id).or(global.as_ref()) else {
+            continue;
+        };
+        report.configs += 1;
+        apply_policy(pool, &config_id, policy, &mut report).await?;
+    }
+
+    if report.deleted > 0 {
+        // 增量 VACUUM 不改变 rowid，无需重建全文索引
+        sqlx::query("PRAGMA incremental_vacuum").execute(pool).await?;
+    }
+    Ok(report)
+}
+
+async fn apply_policy(
+    pool: &SqlitePool,
+    config_id: &str,
+    policy: &RetentionPolicy,
+    report: &mut RetentionReport,
+) -> anyhow::Result<()> {
+    // 过期范围的上界 (接收时间微秒, rowid)，只在开始时计算一次，之后按索引范围分批删除
+    let mut bound = policy
+        .max_age_secs
+        .map(|secs| ((chrono::Utc::now().timestamp() - secs) * 1_000_000 - 1, i64::MAX));
+    if policy.max_rows.is_some() || policy.max_bytes.is_some() {
+        // 从最新消息往前累计行数与字节数，第一条超出任一上限的消息及更早的消息都已过期
+        let exceeded: Option<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT time_us, rowid FROM (
+                SELECT COALESCE(timestamp_us, timestamp * 1000000) AS time_us, rowid,
+                    ROW_NUMBER() OVER newest AS row_rank,
+                    SUM(LENGTH(CAST(content AS BLOB))) OVER newest AS bytes_total
+                FROM t_websocket_message
+                WHERE config_id = ?
+                WINDOW newest AS (ORDER BY COALESCE(timestamp_us, timestamp * 1000000) DESC, rowid DESC)
+            )
+            WHERE row_rank > ? OR bytes_total > ?
+            ORDER BY row_rank
+            LIMIT 1
+            "#
+        )
+        .bind(config_id)
+        .bind(policy.max_rows)
+        .bind(policy.max_bytes)
+        .fetch_optional(pool)
+        .await?;
+        bound = bound.max(exceeded);
+    }
+    let Some((bound_us, bound_rowid)) = bound else {
+        return Ok(());
+    };
+
+    loop {
+        // 发件箱中待发送的消息不清理
+        let expired = sqlx::query_as::<_, WebSocketMessage>(
+            r#"
+            SELECT * FROM t_websocket_message
+            WHERE config_id = ? AND status != 'pending'
+                AND (COALESCE(timestamp_us, timestamp * 1000000), rowid) <= (?, ?)
+            ORDER BY COALESCE(timestamp_us, timestamp * 1000000), rowid
+            LIMIT ?
+            "#
+        )
+        .bind(config_id)
+        .bind(bound_us)
+        .bind(bound_rowid)
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+        if expired.is_empty() {
+            return Ok(());
+        }
+
+        if policy.archive {
+            for archive in write_archives(pool, config_id, &expired).await? {
+                report.archived += archive.row_count as u64;
+                report.archives.push(archive.id);
+            }
+        }
+
+        let mut tx = pool.begin().await?;
+        for message in &expired {
+            sqlx::query("DELETE FROM t_websocket_message WHERE id = ?")
+                .bind(&message.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        report.deleted += expired.len() as u64;
+
+        if (expired.len() as i64) < BATCH_SIZE {
+            return Ok(());
+        }
+    }
+}
+
+// 按 UTC 日期分区写入 gzip 压缩的 NDJSON 文件，并登记归档记录
+async fn write_archives(
+    pool: &SqlitePool,
+    config_id: &str,
+    messages: &[WebSocketMessage],
+) -> anyhow::Result<Vec<MessageArchive>> {
+    let mut partitions: BTreeMap<String, Vec<&WebSocketMessage>> = BTreeMap::new();
+    for message in messages {
+        let period = chrono::DateTime::from_timestamp(message.timestamp, 0)
+            .unwrap_or_default()
+            .format("%Y-%m-%d")
+            .to_string();
+        partitions.entry(period).or_default().push(message);
+    }
+
+    let mut archives = Vec::new();
+    for (period, messages) in partitions {
+        let id = Uuid::new_v4().to_string();
+        let dir = archive_dir().join(config_id).join(&period);
+        let path = dir.join(format!("{}.ndjson.gz", id));
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for message in &messages {
+            serde_json::to_writer(&mut encoder, message)?;
+            encoder.write_all(b"\n")?;
+        }
+        let compressed = encoder.finish()?;
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(&path, &compressed).await?;
+
+        let archive = MessageArchive {
+            id,
+            config_id: config_id.to_string(),
+            period,
+            path: path.to_string_lossy().into_owned(),
+            row_count: messages.len() as i64,
+            bytes: compressed.len() as i64,
+            min_timestamp: messages.iter().map(|m| m.timestamp).min().unwrap_or_default(),
+            max_timestamp: messages.iter().map(|m| m.timestamp).max().unwrap_or_default(),
+            created_at: chrono::Utc::now().timestamp(),
+            restored_at: None,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO t_websocket_archive
+            (id, config_id, period, path, row_count, bytes, min_timestamp, max_timestamp, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&archive.id)
+        .bind(&archive.config_id)
+        .bind(&archive.period)
+        .bind(&archive.path)
+        .bind(archive.row_count)
+        .bind(archive.bytes)
+        .bind(archive.min_timestamp)
+        .bind(archive.max_timestamp)
+        .bind(archive.created_at)
+        .execute(pool)
+        .await?;
+        archives.push(archive);
+    }
+    Ok(archives)
+}
+
+async fn scope_exists(pool: &SqlitePool, scope: &str) -> Result<bool, StatusCode> {
+    if scope == GLOBAL_SCOPE {
+        return Ok(true);
+    }
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM t_websocket_config WHERE id = ?")
+        .bind(scope)
+        .fetch_one(pool)
+        .await
+        .map(|count| count > 0)
+        .map_err(|e| {
+            tracing::error!("Failed to fetch websocket config: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// 获取所有保留策略
+pub async fn list_policies(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<RetentionPolicy>>>, StatusCode> {
+    match sqlx::query_as::<_, RetentionPolicy>("SELECT * FROM t_websocket_retention ORDER BY scope")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(policies) => Ok(Json(ApiResponse::ok(policies))),
+        Err(e) => {
+            tracing::error!("Failed to fetch retention policies: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个保留策略（scope 为 "global" 或配置ID）
+pub async fn get_policy(
+    Path(scope): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<RetentionPolicy>>, StatusCode> {
+    match sqlx::query_as::<_, RetentionPolicy>("SELECT * FROM t_websocket_retention WHERE scope = ?")
+        .bind(&scope)
+        .fetch_one(&state.pool)
+        .await
+    {
+        Ok(policy) => Ok(Json(ApiResponse::ok(policy))),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch retention policy: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 创建或替换保留策略
+pub async fn upsert_policy(
+    Path(scope): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpsertRetentionPolicy>,
+) -> Result<Json<ApiResponse<RetentionPolicy>>, StatusCode> {
+    let limits = [payload.max_age_secs, payload.max_rows, payload.max_bytes];
+    if limits.iter().any(|limit| limit.is_some_and(|value| value <= 0)) {
+        tracing::warn!("Invalid retention policy for {}: {:?}", scope, payload);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if !scope_exists(&state.pool, &scope).await? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let policy = RetentionPolicy {
+        scope,
+        max_age_secs: payload.max_age_secs,
+        max_rows: payload.max_rows,
+        max_bytes: payload.max_bytes,
+        archive: payload.archive.unwrap_or(false),
+        updated_at: chrono::Utc::now().timestamp(),
+    };
+
+    match sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO t_websocket_retention
+        (scope, max_age_secs, max_rows, max_bytes, archive, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&policy.scope)
+    .bind(policy.max_age_secs)
+    .bind(policy.max_rows)
+    .bind(policy.max_bytes)
+    .bind(policy.archive)
+    .bind(policy.updated_at)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::ok(policy))),
+        Err(e) => {
+            tracing::error!("Failed to save retention policy: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除保留策略（配置回退到全局策略）
+pub async fn delete_policy(
+    Path(scope): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_retention WHERE scope = ?")
+        .bind(&scope)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(Json(ApiResponse::ok(()))),
+        Err(e) => {
+            tracing::error!("Failed to delete retention policy: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 立即执行一次保留清理
+pub async fn run_now(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<RetentionReport>>, StatusCode> {
+    match run_retention(&state.pool).await {
+        Ok(report) => Ok(Json(ApiResponse::ok(report))),
+        Err(e) => {
+            tracing::error!("Retention run failed: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取归档列表，可按配置过滤
+pub async fn list_archives(
+    Query(query): Query<ArchiveQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<MessageArchive>>>, StatusCode> {
+    match sqlx::query_as::<_, MessageArchive>(
+        r#"
+        SELECT * FROM t_websocket_archive
+        WHERE (? IS NULL OR config_id = ?)
+        ORDER BY period DESC, created_at DESC
+        "#
+    )
+    .bind(&query.config_id)
+    .bind(&query.config_id)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(archives) => Ok(Json(ApiResponse::ok(archives))),
+        Err(e) => {
+            tracing::error!("Failed to fetch message archives: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn read_archive(compressed: &[u8]) -> Result<Vec<WebSocketMessage>, String> {
+    let mut content = String::new();
+    GzDecoder::new(compressed)
+        .read_to_string(&mut content)
+        .map_err(|e| format!("invalid archive: {}", e))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {}", index + 1, e)))
+        .collect()
+}
+
+// 将归档恢复到消息表，已存在的消息跳过
+// 恢复的消息若仍超出保留策略，会在下一轮清理时重新归档
+pub async fn restore_archive(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<RestoreResult>>, StatusCode> {
+    let archive = match sqlx::query_as::<_, MessageArchive>("SELECT * FROM t_websocket_archive WHERE id = ?")
+        .bind(&id)
+        .fetch_one(&state.pool)
+        .await
+    {
+        Ok(archive) => archive,
+        Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch message archive: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    if !scope_exists(&state.pool, &archive.config_id).await? {
+        tracing::warn!("Archive {} belongs to deleted config {}", archive.id, archive.config_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let compressed = tokio::fs::read(&archive.path).await.map_err(|e| {
+        tracing::warn!("Failed to read archive file {}: {}", archive.path, e);
+        StatusCode::NOT_FOUND
+    })?;
+    let messages = read_archive(&compressed).map_err(|e| {
+        tracing::error!("Failed to read archive {}: {}", archive.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let result: Result<u64, sqlx::Error> = async {
+        let mut tx = state.pool.begin().await?;
+        let mut restored = 0;
+        for message in &messages {
+            restored += sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO t_websocket_message
+                (id, config_id, message_type, content, timestamp, status, error_message, topic, event, session_id, timestamp_us, valid, validation_error, raw_content)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(&message.id)
+            .bind(&archive.config_id)
+            .bind(&message.message_type)
+            .bind(&message.content)
+            .bind(message.timestamp)
+            .bind(&message.status)
+            .bind(&message.error_message)
+            .bind(&message.topic)
+            .bind(&message.event)
+            .bind(&message.session_id)
+            .bind(message.timestamp_us)
+            .bind(message.valid)
+            .bind(&message.validation_error)
+            .bind(&message.raw_content)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        sqlx::query("UPDATE t_websocket_archive SET restored_at = ? WHERE id = ?")
+            .bind(chrono::Utc::now().timestamp())
+            .bind(&archive.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(restored)
+    }
+    .await;
+
+    match result {
+        Ok(restored) => Ok(Json(ApiResponse::ok(RestoreResult {
+            restored,
+            skipped: messages.len() as u64 - restored,
+        }))),
+        Err(e) => {
+            tracing::error!("Failed to restore message archive: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::migrate(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO t_websocket_config (id, name, ws_url, config_type, created_at, updated_at) VALUES ('config-1', 'test', 'ws://127.0.0.1', 'subscriber', 0, 0)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool
+    }
+
+    // 按秒递增的 count 条消息，从 start 开始
+    async fn insert_messages(pool: &SqlitePool, start: i64, count: i64, status: &str) {
+        let mut tx = pool.begin().await.unwrap();
+        for timestamp in start..start + count {
+            sqlx::query(
+                "INSERT INTO t_websocket_message (id, config_id, message_type, content, timestamp, status, timestamp_us) VALUES (?, 'config-1', 'sent', 'ping', ?, ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(timestamp)
+            .bind(status)
+            .bind(timestamp * 1_000_000)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+        tx.commit().await.unwrap();
+    }
+
+    async fn remaining_messages(pool: &SqlitePool) -> Vec<(i64, String)> {
+        sqlx::query_as("SELECT timestamp, status FROM t_websocket_message ORDER BY timestamp")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    fn policy(max_age_secs: Option<i64>, max_rows: Option<i64>, max_bytes: Option<i64>) -> RetentionPolicy {
+        RetentionPolicy { scope: "config-1".to_string(), max_age_secs, max_rows, max_bytes, archive: false, updated_at: 0 }
+    }
+
+    // 超出行数上限的消息分多批删除，发件箱中待发送的消息保留
+    #[tokio::test]
+    async fn deletes_rows_over_the_limit_in_batches() {
+        let pool = test_pool().await;
+        insert_messages(&pool, 0, 2, "pending").await;
+        insert_messages(&pool, 2, BATCH_SIZE * 2 + 10, "success").await;
+
+        let mut report = RetentionReport::default();
+        apply_policy(&pool, "config-1", &policy(None, Some(5), None), &mut report).await.unwrap();
+        assert_eq!(report.deleted, (BATCH_SIZE * 2 + 5) as u64);
+        let remaining = remaining_messages(&pool).await;
+        assert_eq!(remaining.len(), 7);
+        assert!(remaining[..2].iter().all(|(_, status)| status == "pending"));
+        assert_eq!(remaining[2].0, BATCH_SIZE * 2 + 7);
+    }
+
+    #[tokio::test]
+    async fn deletes_rows_by_age_and_size() {
+        let pool = test_pool().await;
+        let now = chrono::Utc::now().timestamp();
+        insert_messages(&pool, now - 100, 100, "success").await;
+
+        let mut report = RetentionReport::default();
+        apply_policy(&pool, "config-1", &policy(Some(30), None, None), &mut report).await.unwrap();
+        let remaining = remaining_messages(&pool).await;
+        assert_eq!(remaining.len(), 30);
+        assert_eq!(remaining[0].0, now - 30);
+
+        // 每条 4 字节，最多保留 10 条
+        apply_policy(&pool, "config-1", &policy(Some(30), None, Some(42)), &mut report).await.unwrap();
+        assert_eq!(report.deleted, 90);
+        assert_eq!(remaining_messages(&pool).await[0].0, now - 10);
+    }
+}
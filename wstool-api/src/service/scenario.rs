@@ -0,0 +1,528 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures::{SinkExt, StreamExt};
+use regex::Regex;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{
+    ApiResponse, NewScenario, RunScenarioRequest, Scenario, ScenarioReport, ScenarioResult,
+    StepReport, UpdateScenario, WebSocketConfig,
+};
+use crate::service::websocket_manager::{build_url_request, WebSocketConnection};
+use crate::utils::json::{self, Assertion};
+
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_EXPECT_TIMEOUT_MS: u64 = 5_000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Step {
+    Connect {
+        ws_url: Option<String>,
+        timeout_ms: Option<u64>,
+    },
+    Send {
+        message: String,
+    },
+    // 在 timeout_ms 窗口内至少收到 count 条满足全部断言的消息
+    Expect {
+        #[serde(default)]
+        assertions: Vec<Assertion>,
+        regex: Option<String>,
+        count: Option<u64>,
+        timeout_ms: Option<u64>,
+    },
+    Sleep {
+        ms: u64,
+    },
+    Disconnect,
+}
+
+impl Step {
+    fn name(&self) -> &'static str {
+        match self {
+            Step::Connect { .. } => "connect",
+            Step::Send { .. } => "send",
+            Step::Expect { .. } => "expect",
+            Step::Sleep { .. } => "sleep",
+            Step::Disconnect => "disconnect",
+        }
+    }
+}
+
+fn parse_steps(raw: &str) -> Result<Vec<Step>, String> {
+    let steps: Vec<Step> = serde_json::from_str(raw).map_err(|e| format!("invalid steps: {}", e))?;
+    for step in &steps {
+        if let Step::Expect { regex: Some(pattern), .. } = step {
+            Regex::new(pattern).map_err(|e| format!("invalid expect regex: {}", e))?;
+        }
+    }
+    Ok(steps)
+}
+
+// 场景执行器：按顺序执行步骤，失败后余下步骤记为 skipped
+struct Runner {
+    target: String,
+    headers: Option<String>,
+    socket: Option<WebSocketConnection>,
+}
+
+impl Runner {
+    async fn run_step(&mut self, step: &Step) -> Result<Option<String>, String> {
+        match step {
+            Step::Connect { ws_url, timeout_ms } => {
+                let url = ws_url.clone().unwrap_or_else(|| self.target.clone());
+                let request = build_url_request(&url, self.headers.as_deref(), None).map_err(|e| e.to_string())?;
+                let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS));
+                let (socket, _) = tokio::time::timeout(timeout, connect_async(request))
+                    .await
+                    .map_err(|_| format!("connect timed out after {} ms", timeout.as_millis()))?
+                    .map_err(|e| format!("connect failed: {}", e))?;
+                self.socket = Some(socket);
+                Ok(Some(format!("connected to {}", url)))
+            }
+            Step::Send { message } => {
+                let socket = self.socket.as_mut().ok_or("not connected")?;
+                socket
+                    .send(Message::Text(message.clone()))
+                    .await
+                    .map_err(|e| format!("send failed: {}", e))?;
+                Ok(None)
+            }
+            Step::Expect { assertions, regex, count, timeout_ms } => {
+                let socket = self.socket.as_mut().ok_or("not connected")?;
+                let regex = regex.as_deref().map(Regex::new).transpose().map_err(|e| e.to_string())?;
+                let wanted = count.unwrap_or(1);
+                let window = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_EXPECT_TIMEOUT_MS));
+                let deadline = tokio::time::Instant::now() + window;
+                let mut matched = 0u64;
+                let mut last: Option<String> = None;
+
+                while matched < wanted {
+                    let text = match tokio::time::timeout_at(deadline, socket.next()).await {
+                        Err(_) => break,
+                        Ok(None) | Ok(Some(Ok(Message::Close(_)))) => {
+                            return Err(format!("connection closed after {} matching message(s)", matched));
+                        }
+                        Ok(Some(Err(e))) => return Err(format!("receive failed: {}", e)),
+                        Ok(Some(Ok(Message::Text(text)))) => text,
+                        Ok(Some(Ok(Message::Binary(data)))) => String::from_utf8_lossy(&data).into_owned(),
+                        Ok(Some(Ok(_))) => continue,
+                    };
+                    if json::message_matches(&text, assertions, regex.as_ref()) {
+                        matched += 1;
+                    }
+                    last = Some(text);
+                }
+
+                if matched >= wanted {
+                    Ok(Some(format!("matched {} message(s)", matched)))
+                } else {
+                    Err(format!(
+                        "expected {} matching message(s) within {} ms, got {}; last message: {}",
+                        wanted,
+                        window.as_millis(),
+                        matched,
+                        last.unwrap_or_else(|| "<none>".to_string())
+                    ))
+                }
+            }
+            Step::Sleep { ms } => {
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+                Ok(None)
+            }
+            Step::Disconnect => {
+                if let Some(mut socket) = self.socket.take() {
+                    let _ = socket.close(None).await;
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+async fn run_scenario(scenario: &Scenario, target: String, headers: Option<String>) -> Result<ScenarioReport, String> {
+    let steps = parse_steps(&scenario.steps)?;
+    let started = Instant::now();
+    let mut runner = Runner { target: target.clone(), headers, socket: None };
+    let mut failed = false;
+    let mut reports = Vec::with_capacity(steps.len());
+
+    for (index, step) in steps.iter().enumerate() {
+        if failed {
+            reports.push(StepReport {
+                index,
+                step_type: step.name().to_string(),
+                status: "skipped".to_string(),
+                duration_ms: 0,
+                detail: None,
+                error: None,
+            });
+            continue;
+        }
+        let step_started = Instant::now();
+        let result = runner.run_step(step).await;
+        let duration_ms = step_started.elapsed().as_millis() as u64;
+        let (status, detail, error) = match result {
+            Ok(detail) => ("passed", detail, None),
+            Err(error) => {
+                failed = true;
+                ("failed", None, Some(error))
+            }
+        };
+        reports.push(StepReport {
+            index,
+            step_type: step.name().to_string(),
+            status: status.to_string(),
+            duration_ms,
+            detail,
+            error,
+        });
+    }
+
+    if let Some(mut socket) = runner.socket.take() {
+        let _ = socket.close(None).await;
+    }
+
+    Ok(ScenarioReport {
+        scenario_id: scenario.id.clone(),
+        scenario_name: scenario.name.clone(),
+        target,
+        passed: !failed,
+        duration_ms: started.elapsed().as_millis() as u64,
+        steps: reports,
+    })
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// 生成 JUnit XML：一个场景对应一个 testsuite，每个步骤一个 testcase
+fn junit_xml(report: &ScenarioReport, started_at: i64) -> String {
+    let failures = report.steps.iter().filter(|s| s.status == "failed").count();
+    let skipped = report.steps.iter().filter(|s| s.status == "skipped").count();
+    let suite = escape_xml(&report.scenario_name);
+    let timestamp = chrono::DateTime::from_timestamp(started_at, 0)
+        .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+        .unwrap_or_default();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        report.steps.len(),
+        failures,
+        skipped,
+        report.duration_ms as f64 / 1000.0
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\" timestamp=\"{}\" hostname=\"{}\">\n",
+        suite,
+        report.steps.len(),
+        failures,
+        skipped,
+        report.duration_ms as f64 / 1000.0,
+        timestamp,
+        escape_xml(&report.target)
+    ));
+    for step in &report.steps {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{:02} {}\" time=\"{:.3}\"",
+            suite,
+            step.index + 1,
+            step.step_type,
+            step.duration_ms as f64 / 1000.0
+        ));
+        match step.status.as_str() {
+            "failed" => {
+                let error = escape_xml(step.error.as_deref().unwrap_or_default());
+                xml.push_str(&format!(">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n", error, error));
+            }
+            "skipped" => xml.push_str(">\n      <skipped/>\n    </testcase>\n"),
+            _ => xml.push_str("/>\n"),
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+async fn fetch_scenario(pool: &SqlitePool, id: &str) -> Result<Scenario, StatusCode> {
+    match sqlx::query_as::<_, Scenario>("SELECT * FROM t_websocket_scenario WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(scenario) => Ok(scenario),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch scenario: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn fetch_result(pool: &SqlitePool, id: &str) -> Result<ScenarioResult, StatusCode> {
+    match sqlx::query_as::<_, ScenarioResult>("SELECT * FROM t_websocket_scenario_result WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(result) => Ok(result),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch scenario result: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取场景列表
+pub async fn list_scenarios(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Scenario>>>, StatusCode> {
+    match sqlx::query_as::<_, Scenario>("SELECT * FROM t_websocket_scenario ORDER BY created_at DESC")
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(scenarios) => Ok(Json(ApiResponse::ok(scenarios))),
+        Err(e) => {
+            tracing::error!("Failed to fetch scenarios: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个场景
+pub async fn get_scenario(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Scenario>>, StatusCode> {
+    fetch_scenario(&state.pool, &id).await.map(|scenario| Json(ApiResponse::ok(scenario)))
+}
+
+// 创建场景
+pub async fn create_scenario(
+    State(state): State<AppState>,
+    Json(payload): Json<NewScenario>,
+) -> Result<Json<ApiResponse<Scenario>>, StatusCode> {
+    if let Err(e) = parse_steps(&payload.steps) {
+        tracing::warn!("Invalid scenario: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let now = chrono::Utc::now().timestamp();
+    let scenario = Scenario {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name,
+        description: payload.description,
+        ws_url: payload.ws_url,
+        steps: payload.steps,
+        created_at: now,
+        updated_at: now,
+    };
+
+    match sqlx::query(
+        r#"
+        INSERT INTO t_websocket_scenario (id, name, description, ws_url, steps, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&scenario.id)
+    .bind(&scenario.name)
+    .bind(&scenario.description)
+    .bind(&scenario.ws_url)
+    .bind(&scenario.steps)
+    .bind(scenario.created_at)
+    .bind(scenario.updated_at)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::ok(scenario))),
+        Err(e) => {
+            tracing::error!("Failed to create scenario: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 更新场景（未提供的字段保持不变）
+pub async fn update_scenario(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateScenario>,
+) -> Result<Json<ApiResponse<Scenario>>, StatusCode> {
+    let mut scenario = fetch_scenario(&state.pool, &id).await?;
+    if let Some(steps) = payload.steps {
+        if let Err(e) = parse_steps(&steps) {
+            tracing::warn!("Invalid scenario: {}", e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        scenario.steps = steps;
+    }
+    if let Some(name) = payload.name {
+        scenario.name = name;
+    }
+    if payload.description.is_some() {
+        scenario.description = payload.description;
+    }
+    if payload.ws_url.is_some() {
+        scenario.ws_url = payload.ws_url;
+    }
+    scenario.updated_at = chrono::Utc::now().timestamp();
+
+    match sqlx::query(
+        "UPDATE t_websocket_scenario SET name = ?, description = ?, ws_url = ?, steps = ?, updated_at = ? WHERE id = ?"
+    )
+    .bind(&scenario.name)
+    .bind(&scenario.description)
+    .bind(&scenario.ws_url)
+    .bind(&scenario.steps)
+    .bind(scenario.updated_at)
+    .bind(&scenario.id)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => Ok(Json(ApiResponse::ok(scenario))),
+        Err(e) => {
+            tracing::error!("Failed to update scenario: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除场景
+pub async fn delete_scenario(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_scenario WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => Ok(Json(ApiResponse::ok(()))),
+        Err(e) => {
+            tracing::error!("Failed to delete scenario: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 运行场景并保存结果
+pub async fn run_scenario_handler(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<RunScenarioRequest>,
+) -> Result<Json<ApiResponse<ScenarioReport>>, StatusCode> {
+    let scenario = fetch_scenario(&state.pool, &id).await?;
+
+    // 目标地址优先级：请求中的URL > 配置的URL > 场景默认URL
+    let config = match &payload.config_id {
+        Some(config_id) => match sqlx::query_as::<_, WebSocketConfig>(
+            "SELECT * FROM t_websocket_config WHERE id = ?"
+        )
+        .bind(config_id)
+        .fetch_one(&state.pool)
+        .await
+        {
+            Ok(config) => Some(config),
+            Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+            Err(e) => {
+                tracing::error!("Failed to fetch websocket config: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        },
+        None => None,
+    };
+    let target = payload
+        .ws_url
+        .or_else(|| config.as_ref().map(|c| c.ws_url.clone()))
+        .or_else(|| scenario.ws_url.clone())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let headers = config.and_then(|c| c.headers);
+
+    let started_at = chrono::Utc::now().timestamp();
+    let report = run_scenario(&scenario, target, headers).await.map_err(|e| {
+        tracing::warn!("Invalid scenario {}: {}", id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let report_json = serde_json::to_string(&report).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_scenario_result (id, scenario_id, target, passed, started_at, duration_ms, report)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&scenario.id)
+    .bind(&report.target)
+    .bind(report.passed)
+    .bind(started_at)
+    .bind(report.duration_ms as i64)
+    .bind(&report_json)
+    .execute(&state.pool)
+    .await
+    {
+        tracing::error!("Failed to save scenario result: {}", e);
+    }
+
+    Ok(Json(ApiResponse::ok(report)))
+}
+
+// 获取场景的历史运行结果
+pub async fn list_results(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<ScenarioResult>>>, StatusCode> {
+    match sqlx::query_as::<_, ScenarioResult>(
+        "SELECT * FROM t_websocket_scenario_result WHERE scenario_id = ? ORDER BY started_at DESC LIMIT 100"
+    )
+    .bind(&id)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(results) => Ok(Json(ApiResponse::ok(results))),
+        Err(e) => {
+            tracing::error!("Failed to fetch scenario results: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单次运行结果
+pub async fn get_result(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<ScenarioResult>>, StatusCode> {
+    fetch_result(&state.pool, &id).await.map(|result| Json(ApiResponse::ok(result)))
+}
+
+// 导出单次运行结果为 JUnit XML
+pub async fn get_result_junit(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Response, StatusCode> {
+    let result = fetch_result(&state.pool, &id).await?;
+    let report: ScenarioReport = serde_json::from_str(&result.report).map_err(|e| {
+        tracing::error!("Failed to parse scenario report: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let xml = junit_xml(&report, result.started_at);
+    Ok(([(header::CONTENT_TYPE, "application/xml; charset=utf-8")], xml).into_response())
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use jsonschema::Validator;
+use serde_json::Value;
+
+use crate::models::{MessageSchema, WebSocketConfig};
+use crate::utils::json;
+
+// 违规发生在消息根节点时记录的路径
+const ROOT_PATH: &str = "(root)";
+
+// 编译后的消息校验器：默认 schema 加按判别字段选择的 schema
+pub struct SchemaValidator {
+    default: Option<Validator>,
+    discriminator: Option<String>,
+    schemas: HashMap<String, Validator>,
+    violations_only: bool,
+}
+
+fn compile(schema: &Value) -> Result<Validator, String> {
+    jsonschema::draft202012::new(schema).map_err(|e| format!("invalid schema: {}", e))
+}
+
+impl SchemaValidator {
+    // 按配置中的 message_schema 构建校验器，未配置时返回 None
+    pub fn from_config(config: &WebSocketConfig) -> Result<Option<Self>, String> {
+        match config.message_schema.as_deref() {
+            Some(raw) if !raw.trim().is_empty() => {
+                let schema: MessageSchema =
+                    serde_json::from_str(raw).map_err(|e| format!("invalid message_schema: {}", e))?;
+                Self::new(&schema).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn new(schema: &MessageSchema) -> Result<Self, String> {
+        if !schema.schemas.is_empty() && schema.discriminator.is_none() {
+            return Err("schemas require a discriminator".to_string());
+        }
+        if schema.schema.is_none() && schema.schemas.is_empty() {
+            return Err("message_schema needs a schema or schemas".to_string());
+        }
+        let schemas = schema
+            .schemas
+            .iter()
+            .map(|(key, schema)| compile(schema).map(|validator| (key.clone(), validator)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            default: schema.schema.as_ref().map(compile).transpose()?,
+            discriminator: schema.discriminator.clone(),
+            schemas,
+            violations_only: schema.violations_only,
+        })
+    }
+
+    pub fn violations_only(&self) -> bool {
+        self.violations_only
+    }
+
+    // 校验消息内容；没有适用的 schema 时返回 None，违规时返回 "<路径>: <错误>"
+    pub fn validate(&self, content: &str) -> Option<Result<(), String>> {
+        let value = match serde_json::from_str::<Value>(content) {
+            Ok(value) => value,
+            Err(e) => return self.default.as_ref().map(|_| Err(format!("{}: invalid JSON: {}", ROOT_PATH, e))),
+        };
+        let selected = self
+            .discriminator
+            .as_deref()
+            .and_then(|path| json::lookup(&value, path))
+            .and_then(|key| self.schemas.get(&json::value_to_string(key)));
+        let validator = selected.or(self.default.as_ref())?;
+        Some(
+            validator
+                .validate(&value)
+                .map_err(|e| {
+                    let path = e.instance_path.to_string();
+                    format!("{}: {}", if path.is_empty() { ROOT_PATH } else { &path }, e)
+                }),
+        )
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{ApiResponse, SequenceEvent, SequenceRule, WebSocketConfig};
+use crate::utils::json;
+
+// 两次重新同步之间的最小间隔，避免缺口持续时反复重订阅/重连
+const RESYNC_COOLDOWN: Duration = Duration::from_secs(5);
+
+// 发现缺口后的重新同步方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resync {
+    Resubscribe,
+    Reconnect,
+}
+
+impl Resync {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Resync::Resubscribe => "resubscribe",
+            Resync::Reconnect => "reconnect",
+        }
+    }
+}
+
+// 一次序号异常
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    pub partition_key: String,
+    pub kind: &'static str, // "gap"、"duplicate" 或 "out_of_order"
+    pub expected: i64,
+    pub actual: i64,
+    pub missed: i64,
+}
+
+impl Anomaly {
+    // 重复或乱序（晚到）的消息
+    pub fn is_stale(&self) -> bool {
+        self.kind != "gap"
+    }
+}
+
+// 按配置的序号规则逐条检查接收消息，按分区记录最后一个序号
+pub struct SequenceTracker {
+    path: String,
+    prev_path: Option<String>,
+    key: Option<String>,
+    on_gap: Option<Resync>,
+    resync_message: Option<String>,
+    drop_stale: bool,
+    last: HashMap<String, i64>,
+    last_resync: Option<Instant>,
+}
+
+// 序号可以是数字或数字字符串
+fn as_sequence(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+impl SequenceTracker {
+    // 按配置中的 sequence 构建检查器，未配置时返回 None
+    pub fn from_config(config: &WebSocketConfig) -> Result<Option<Self>, String> {
+        match config.sequence.as_deref() {
+            Some(raw) if !raw.trim().is_empty() => {
+                let rule: SequenceRule = serde_json::from_str(raw).map_err(|e| format!("invalid sequence: {}", e))?;
+                Self::new(&rule).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn new(rule: &SequenceRule) -> Result<Self, String> {
+        if json::path_keys(&rule.path).is_empty() {
+            return Err("sequence path must not be empty".to_string());
+        }
+        let on_gap = match rule.on_gap.as_deref().unwrap_or("none") {
+            "none" => None,
+            "resubscribe" => Some(Resync::Resubscribe),
+            "reconnect" => Some(Resync::Reconnect),
+            other => return Err(format!("unsupported on_gap: {}", other)),
+        };
+        Ok(Self {
+            path: rule.path.clone(),
+            prev_path: rule.prev_path.clone().filter(|path| !path.trim().is_empty()),
+            key: rule.key.clone().filter(|key| !key.trim().is_empty()),
+            on_gap,
+            resync_message: rule.resync_message.clone().filter(|message| !message.is_empty()),
+            drop_stale: rule.drop_stale,
+            last: HashMap::new(),
+            last_resync: None,
+        })
+    }
+
+    pub fn drop_stale(&self) -> bool {
+        self.drop_stale
+    }
+
+    pub fn resync_message(&self) -> Option<&str> {
+        self.resync_message.as_deref()
+    }
+
+    // 检查一条消息；不是 JSON 或没有序号字段的消息（订阅应答、心跳等）直接跳过
+    pub fn check(&mut self, content: &str) -> Option<Anomaly> {
+        let value: Value = serde_json::from_str(content).ok()?;
+        let sequence = json::lookup(&value, &self.path).and_then(as_sequence)?;
+        let partition_key = self
+            .key
+            .as_deref()
+            .and_then(|path| json::lookup(&value, path))
+            .map(json::value_to_string)
+            .unwrap_or_default();
+        let prev = self.prev_path.as_deref().and_then(|path| json::lookup(&value, path)).and_then(as_sequence);
+
+        // 分区的第一条消息作为起点
+        let Some(&last) = self.last.get(&partition_key) else {
+            self.last.insert(partition_key, sequence);
+            return None;
+        };
+        let anomaly = |kind, expected: i64, actual: i64| Anomaly {
+            partition_key: partition_key.clone(),
+            kind,
+            expected,
+            actual,
+            missed: if kind == "gap" { actual - expected } else { 0 },
+        };
+        let anomaly = match prev {
+            // 带上一序号的流：上一序号等于已记录的序号即为连续
+            Some(prev) if prev == last => None,
+            _ if sequence == last => Some(anomaly("duplicate", last + 1, sequence)),
+            _ if sequence < last => Some(anomaly("out_of_order", last + 1, sequence)),
+            // 与已收到的更新有重叠，没有丢失
+            Some(prev) if prev < last => None,
+            Some(prev) => Some(anomaly("gap", last, prev)),
+            None if sequence == last + 1 => None,
+            None => Some(anomaly("gap", last + 1, sequence)),
+        };
+        // 晚到的消息不改变已记录的序号
+        if !anomaly.as_ref().is_some_and(Anomaly::is_stale) {
+            self.last.insert(partition_key, sequence);
+        }
+        anomaly
+    }
+
+    // 缺口需要重新同步时返回同步方式，并清空已记录的序号（以同步后的第一条消息为新起点）
+    pub fn resync(&mut self, anomaly: &Anomaly) -> Option<Resync> {
+        if anomaly.is_stale() {
+            return None;
+        }
+        let action = self.on_gap?;
+        if self.last_resync.is_some_and(|at| at.elapsed() < RESYNC_COOLDOWN) {
+            return None;
+        }
+        self.last_resync = Some(Instant::now());
+        self.last.clear();
+        Some(action)
+    }
+}
+
+// 记录一次序号异常
+pub async fn record(pool: &SqlitePool, config_id: &str, session_id: &str, anomaly: &Anomaly, action: &str) {
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_sequence_event
+        (id, config_id, session_id, partition_key, kind, expected, actual, missed, action, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(config_id)
+    .bind(session_id)
+    .bind(&anomaly.partition_key)
+    .bind(anomaly.kind)
+    .bind(anomaly.expected)
+    .bind(anomaly.actual)
+    .bind(anomaly.missed)
+    .bind(action)
+    .bind(chrono::Utc::now().timestamp())
+    .execute(pool)
+    .await
+    {
+        tracing::error!("Failed to save sequence event: {}", e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SequenceEventQuery {
+    pub kind: Option<String>,
+    pub partition_key: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// 获取配置的序号异常事件（最新的在前）
+pub async fn list_events(
+    Path(config_id): Path<String>,
+    Query(query): Query<SequenceEventQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<SequenceEvent>>>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    match sqlx::query_as::<_, SequenceEvent>(
+        r#"
+        SELECT * FROM t_websocket_sequence_event
+        WHERE config_id = ? AND (? IS NULL OR kind = ?) AND (? IS NULL OR partition_key = ?)
+        ORDER BY created_at DESC, rowid DESC LIMIT ?
+        "#
+    )
+    .bind(&config_id)
+    .bind(&query.kind)
+    .bind(&query.kind)
+    .bind(&query.partition_key)
+    .bind(&query.partition_key)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(events) => Ok(Json(ApiResponse::ok(events))),
+        Err(e) => {
+            tracing::error!("Failed to fetch sequence events: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
+use futures::stream::{BoxStream, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::app::AppState;
+use crate::models::{ApiResponse, StateChange, StateEntry, StateSettings, WebSocketConfig};
+use crate::utils::json;
+
+// 最新值写入数据库的间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+// 变化广播的缓冲大小
+const CHANGE_CHANNEL_CAPACITY: usize = 4096;
+
+pub type SharedStateStore = Arc<Mutex<StateStore>>;
+
+lazy_static::lazy_static! {
+    // 配置ID -> 已连接配置的状态
+    static ref STORES: RwLock<HashMap<String, SharedStateStore>> = RwLock::new(HashMap::new());
+    static ref CHANGES: broadcast::Sender<StateChange> = broadcast::channel(CHANGE_CHANNEL_CAPACITY).0;
+}
+
+// 比较两个条目的顶层字段，返回新增、删除或值变化的字段
+fn changed_fields(previous: Option<&Value>, current: &Value) -> Vec<String> {
+    let Some(current) = current.as_object() else {
+        return Vec::new();
+    };
+    let previous = match previous {
+        Some(previous) => match previous.as_object() {
+            Some(previous) => previous,
+            None => return Vec::new(),
+        },
+        None => return current.keys().cloned().collect(),
+    };
+    let fields: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| previous.get(*field) != current.get(*field))
+        .cloned()
+        .collect()
+}
+
+struct Slot {
+    entry: StateEntry,
+    value: Value,
+}
+
+// 按配置维护每个键的最新消息，定时写入数据库
+pub struct StateStore {
+    config_id: String,
+    settings: StateSettings,
+    slots: HashMap<String, Slot>,
+    dirty: HashSet<String>,
+    last_flush: Instant,
+}
+
+impl StateStore {
+    // 按配置中的 state_store 创建，未配置时返回 None
+    pub fn from_config(config: &WebSocketConfig) -> Result<Option<Self>, String> {
+        match config.state_store.as_deref() {
+            Some(raw) if !raw.trim().is_empty() => {
+                let settings: StateSettings =
+                    serde_json::from_str(raw).map_err(|e| format!("invalid state_store: {}", e))?;
+                Self::new(&config.id, settings).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn new(config_id: &str, settings: StateSettings) -> Result<Self, String> {
+        if settings.key.trim().is_empty() {
+            return Err("state store needs a key path".to_string());
+        }
+        Ok(Self {
+            config_id: config_id.to_string(),
+            settings,
+            slots: HashMap::new(),
+            dirty: HashSet::new(),
+            last_flush: Instant::now(),
+        })
+    }
+
+    // 载入已保存的最新值
+    async fn load(&mut self, pool: &SqlitePool) {
+        let entries = match sqlx::query_as::<_, StateEntry>("SELECT * FROM t_websocket_state WHERE config_id = ?")
+            .bind(&self.config_id)
+            .fetch_all(pool)
+            .await
+        {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::error!("Failed to fetch websocket state: {}", e);
+                return;
+            }
+        };
+        for entry in entries {
+            let value = serde_json::from_str(&entry.content).unwrap_or(Value::Null);
+            self.slots.insert(entry.key.clone(), Slot { entry, value });
+        }
+    }
+
+    // 从一条消息中取出 (键, 条目)，没有键的条目（订阅应答等）跳过
+    fn items(&self, content: &str) -> Vec<(String, Value)> {
+        let Ok(mut value) = serde_json::from_str::<Value>(content) else {
+            return Vec::new();
+        };
+        let items = match self.settings.items.as_deref() {
+            Some(path) => match json::lookup(&value, path).and_then(Value::as_array) {
+                Some(items) => items.clone(),
+                None => return Vec::new(),
+            },
+            None => vec![value.take()],
+        };
+        items
+            .into_iter()
+            .filter_map(|item| {
+                let key = json::lookup(&item, &self.settings.key).map(json::value_to_string)?;
+                Some((key, item))
+            })
+            .collect()
+    }
+
+    // 更新一个键，内容有变化时返回变化
+    fn apply(&mut self, key: String, value: Value) -> Option<StateChange> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let content = value.to_string();
+        self.dirty.insert(key.clone());
+        match self.slots.get_mut(&key) {
+            Some(slot) => {
+                slot.entry.update_count += 1;
+                slot.entry.updated_at = now;
+                if slot.value == value {
+                    return None;
+                }
+                let change = StateChange {
+                    config_id: self.config_id.clone(),
+                    key,
+                    content: content.clone(),
+                    previous: Some(std::mem::replace(&mut slot.entry.content, content)),
+                    changed_fields: changed_fields(Some(&slot.value), &value),
+                    update_count: slot.entry.update_count,
+                    updated_at: now,
+                };
+                slot.value = value;
+                Some(change)
+            }
+            None => {
+                let change = StateChange {
+                    config_id: self.config_id.clone(),
+                    key: key.clone(),
+                    content: content.clone(),
+                    previous: None,
+                    changed_fields: changed_fields(None, &value),
+                    update_count: 1,
+                    updated_at: now,
+                };
+                let entry = StateEntry {
+                    config_id: self.config_id.clone(),
+                    key: key.clone(),
+                    content,
+                    update_count: 1,
+                    created_at: now,
+                    updated_at: now,
+                };
+                self.slots.insert(key, Slot { entry, value });
+                Some(change)
+            }
+        }
+    }
+
+    // 把有更新的键写入数据库
+    pub async fn flush(&mut self, pool: &SqlitePool) {
+        for key in std::mem::take(&mut self.dirty) {
+            if let Some(slot) = self.slots.get(&key) {
+                save(pool, &slot.entry).await;
+            }
+        }
+        self.last_flush = Instant::now();
+    }
+
+    fn entries(&self) -> Vec<StateEntry> {
+        let mut entries: Vec<StateEntry> = self.slots.values().map(|slot| slot.entry.clone()).collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries
+    }
+}
+
+async fn save(pool: &SqlitePool, entry: &StateEntry) {
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_state (config_id, key, content, update_count, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(config_id, key) DO UPDATE SET
+            content = excluded.content, update_count = excluded.update_count, updated_at = excluded.updated_at
+        "#
+    )
+    .bind(&entry.config_id)
+    .bind(&entry.key)
+    .bind(&entry.content)
+    .bind(entry.update_count)
+    .bind(entry.created_at)
+    .bind(entry.updated_at)
+    .execute(pool)
+    .await
+    {
+        tracing::error!("Failed to save websocket state: {}", e);
+    }
+}
+
+// 连接建立时载入已保存的状态并登记
+pub async fn register(mut store: StateStore, pool: &SqlitePool) -> SharedStateStore {
+    store.load(pool).await;
+    let config_id = store.config_id.clone();
+    let shared = Arc::new(Mutex::new(store));
+    STORES.write().await.insert(config_id, shared.clone());
+    shared
+}
+
+// 处理一条接收消息，只广播内容有变化的键
+pub async fn handle(store: &SharedStateStore, pool: &SqlitePool, content: &str) {
+    let mut store = store.lock().await;
+    for (key, value) in store.items(content) {
+        if let Some(change) = store.apply(key, value) {
+            let _ = CHANGES.send(change);
+        }
+    }
+    if store.last_flush.elapsed() >= FLUSH_INTERVAL {
+        store.flush(pool).await;
+    }
+}
+
+// 连接断开：保存尚未写入的更新
+pub async fn close(pool: &SqlitePool, config_id: &str) {
+    let store = STORES.write().await.remove(config_id);
+    if let Some(store) = store {
+        store.lock().await.flush(pool).await;
+    }
+}
+
+// 查询某个配置的全部最新值（按键排序），已连接时读取内存
+pub async fn list_state(
+    Path(config_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<StateEntry>>>, StatusCode> {
+    let store = STORES.read().await.get(&config_id).cloned();
+    if let Some(store) = store {
+        return Ok(Json(ApiResponse::ok(store.lock().await.entries())));
+    }
+    match sqlx::query_as::<_, StateEntry>("SELECT * FROM t_websocket_state WHERE config_id = ? ORDER BY key")
+        .bind(&config_id)
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(entries) => Ok(Json(ApiResponse::ok(entries))),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket state: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 查询单个键的最新值
+pub async fn get_state(
+    Path((config_id, key)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<StateEntry>>, StatusCode> {
+    let store = STORES.read().await.get(&config_id).cloned();
+    if let Some(store) = store {
+        return match store.lock().await.slots.get(&key) {
+            Some(slot) => Ok(Json(ApiResponse::ok(slot.entry.clone()))),
+            None => Err(StatusCode::NOT_FOUND),
+        };
+    }
+    match sqlx::query_as::<_, StateEntry>("SELECT * FROM t_websocket_state WHERE config_id = ? AND key = ?")
+        .bind(&config_id)
+        .bind(&key)
+        .fetch_one(&state.pool)
+        .await
+    {
+        Ok(entry) => Ok(Json(ApiResponse::ok(entry))),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket state: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StateStreamQuery {
+    pub key: Option<String>,
+}
+
+// 以 SSE 推送内容有变化的键
+pub async fn stream_state(
+    Path(config_id): Path<String>,
+    Query(query): Query<StateStreamQuery>,
+) -> Sse<BoxStream<'static, Result<Event, Infallible>>> {
+    let receiver = CHANGES.subscribe();
+    let stream = futures::stream::unfold(
+        (receiver, config_id, query),
+        |(mut receiver, config_id, query)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(change)
+                        if change.config_id == config_id
+                            && query.key.as_ref().is_none_or(|key| *key == change.key) =>
+                    {
+                        let event = Event::default().event("change").data(serde_json::to_string(&change).unwrap_or_default());
+                        return Some((Ok(event), (receiver, config_id, query)));
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+    Sse::new(stream.boxed()).keep_alive(KeepAlive::default())
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::models::WebSocketConfig;
+use crate::service::protocol::Inbound;
+use crate::utils::json;
+
+// 单帧解压后的最大字节数
+const MAX_DECOMPRESSED: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Codec {
+    #[default]
+    Auto,
+    Gzip,
+    Zlib,
+    Deflate,
+}
+
+// 转换步骤，按配置顺序执行
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Step {
+    // 解压二进制帧（只能出现在管道开头）
+    Decompress {
+        #[serde(default)]
+        codec: Codec,
+    },
+    // 解析 JSON 文本；指定 path 时解析该字段中的 JSON 字符串
+    Parse { path: Option<String> },
+    // 取出路径下的值作为新消息
+    Extract { path: String },
+    // 按 输出字段 -> 源路径 重新组装对象
+    Reshape { fields: BTreeMap<String, String> },
+    // 重命名顶层字段：旧名 -> 新名
+    Rename { fields: BTreeMap<String, String> },
+    // 删除字段（支持路径）
+    Drop { fields: Vec<String> },
+    // 把数组拆成多条消息；指定 path 时拆分该字段
+    Split { path: Option<String> },
+}
+
+// 管道中间结果：尚未解析的文本或 JSON 值
+enum Payload {
+    Text(String),
+    Json(Value),
+}
+
+impl Payload {
+    fn into_json(self) -> Result<Value, String> {
+        match self {
+            Payload::Json(value) => Ok(value),
+            Payload::Text(text) => serde_json::from_str(&text).map_err(|e| format!("invalid JSON: {}", e)),
+        }
+    }
+
+    fn into_content(self) -> String {
+        match self {
+            Payload::Text(text) => text,
+            Payload::Json(value) => json::value_to_string(&value),
+        }
+    }
+}
+
+pub struct Pipeline {
+    codec: Option<Codec>,
+    steps: Vec<Step>,
+}
+
+impl Pipeline {
+    // 按配置中的 transforms 构建管道，未配置时返回 None
+    pub fn from_config(config: &WebSocketConfig) -> Result<Option<Self>, String> {
+        match config.transforms.as_deref() {
+            Some(raw) if !raw.trim().is_empty() => {
+                let steps: Value = serde_json::from_str(raw).map_err(|e| format!("invalid transforms: {}", e))?;
+                Self::new(&steps).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub fn new(steps: &Value) -> Result<Self, String> {
+        let mut steps: Vec<Step> =
+            serde_json::from_value(steps.clone()).map_err(|e| format!("invalid transforms: {}", e))?;
+        let codec = match steps.first() {
+            Some(Step::Decompress { codec }) => Some(*codec),
+            _ => None,
+        };
+        if codec.is_some() {
+            steps.remove(0);
+        }
+        if steps.iter().any(|step| matches!(step, Step::Decompress { .. })) {
+            return Err("decompress must be the first transform".to_string());
+        }
+        Ok(Self { codec, steps })
+    }
+
+    // 是否包含解压步骤（只对原始帧有意义）
+    pub fn decompresses(&self) -> bool {
+        self.codec.is_some()
+    }
+
+    // 解压二进制帧，交给协议适配器前调用
+    pub fn prepare_frame(&self, message: Message) -> Result<Message, String> {
+        match (self.codec, message) {
+            (Some(codec), Message::Binary(bytes)) => decompress(codec, &bytes).map(Message::Text),
+            (_, message) => Ok(message),
+        }
+    }
+
+    // 对解码后的消息执行转换，可能拆分为多条
+    pub fn apply(&self, inbound: &Inbound) -> Result<Vec<Inbound>, String> {
+        let mut payloads = vec![Payload::Text(inbound.content.clone())];
+        for (index, step) in self.steps.iter().enumerate() {
+            let mut next = Vec::with_capacity(payloads.len());
+            for payload in payloads {
+                apply_step(step, payload, &mut next).map_err(|e| format!("transform {}: {}", index + 1, e))?;
+            }
+            payloads = next;
+        }
+        Ok(payloads
+            .into_iter()
+            .map(|payload| Inbound {
+                topic: inbound.topic.clone(),
+                event: inbound.event.clone(),
+                content: payload.into_content(),
+            })
+            .collect())
+    }
+}
+
+fn decompress(codec: Codec, bytes: &[u8]) -> Result<String, String> {
+    let codec = match codec {
+        Codec::Auto if bytes.starts_with(&[0x1f, 0x8b]) => Codec::Gzip,
+        Codec::Auto if bytes.first() == Some(&0x78) => Codec::Zlib,
+        Codec::Auto => Codec::Deflate,
+        codec => codec,
+    };
+    // 限制解压后的大小，防止压缩炸弹耗尽内存
+    let limit = MAX_DECOMPRESSED as u64 + 1;
+    let mut output = Vec::new();
+    let result = match codec {
+        Codec::Gzip => GzDecoder::new(bytes).take(limit).read_to_end(&mut output),
+        Codec::Zlib => ZlibDecoder::new(bytes).take(limit).read_to_end(&mut output),
+        _ => DeflateDecoder::new(bytes).take(limit).read_to_end(&mut output),
+    };
+    result.map_err(|e| format!("decompress failed: {}", e))?;
+    if output.len() > MAX_DECOMPRESSED {
+        return Err(format!("decompressed frame exceeds {} bytes", MAX_DECOMPRESSED));
+    }
+    String::from_utf8(output).map_err(|e| format!("decompress failed: {}", e))
+}
+
+fn lookup_required<'a>(value: &'a Value, path: &str) -> Result<&'a Value, String> {
+    json::lookup(value, path).ok_or_else(|| format!("path {} not found", path))
+}
+
+// 按路径取可修改的字段
+fn lookup_mut<'a>(value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    json::path_keys(path).iter().try_fold(value, |current, key| match current {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(move |i| items.get_mut(i)),
+        _ => current.get_mut(key.as_str()),
+    })
+}
+
+fn apply_step(step: &Step, payload: Payload, out: &mut Vec<Payload>) -> Result<(), String> {
+    match step {
+        Step::Decompress { .. } => out.push(payload),
+        Step::Parse { path: None } => out.push(Payload::Json(payload.into_json()?)),
+        Step::Parse { path: Some(path) } => {
+            let mut value = payload.into_json()?;
+            let field = lookup_mut(&mut value, path).ok_or_else(|| format!("path {} not found", path))?;
+            if let Value::String(text) = field {
+                *field = serde_json::from_str(text).map_err(|e| format!("invalid JSON at {}: {}", path, e))?;
+            }
+            out.push(Payload::Json(value));
+        }
+        Step::Extract { path } => {
+            let value = payload.into_json()?;
+            out.push(Payload::Json(lookup_required(&value, path)?.clone()));
+        }
+        Step::Reshape { fields } => {
+            let value = payload.into_json()?;
+            let object: Map<String, Value> = fields
+                .iter()
+                .map(|(name, path)| (name.clone(), json::lookup(&value, path).cloned().unwrap_or(Value::Null)))
+                .collect();
+            out.push(Payload::Json(Value::Object(object)));
+        }
+        Step::Rename { fields } => {
+            let mut value = payload.into_json()?;
+            if let Value::Object(object) = &mut value {
+                for (from, to) in fields {
+                    if let Some(field) = object.remove(from) {
+                        object.insert(to.clone(), field);
+                    }
+                }
+            }
+            out.push(Payload::Json(value));
+        }
+        Step::Drop { fields } => {
+            let mut value = payload.into_json()?;
+            for path in fields {
+                let mut keys = json::path_keys(path);
+                let Some(last) = keys.pop() else {
+                    continue;
+                };
+                let parent = lookup_mut(&mut value, &keys.join("."));
+                match parent {
+                    Some(Value::Object(object)) => {
+                        object.remove(&last);
+                    }
+                    Some(Value::Array(items)) => {
+                        if let Some(i) = last.parse::<usize>().ok().filter(|i| *i < items.len()) {
+                            items.remove(i);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            out.push(Payload::Json(value));
+        }
+        Step::Split { path } => {
+            let value = payload.into_json()?;
+            let target = match path {
+                Some(path) => lookup_required(&value, path)?,
+                None => &value,
+            };
+            let items = target.as_array().ok_or("split target is not an array")?;
+            out.extend(items.iter().cloned().map(Payload::Json));
+        }
+    }
+    Ok(())
+}
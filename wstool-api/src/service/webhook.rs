@@ -0,0 +1,858 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use tokio::sync::{mpsc, RwLock};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::app::AppState;
+use crate::models::{
+    ApiResponse, NewWebhook, UpdateWebhook, WebSocketMessage, Webhook, WebhookAttempt, WebhookDelivery,
+};
+use crate::utils::json::MessageFilter;
+
+const SIGNATURE_HEADER: &str = "X-Wstool-Signature";
+const DELIVERY_HEADER: &str = "X-Wstool-Delivery";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// 重试间隔：1s 起按 2 倍递增，最长 60s
+const RETRY_BASE: Duration = Duration::from_secs(1);
+const RETRY_MAX: Duration = Duration::from_secs(60);
+const MAX_BATCH_SIZE: i64 = 1000;
+const MAX_ATTEMPTS: i64 = 20;
+
+// 投递目标（创建投递任务时从 webhook 复制）
+#[derive(Clone)]
+struct Target {
+    url: String,
+    secret: Option<String>,
+    max_attempts: i64,
+}
+
+// 运行中的 webhook：过滤条件 + 批处理任务的输入通道
+struct ActiveWebhook {
+    filter: MessageFilter,
+    tx: mpsc::UnboundedSender<WebSocketMessage>,
+}
+
+lazy_static::lazy_static! {
+    // 配置ID -> 启用的 webhook
+    static ref WEBHOOKS: RwLock<HashMap<String, Vec<Arc<ActiveWebhook>>>> = RwLock::new(HashMap::new());
+    static ref CLIENT: reqwest::Client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("failed to build webhook http client");
+}
+
+fn validate(webhook: &Webhook) -> Result<MessageFilter, String> {
+    let url = reqwest::Url::parse(&webhook.url).map_err(|e| format!("invalid url: {}", e))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err("url must be http or https".to_string());
+    }
+    if !(1..=MAX_BATCH_SIZE).contains(&webhook.batch_size) {
+        return Err(format!("batch_size must be between 1 and {}", MAX_BATCH_SIZE));
+    }
+    if webhook.batch_interval_ms < 0 {
+        return Err("batch_interval_ms must not be negative".to_string());
+    }
+    if !(1..=MAX_ATTEMPTS).contains(&webhook.max_attempts) {
+        return Err(format!("max_attempts must be between 1 and {}", MAX_ATTEMPTS));
+    }
+    MessageFilter::parse(webhook.filter.as_deref())
+}
+
+// 从数据库加载启用的 webhook 并启动批处理任务（启动时及 webhook 变更后调用）
+// 旧任务在通道关闭后发送剩余消息再退出
+pub async fn load_webhooks(pool: &SqlitePool) -> anyhow::Result<()> {
+    let webhooks = sqlx::query_as::<_, Webhook>("SELECT * FROM t_websocket_webhook WHERE enabled = TRUE")
+        .fetch_all(pool)
+        .await?;
+
+    let mut by_config: HashMap<String, Vec<Arc<ActiveWebhook>>> = HashMap::new();
+    for webhook in webhooks {
+        let filter = match validate(&webhook) {
+            Ok(filter) => filter,
+            Err(e) => {
+                tracing::warn!("Skipping invalid webhook {}: {}", webhook.id, e);
+                continue;
+            }
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        by_config
+            .entry(webhook.config_id.clone())
+            .or_default()
+            .push(Arc::new(ActiveWebhook { filter, tx }));
+        tokio::spawn(run_batcher(pool.clone(), webhook, rx));
+    }
+    *WEBHOOKS.write().await = by_config;
+    Ok(())
+}
+
+async fn reload(pool: &SqlitePool) {
+    if let Err(e) = load_webhooks(pool).await {
+        tracing::error!("Failed to reload webhooks: {}", e);
+    }
+}
+
+// 把已保存的接收消息交给该配置下匹配的 webhook
+pub async fn dispatch(message: &WebSocketMessage) {
+    if let Some(webhooks) = WEBHOOKS.read().await.get(&message.config_id) {
+        for webhook in webhooks {
+            if webhook.filter.matches(&message.content) {
+                let _ = webhook.tx.send(message.clone());
+            }
+        }
+    }
+}
+
+// 攒够 batch_size 条或最早一条等待超过 batch_interval_ms 后生成一次投递
+async fn run_batcher(pool: SqlitePool, webhook: Webhook, mut rx: mpsc::UnboundedReceiver<WebSocketMessage>) {
+    let target = Target {
+        url: webhook.url.clone(),
+        secret: webhook.secret.clone(),
+        max_attempts: webhook.max_attempts,
+    };
+    let interval = Duration::from_millis(webhook.batch_interval_ms as u64);
+    let mut batch = Vec::new();
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let received = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, rx.recv()).await.ok(),
+            None => Some(rx.recv().await),
+        };
+        let closed = match received {
+            Some(Some(message)) => {
+                if batch.is_empty() {
+                    deadline = Some(Instant::now() + interval);
+                }
+                batch.push(message);
+                if (batch.len() as i64) < webhook.batch_size {
+                    continue;
+                }
+                false
+            }
+            Some(None) => true,
+            None => false,
+        };
+
+        if !batch.is_empty() {
+            deadline = None;
+            let messages = std::mem::take(&mut batch);
+            match create_delivery(&pool, &webhook, messages).await {
+                Ok(delivery) => {
+                    tokio::spawn(deliver(pool.clone(), target.clone(), delivery));
+                }
+                Err(e) => tracing::error!("Failed to create webhook delivery for {}: {}", webhook.id, e),
+            }
+        }
+        if closed {
+            return;
+        }
+    }
+}
+
+async fn create_delivery(
+    pool: &SqlitePool,
+    webhook: &Webhook,
+    messages: Vec<WebSocketMessage>,
+) -> Result<WebhookDelivery, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    let delivery = WebhookDelivery {
+        payload: json!({
+            "webhook_id": webhook.id,
+            "config_id": webhook.config_id,
+            "delivery_id": id,
+            "messages": messages,
+        })
+        .to_string(),
+        id,
+        webhook_id: webhook.id.clone(),
+        message_count: messages.len() as i64,
+        status: "pending".to_string(),
+        attempts: 0,
+        last_status_code: None,
+        last_error: None,
+        created_at: chrono::Utc::now().timestamp(),
+        delivered_at: None,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO t_websocket_webhook_delivery (id, webhook_id, payload, message_count, status, attempts, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&delivery.id)
+    .bind(&delivery.webhook_id)
+    .bind(&delivery.payload)
+    .bind(delivery.message_count)
+    .bind(&delivery.status)
+    .bind(delivery.attempts)
+    .bind(delivery.created_at)
+    .execute(pool)
+    .await?;
+    Ok(delivery)
+}
+
+// 请求体签名：HMAC-SHA256(secret, body) 的十六进制
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// 发送一次请求，返回状态码与错误
+async fn post(target: &Target, delivery: &WebhookDelivery) -> (Option<i64>, Option<String>) {
+    let mut request = CLIENT
+        .post(&target.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(DELIVERY_HEADER, &delivery.id)
+        .body(delivery.payload.clone());
+    if let Some(secret) = &target.secret {
+        request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, &delivery.payload)));
+    }
+    match request.send().await {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i64), None),
+        Ok(response) => (Some(response.status().as_u16() as i64), Some(format!("HTTP {}", response.status()))),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+// 按退避策略投递，直到成功或用完 max_attempts 次尝试；每次尝试都写入日志
+async fn deliver(pool: SqlitePool, target: Target, mut delivery: WebhookDelivery) {
+    let mut backoff = RETRY_BASE;
+    for attempt in 1..=target.max_attempts {
+        let started = Instant::now();
+        let (status_code, error) = post(&target, &delivery).await;
+        let now = chrono::Utc::now().timestamp();
+
+        delivery.attempts += 1;
+        delivery.last_status_code = status_code;
+        delivery.last_error = error.clone();
+        if error.is_none() {
+            delivery.status = "success".to_string();
+            delivery.delivered_at = Some(now);
+        } else if attempt == target.max_attempts {
+            delivery.status = "failed".to_string();
+        }
+
+        let result: Result<(), sqlx::Error> = async {
+            sqlx::query(
+                r#"
+                INSERT INTO t_websocket_webhook_attempt (id, delivery_id, attempt, status_code, error, duration_ms, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&delivery.id)
+            .bind(delivery.attempts)
+            .bind(status_code)
+            .bind(&error)
+            .bind(started.elapsed().as_millis() as i64)
+            .bind(now)
+            .execute(&pool)
+            .await?;
+            sqlx::query(
+                r#"
+                UPDATE t_websocket_webhook_delivery
+                SET status = ?, attempts = ?, last_status_code = ?, last_error = ?, delivered_at = ?
+                WHERE id = ?
+                "#
+            )
+            .bind(&delivery.status)
+            .bind(delivery.attempts)
+            .bind(delivery.last_status_code)
+            .bind(&delivery.last_error)
+            .bind(delivery.delivered_at)
+            .bind(&delivery.id)
+            .execute(&pool)
+            .await?;
+            Ok(())
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to record webhook attempt: {}", e);
+        }
+
+        match error {
+            None => return,
+            Some(error) => {
+                tracing::warn!("Webhook delivery {} attempt {} failed: {}", delivery.id, delivery.attempts, error);
+            }
+        }
+        if attempt < target.max_attempts {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RETRY_MAX);
+        }
+    }
+}
+
+async fn fetch_webhook(pool: &SqlitePool, id: &str) -> Result<Webhook, StatusCode> {
+    match sqlx::query_as::<_, Webhook>("SELECT * FROM t_websocket_webhook WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(webhook) => Ok(webhook),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch webhook: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn check(webhook: &Webhook) -> Result<(), StatusCode> {
+    validate(webhook).map(|_| ()).map_err(|e| {
+        tracing::warn!("Invalid webhook: {}", e);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+// 获取配置下的 webhook 列表
+pub async fn list_webhooks(
+    Path(config_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Webhook>>>, StatusCode> {
+    match sqlx::query_as::<_, Webhook>("SELECT * FROM t_websocket_webhook WHERE config_id = ? ORDER BY created_at DESC")
+        .bind(&config_id)
+        .fetch_all(&state.pool)
+        .await
+    {
+        Ok(webhooks) => Ok(Json(ApiResponse::ok(webhooks))),
+        Err(e) => {
+            tracing::error!("Failed to fetch webhooks: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取单个 webhook
+pub async fn get_webhook(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Webhook>>, StatusCode> {
+    fetch_webhook(&state.pool, &id).await.map(|webhook| Json(ApiResponse::ok(webhook)))
+}
+
+// 为配置创建 webhook
+pub async fn create_webhook(
+    Path(config_id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<NewWebhook>,
+) -> Result<Json<ApiResponse<Webhook>>, StatusCode> {
+    let now = chrono::Utc::now().timestamp();
+    let webhook = Webhook {
+        id: Uuid::new_v4().to_string(),
+        config_id,
+        url: payload.url,
+        secret: payload.secret,
+        filter: payload.filter,
+        batch_size: payload.batch_size.unwrap_or(1),
+        batch_interval_ms: payload.batch_interval_ms.unwrap_or(1000),
+        max_attempts: payload.max_attempts.unwrap_or(5),
+        enabled: payload.enabled.unwrap_or(true),
+        created_at: now,
+        updated_at: now,
+    };
+    check(&webhook)?;
+
+    match sqlx::query(
+        r#"
+        INSERT INTO t_websocket_webhook
+        (id, config_id, url, secret, filter, batch_size, batch_interval_ms, max_attempts, enabled, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&webhook.id)
+    .bind(&webhook.config_id)
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(&webhook.filter)
+    .bind(webhook.batch_size)
+    .bind(webhook.batch_interval_ms)
+    .bind(webhook.max_attempts)
+    .bind(webhook.enabled)
+    .bind(webhook.created_at)
+    .bind(webhook.updated_at)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(webhook)))
+        }
+        // 配置不存在
+        Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to create webhook: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 更新 webhook（未提供的字段保持不变）
+pub async fn update_webhook(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateWebhook>,
+) -> Result<Json<ApiResponse<Webhook>>, StatusCode> {
+    let mut webhook = fetch_webhook(&state.pool, &id).await?;
+    if let Some(url) = payload.url {
+        webhook.url = url;
+    }
+    if payload.secret.is_some() {
+        webhook.secret = payload.secret;
+    }
+    if payload.filter.is_some() {
+        webhook.filter = payload.filter;
+    }
+    if let Some(batch_size) = payload.batch_size {
+        webhook.batch_size = batch_size;
+    }
+    if let Some(batch_interval_ms) = payload.batch_interval_ms {
+        webhook.batch_interval_ms = batch_interval_ms;
+    }
+    if let Some(max_attempts) = payload.max_attempts {
+        webhook.max_attempts = max_attempts;
+    }
+    if let Some(enabled) = payload.enabled {
+        webhook.enabled = enabled;
+    }
+    webhook.updated_at = chrono::Utc::now().timestamp();
+    check(&webhook)?;
+
+    match sqlx::query(
+        r#"
+        UPDATE t_websocket_webhook
+        SET url = ?, secret = ?, filter = ?, batch_size = ?, batch_interval_ms = ?, max_attempts = ?, enabled = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(&webhook.filter)
+    .bind(webhook.batch_size)
+    .bind(webhook.batch_interval_ms)
+    .bind(webhook.max_attempts)
+    .bind(webhook.enabled)
+    .bind(webhook.updated_at)
+    .bind(&webhook.id)
+    .execute(&state.pool)
+    .await
+    {
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(webhook)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to update webhook: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 删除 webhook 及其投递记录
+pub async fn delete_webhook(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    match sqlx::query("DELETE FROM t_websocket_webhook WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => Err(StatusCode::NOT_FOUND),
+        Ok(_) => {
+            reload(&state.pool).await;
+            Ok(Json(ApiResponse::ok(())))
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete webhook: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveryQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// 获取 webhook 的投递记录
+pub async fn list_deliveries(
+    Path(id): Path<String>,
+    Query(query): Query<DeliveryQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<WebhookDelivery>>>, StatusCode> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    match sqlx::query_as::<_, WebhookDelivery>(
+        r#"
+        SELECT * FROM t_websocket_webhook_delivery
+        WHERE webhook_id = ? AND (? IS NULL OR status = ?)
+        ORDER BY created_at DESC, rowid DESC LIMIT ?
+        "#
+    )
+    .bind(&id)
+    .bind(&query.status)
+    .bind(&query.status)
+    .bind(limit)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(deliveries) => Ok(Json(ApiResponse::ok(deliveries))),
+        Err(e) => {
+            tracing::error!("Failed to fetch webhook deliveries: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 获取一次投递的全部尝试
+pub async fn list_attempts(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<WebhookAttempt>>>, StatusCode> {
+    match sqlx::query_as::<_, WebhookAttempt>(
+        "SELECT * FROM t_websocket_webhook_attempt WHERE delivery_id = ? ORDER BY attempt"
+    )
+    .bind(&id)
+    .fetch_all(&state.pool)
+    .await
+    {
+        Ok(attempts) => Ok(Json(ApiResponse::ok(attempts))),
+        Err(e) => {
+            tracing::error!("Failed to fetch webhook attempts: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 手动重新投递（使用 webhook 当前的地址与密钥，重新计算重试次数）
+pub async fn redeliver(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebhookDelivery>>, StatusCode> {
+    let mut delivery = match sqlx::query_as::<_, WebhookDelivery>("SELECT * FROM t_websocket_webhook_delivery WHERE id = ?")
+        .bind(&id)
+        .fetch_one(&state.pool)
+        .await
+    {
+        Ok(delivery) => delivery,
+        Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch webhook delivery: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    if delivery.status == "pending" {
+        return Err(StatusCode::CONFLICT);
+    }
+    let webhook = fetch_webhook(&state.pool, &delivery.webhook_id).await?;
+
+    if let Err(e) = sqlx::query("UPDATE t_websocket_webhook_delivery SET status = 'pending' WHERE id = ?")
+        .bind(&delivery.id)
+        .execute(&state.pool)
+        .await
+    {
+        tracing::error!("Failed to update webhook delivery: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    delivery.status = "pending".to_string();
+
+    let target = Target {
+        url: webhook.url,
+        secret: webhook.secret,
+        max_attempts: webhook.max_attempts,
+    };
+    tokio::spawn(deliver(state.pool.clone(), target, delivery.clone()));
+    Ok(Json(ApiResponse::ok(delivery)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use axum::{extract::State as AxumState, http::HeaderMap, routing::post as post_route, Router};
+    use serde_json::Value;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use tokio::sync::Mutex;
+
+    use super::*;
+
+    // 本地 HTTP 接收端收到的一次请求
+    struct Received {
+        headers: HeaderMap,
+        body: String,
+        at: Instant,
+    }
+
+    struct Listener {
+        requests: mpsc::UnboundedSender<Received>,
+        // 依次返回的状态码，用完后返回 200
+        statuses: Mutex<VecDeque<u16>>,
+    }
+
+    async fn receive(AxumState(listener): AxumState<Arc<Listener>>, headers: HeaderMap, body: String) -> StatusCode {
+        let _ = listener.requests.send(Received { headers, body, at: Instant::now() });
+        let status = listener.statuses.lock().await.pop_front().unwrap_or(200);
+        StatusCode::from_u16(status).unwrap()
+    }
+
+    async fn spawn_listener(statuses: &[u16]) -> (String, mpsc::UnboundedReceiver<Received>) {
+        let (requests, received) = mpsc::unbounded_channel();
+        let listener = Arc::new(Listener { requests, statuses: Mutex::new(statuses.iter().copied().collect()) });
+        let router = Router::new().route("/hook", post_route(receive)).with_state(listener);
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", tcp.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(tcp, router).await.unwrap() });
+        (url, received)
+    }
+
+    async fn next_request(received: &mut mpsc::UnboundedReceiver<Received>) -> Received {
+        tokio::time::timeout(Duration::from_secs(10), received.recv())
+            .await
+            .expect("no webhook request")
+            .unwrap()
+    }
+
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        crate::db::migrate(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO t_websocket_config (id, name, ws_url, config_type, created_at, updated_at) VALUES ('config-1', 'test', 'ws://127.0.0.1', 'subscriber', 0, 0)"
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool
+    }
+
+    async fn insert_webhook(pool: &SqlitePool, url: &str, secret: Option<&str>, batch_size: i64, batch_interval_ms: i64, max_attempts: i64) -> Webhook {
+        let webhook = Webhook {
+            id: Uuid::new_v4().to_string(),
+            config_id: "config-1".to_string(),
+            url: url.to_string(),
+            secret: secret.map(str::to_string),
+            filter: None,
+            batch_size,
+            batch_interval_ms,
+            max_attempts,
+            enabled: true,
+            created_at: 0,
+            updated_at: 0,
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO t_websocket_webhook (id, config_id, url, secret, batch_size, batch_interval_ms, max_attempts, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, 0, 0)
+            "#
+        )
+        .bind(&webhook.id)
+        .bind(&webhook.config_id)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(webhook.batch_size)
+        .bind(webhook.batch_interval_ms)
+        .bind(webhook.max_attempts)
+        .execute(pool)
+        .await
+        .unwrap();
+        webhook
+    }
+
+    fn message(content: &str) -> WebSocketMessage {
+        WebSocketMessage {
+            id: Uuid::new_v4().to_string(),
+            config_id: "config-1".to_string(),
+            message_type: "received".to_string(),
+            content: content.to_string(),
+            timestamp: 0,
+            status: "success".to_string(),
+            error_message: None,
+            topic: None,
+            event: None,
+            session_id: None,
+            timestamp_us: None,
+            valid: None,
+            validation_error: None,
+            raw_content: None,
+            qos: None,
+            retain: None,
+        }
+    }
+
+    fn target(webhook: &Webhook) -> Target {
+        Target { url: webhook.url.clone(), secret: webhook.secret.clone(), max_attempts: webhook.max_attempts }
+    }
+
+    fn contents(body: &str) -> Vec<String> {
+        let payload: Value = serde_json::from_str(body).unwrap();
+        payload["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["content"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn fetch_delivery(pool: &SqlitePool, id: &str) -> WebhookDelivery {
+        sqlx::query_as("SELECT * FROM t_websocket_webhook_delivery WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn fetch_attempts(pool: &SqlitePool, id: &str) -> Vec<WebhookAttempt> {
+        sqlx::query_as("SELECT * FROM t_websocket_webhook_attempt WHERE delivery_id = ? ORDER BY attempt")
+            .bind(id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn batches_by_size() {
+        let pool = test_pool().await;
+        let (url, mut received) = spawn_listener(&[]).await;
+        let webhook = insert_webhook(&pool, &url, None, 2, 60_000, 1).await;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let batcher = tokio::spawn(run_batcher(pool.clone(), webhook, rx));
+
+        for content in ["m1", "m2", "m3"] {
+            tx.send(message(content)).unwrap();
+        }
+        assert_eq!(contents(&next_request(&mut received).await.body), ["m1", "m2"]);
+
+        // 通道关闭时发送剩余的消息
+        drop(tx);
+        assert_eq!(contents(&next_request(&mut received).await.body), ["m3"]);
+        batcher.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn batches_by_interval() {
+        let pool = test_pool().await;
+        let (url, mut received) = spawn_listener(&[]).await;
+        let webhook = insert_webhook(&pool, &url, None, 100, 200, 1).await;
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_batcher(pool.clone(), webhook, rx));
+
+        let started = Instant::now();
+        tx.send(message("m1")).unwrap();
+        tx.send(message("m2")).unwrap();
+        let request = next_request(&mut received).await;
+        assert!(request.at - started >= Duration::from_millis(200));
+        assert_eq!(contents(&request.body), ["m1", "m2"]);
+    }
+
+    #[tokio::test]
+    async fn signs_the_request_body() {
+        // 常用的 HMAC-SHA256 测试向量
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+
+        let pool = test_pool().await;
+        let (url, mut received) = spawn_listener(&[]).await;
+        let webhook = insert_webhook(&pool, &url, Some("s3cret"), 1, 0, 1).await;
+        let delivery = create_delivery(&pool, &webhook, vec![message(r#"{"price":1}"#)]).await.unwrap();
+        deliver(pool.clone(), target(&webhook), delivery.clone()).await;
+
+        let request = next_request(&mut received).await;
+        assert_eq!(request.body, delivery.payload);
+        assert_eq!(request.headers[DELIVERY_HEADER], delivery.id.as_str());
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(request.body.as_bytes());
+        let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+        assert_eq!(request.headers[SIGNATURE_HEADER], expected.as_str());
+    }
+
+    #[tokio::test]
+    async fn retries_with_backoff_then_fails() {
+        let pool = test_pool().await;
+        let (url, mut received) = spawn_listener(&[500, 500, 500]).await;
+        let webhook = insert_webhook(&pool, &url, None, 1, 0, 3).await;
+        let delivery = create_delivery(&pool, &webhook, vec![message("m1")]).await.unwrap();
+        deliver(pool.clone(), target(&webhook), delivery.clone()).await;
+
+        let first = next_request(&mut received).await;
+        let second = next_request(&mut received).await;
+        let third = next_request(&mut received).await;
+        assert!(second.at - first.at >= RETRY_BASE);
+        assert!(third.at - second.at >= RETRY_BASE * 2);
+        assert!(received.try_recv().is_err());
+
+        let delivery = fetch_delivery(&pool, &delivery.id).await;
+        assert_eq!(delivery.status, "failed");
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(delivery.last_status_code, Some(500));
+        assert!(delivery.delivered_at.is_none());
+
+        let attempts = fetch_attempts(&pool, &delivery.id).await;
+        assert_eq!(attempts.iter().map(|a| a.attempt).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(attempts.iter().all(|a| a.status_code == Some(500) && a.error.is_some()));
+    }
+
+    #[tokio::test]
+    async fn logs_each_attempt_until_success() {
+        let pool = test_pool().await;
+        let (url, mut received) = spawn_listener(&[503]).await;
+        let webhook = insert_webhook(&pool, &url, None, 1, 0, 5).await;
+        let delivery = create_delivery(&pool, &webhook, vec![message("m1")]).await.unwrap();
+        deliver(pool.clone(), target(&webhook), delivery.clone()).await;
+
+        next_request(&mut received).await;
+        next_request(&mut received).await;
+        let delivery = fetch_delivery(&pool, &delivery.id).await;
+        assert_eq!(delivery.status, "success");
+        assert_eq!(delivery.attempts, 2);
+        assert_eq!(delivery.last_status_code, Some(200));
+        assert!(delivery.last_error.is_none());
+        assert!(delivery.delivered_at.is_some());
+
+        let attempts = fetch_attempts(&pool, &delivery.id).await;
+        assert_eq!(attempts.len(), 2);
+        assert_eq!((attempts[0].status_code, attempts[0].error.as_deref()), (Some(503), Some("HTTP 503 Service Unavailable")));
+        assert_eq!((attempts[1].status_code, attempts[1].error.as_deref()), (Some(200), None));
+    }
+
+    #[tokio::test]
+    async fn redeliver_conflicts_while_pending() {
+        let pool = test_pool().await;
+        let (url, mut received) = spawn_listener(&[]).await;
+        let webhook = insert_webhook(&pool, &url, None, 1, 0, 1).await;
+        let delivery = create_delivery(&pool, &webhook, vec![message("m1")]).await.unwrap();
+        let state = || State(AppState { pool: pool.clone() });
+
+        let result = redeliver(Path(delivery.id.clone()), state()).await;
+        assert_eq!(result.err(), Some(StatusCode::CONFLICT));
+
+        sqlx::query("UPDATE t_websocket_webhook_delivery SET status = 'failed' WHERE id = ?")
+            .bind(&delivery.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let Json(response) = redeliver(Path(delivery.id.clone()), state()).await.unwrap();
+        assert_eq!(response.data.unwrap().status, "pending");
+        assert_eq!(next_request(&mut received).await.body, delivery.payload);
+
+
+        let result = redeliver(Path("missing".to_string()), state()).await;
+        assert_eq!(result.err(), Some(StatusCode::NOT_FOUND));
+    }
+}
@@ -3,17 +3,30 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
+use base64::Engine;
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
-use std::collections::HashMap;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use tokio_tungstenite::tungstenite::protocol::Message;
 use uuid::Uuid;
 
 use crate::app::AppState;
 use crate::models::{
     ApiResponse, WebSocketConfig, NewWebSocketConfig, UpdateWebSocketConfig,
     WebSocketMessage, SendMessageRequest, SubscribeRequest, WebSocketStatus,
-    TestConnectionRequest, TestConnectionResponse
+    TestConnectionRequest, TestConnectionResponse, PresetConfigRequest, PresetInfo, MessageSearchHit, MessagePage,
+    MessageSchema, TransformPreviewRequest, TransformPreview, OutboxSettings, SequenceRule, OrderBookSettings,
+    CandleSettings, StateSettings
 };
+use crate::service::candle::Aggregator;
+use crate::service::orderbook::OrderBookFeed;
+use crate::service::outbox;
+use crate::service::protocol::{preset, Inbound};
+use crate::service::schema_validation::SchemaValidator;
+use crate::service::sequence::SequenceTracker;
+use crate::service::state_store::StateStore;
+use crate::service::transform::Pipeline;
+use crate::service::websocket_manager::WEBSOCKET_MANAGER;
+use crate::utils::json;
 
 #[derive(Deserialize)]
 pub struct ListQuery {
@@ -75,6 +88,7 @@ pub async fn create_config(
     let id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp();
     let auto_reconnect = payload.auto_reconnect.unwrap_or(true);
+    let protocol = payload.protocol.unwrap_or_else(|| "raw".to_string());
 
     let config = WebSocketConfig {
         id: id.clone(),
@@ -87,15 +101,70 @@ pub async fn create_config(
         message_template: payload.message_template,
         auto_reconnect,
         status: "inactive".to_string(),
+        protocol,
+        protocol_options: payload.protocol_options,
+        filters: payload.filters,
+        message_schema: payload.message_schema,
+        transforms: payload.transforms,
+        outbox: payload.outbox,
+        sequence: payload.sequence,
+        orderbook: payload.orderbook,
+        candles: payload.candles,
+        state_store: payload.state_store,
         created_at: now,
         updated_at: now,
     };
 
-    match sqlx::query(
+    // 校验协议及协议参数
+    if let Err(e) = crate::service::protocol::build_adapter(&config) {
+        tracing::warn!("Invalid websocket protocol config: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = SchemaValidator::from_config(&config) {
+        tracing::warn!("Invalid websocket message schema: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = Pipeline::from_config(&config) {
+        tracing::warn!("Invalid websocket transforms: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = outbox::settings(&config) {
+        tracing::warn!("Invalid websocket outbox: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = SequenceTracker::from_config(&config) {
+        tracing::warn!("Invalid websocket sequence rule: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = OrderBookFeed::from_config(&config) {
+        tracing::warn!("Invalid websocket order book settings: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = Aggregator::from_config(&config) {
+        tracing::warn!("Invalid websocket candle settings: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = StateStore::from_config(&config) {
+        tracing::warn!("Invalid websocket state store settings: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match insert_config(&state.pool, &config).await {
+        Ok(_) => Ok(Json(ApiResponse::success(config))),
+        Err(e) => {
+            tracing::error!("Failed to create websocket config: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 保存WebSocket配置
+async fn insert_config(pool: &SqlitePool, config: &WebSocketConfig) -> Result<(), sqlx::Error> {
+    sqlx::query(
         r#"
         INSERT INTO t_websocket_config 
-        (id, name, description, ws_url, config_type, headers, auth_token, message_template, auto_reconnect, status, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        (id, name, description, ws_url, config_type, headers, auth_token, message_template, auto_reconnect, status, protocol, protocol_options, filters, message_schema, transforms, outbox, sequence, orderbook, candles, state_store, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&config.id)
@@ -108,14 +177,97 @@ pub async fn create_config(
     .bind(&config.message_template)
     .bind(config.auto_reconnect)
     .bind(&config.status)
+    .bind(&config.protocol)
+    .bind(&config.protocol_options)
+    .bind(&config.filters)
+    .bind(&config.message_schema)
+    .bind(&config.transforms)
+    .bind(&config.outbox)
+    .bind(&config.sequence)
+    .bind(&config.orderbook)
+    .bind(&config.candles)
+    .bind(&config.state_store)
     .bind(config.created_at)
     .bind(config.updated_at)
-    .execute(&state.pool)
-    .await
-    {
-        Ok(_) => Ok(Json(ApiResponse::success(config))),
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// 列出交易所行情流预设
+pub async fn list_presets() -> Json<ApiResponse<Vec<PresetInfo>>> {
+    let sample_symbols = vec!["BTCUSDT".to_string()];
+    let sample_channels = vec!["trade".to_string()];
+    let presets = preset::presets()
+        .into_iter()
+        .map(|p| {
+            let ping = p.ping();
+            PresetInfo {
+                name: p.name().to_string(),
+                ws_url: p.ws_url().to_string(),
+                max_streams: p.max_streams(),
+                ping_interval_secs: ping.as_ref().map(|ping| ping.interval.as_secs()),
+                ping_message: ping.map(|ping| ping.message.to_string()),
+                subscribe_example: p.subscribe_message(&sample_symbols, &sample_channels, 1),
+                unsubscribe_example: p.unsubscribe_message(&sample_symbols, &sample_channels, 2),
+            }
+        })
+        .collect();
+    Json(ApiResponse::ok(presets))
+}
+
+// 根据预设创建订阅配置
+pub async fn create_config_from_preset(
+    State(state): State<AppState>,
+    Json(payload): Json<PresetConfigRequest>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    let Some(p) = preset::find_preset(&payload.preset) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    if payload.symbols.is_empty() || payload.channels.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if p.stream_count(&payload.symbols, &payload.channels) > p.max_streams() {
+        tracing::warn!("Preset {} allows at most {} streams per connection", p.name(), p.max_streams());
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let filters = serde_json::json!({
+        "symbols": payload.symbols,
+        "channels": payload.channels,
+    });
+    let config = WebSocketConfig {
+        id: Uuid::new_v4().to_string(),
+        name: payload.name.unwrap_or_else(|| {
+            format!("{} {}", p.name(), payload.channels.join(","))
+        }),
+        description: payload.description,
+        ws_url: payload.ws_url.unwrap_or_else(|| p.ws_url().to_string()),
+        config_type: "subscriber".to_string(),
+        headers: None,
+        auth_token: None,
+        message_template: None,
+        auto_reconnect: payload.auto_reconnect.unwrap_or(true),
+        status: "inactive".to_string(),
+        protocol: p.name().to_string(),
+        protocol_options: None,
+        filters: Some(filters.to_string()),
+        message_schema: None,
+        transforms: None,
+        outbox: None,
+        sequence: None,
+        orderbook: None,
+        candles: None,
+        state_store: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    match insert_config(&state.pool, &config).await {
+        Ok(_) => Ok(Json(ApiResponse::ok(config))),
         Err(e) => {
-            tracing::error!("Failed to create websocket config: {}", e);
+            tracing::error!("Failed to create websocket config from preset: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
@@ -125,39 +277,53 @@ pub async fn create_config(
 pub async fn update_config(
     Path(id): Path<String>,
     State(state): State<AppState>,
-    Json(_payload): Json<UpdateWebSocketConfig>,
+    Json(payload): Json<UpdateWebSocketConfig>,
 ) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
-    let now = chrono::Utc::now().timestamp();
+    let mut config = match sqlx::query_as::<_, WebSocketConfig>("SELECT * FROM t_websocket_config WHERE id = ?")
+        .bind(&id)
+        .fetch_one(&state.pool)
+        .await
+    {
+        Ok(config) => config,
+        Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket config: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // 更新协议、协议参数和订阅过滤器（空字符串表示清除），重新连接后生效
+    if let Some(protocol) = payload.protocol {
+        config.protocol = protocol;
+    }
+    if let Some(options) = payload.protocol_options {
+        config.protocol_options = Some(options).filter(|options| !options.trim().is_empty());
+    }
+    if let Some(filters) = payload.filters {
+        config.filters = Some(filters).filter(|filters| !filters.trim().is_empty());
+    }
+    if let Err(e) = crate::service::protocol::build_adapter(&config) {
+        tracing::warn!("Invalid websocket protocol config: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Some(Err(e)) = config.filters.as_deref().map(serde_json::from_str::<serde_json::Value>) {
+        tracing::warn!("Invalid websocket filters: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    config.updated_at = chrono::Utc::now().timestamp();
 
-    // 简化实现，只更新时间戳
     match sqlx::query(
-        "UPDATE t_websocket_config SET updated_at = ? WHERE id = ?"
+        "UPDATE t_websocket_config SET protocol = ?, protocol_options = ?, filters = ?, updated_at = ? WHERE id = ?"
     )
-    .bind(now)
+    .bind(&config.protocol)
+    .bind(&config.protocol_options)
+    .bind(&config.filters)
+    .bind(config.updated_at)
     .bind(&id)
     .execute(&state.pool)
     .await
     {
-        Ok(result) => {
-            if result.rows_affected() == 0 {
-                return Err(StatusCode::NOT_FOUND);
-            }
-            
-            // Fetch updated config
-            match sqlx::query_as::<_, WebSocketConfig>(
-                "SELECT * FROM t_websocket_config WHERE id = ?"
-            )
-            .bind(&id)
-            .fetch_one(&state.pool)
-            .await
-            {
-                Ok(config) => Ok(Json(ApiResponse::success(config))),
-                Err(e) => {
-                    tracing::error!("Failed to fetch updated config: {}", e);
-                    Err(StatusCode::INTERNAL_SERVER_ERROR)
-                }
-            }
-        }
+        Ok(_) => Ok(Json(ApiResponse::ok(config))),
         Err(e) => {
             tracing::error!("Failed to update websocket config: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -165,6 +331,235 @@ pub async fn update_config(
     }
 }
 
+// 设置接收消息的校验 schema（重新连接后生效）
+pub async fn set_message_schema(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<MessageSchema>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    if let Err(e) = SchemaValidator::new(&payload) {
+        tracing::warn!("Invalid websocket message schema: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let raw = serde_json::to_string(&payload).map_err(|_| StatusCode::BAD_REQUEST)?;
+    update_config_column(&state.pool, &id, "message_schema", Some(raw)).await
+}
+
+// 移除接收消息的校验 schema
+pub async fn delete_message_schema(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    update_config_column(&state.pool, &id, "message_schema", None).await
+}
+
+// 更新配置中的单个 JSON 列并返回更新后的配置
+async fn update_config_column(
+    pool: &SqlitePool,
+    id: &str,
+    column: &'static str,
+    value: Option<String>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    match sqlx::query_as::<_, WebSocketConfig>(&format!(
+        "UPDATE t_websocket_config SET {} = ?, updated_at = ? WHERE id = ? RETURNING *",
+        column
+    ))
+    .bind(value)
+    .bind(chrono::Utc::now().timestamp())
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(config) => Ok(Json(ApiResponse::ok(config))),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to update websocket config {}: {}", column, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 设置接收消息的转换管道（重新连接后生效）
+pub async fn set_transforms(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    if let Err(e) = Pipeline::new(&payload) {
+        tracing::warn!("Invalid websocket transforms: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    update_config_column(&state.pool, &id, "transforms", Some(payload.to_string())).await
+}
+
+// 移除接收消息的转换管道
+pub async fn delete_transforms(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    update_config_column(&state.pool, &id, "transforms", None).await
+}
+
+// 启用发件箱：未连接时发送的消息先排队，连接建立后按顺序补发
+pub async fn set_outbox(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<OutboxSettings>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    if let Err(e) = outbox::check(&payload) {
+        tracing::warn!("Invalid websocket outbox: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let raw = serde_json::to_string(&payload).map_err(|_| StatusCode::BAD_REQUEST)?;
+    update_config_column(&state.pool, &id, "outbox", Some(raw)).await
+}
+
+// 停用发件箱，尚未发送的消息会被取消
+pub async fn delete_outbox(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    let config = update_config_column(&state.pool, &id, "outbox", None).await?;
+    if let Err(e) = outbox::cancel(&state.pool, &id, None, "outbox disabled").await {
+        tracing::error!("Failed to cancel pending messages: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Ok(config)
+}
+
+// 设置接收消息的序号检查规则（重新连接后生效）
+pub async fn set_sequence(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<SequenceRule>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    if let Err(e) = SequenceTracker::new(&payload) {
+        tracing::warn!("Invalid websocket sequence rule: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let raw = serde_json::to_string(&payload).map_err(|_| StatusCode::BAD_REQUEST)?;
+    update_config_column(&state.pool, &id, "sequence", Some(raw)).await
+}
+
+// 移除序号检查规则
+pub async fn delete_sequence(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    update_config_column(&state.pool, &id, "sequence", None).await
+}
+
+// 启用订单簿：按深度推送维护 L2 订单簿（重新连接后生效）
+pub async fn set_orderbook(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<OrderBookSettings>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    let config = match sqlx::query_as::<_, WebSocketConfig>("SELECT * FROM t_websocket_config WHERE id = ?")
+        .bind(&id)
+        .fetch_one(&state.pool)
+        .await
+    {
+        Ok(config) => config,
+        Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket config: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    // 未指定 format 时按配置的协议确定
+    if let Err(e) = OrderBookFeed::new(&config, &payload) {
+        tracing::warn!("Invalid websocket order book settings: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let raw = serde_json::to_string(&payload).map_err(|_| StatusCode::BAD_REQUEST)?;
+    update_config_column(&state.pool, &id, "orderbook", Some(raw)).await
+}
+
+// 停用订单簿
+pub async fn delete_orderbook(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    update_config_column(&state.pool, &id, "orderbook", None).await
+}
+
+// 启用 K 线聚合：把成交消息聚合为 OHLCV K 线（重新连接后生效）
+pub async fn set_candles(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<CandleSettings>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    if let Err(e) = Aggregator::new(&id, payload.clone()) {
+        tracing::warn!("Invalid websocket candle settings: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let raw = serde_json::to_string(&payload).map_err(|_| StatusCode::BAD_REQUEST)?;
+    update_config_column(&state.pool, &id, "candles", Some(raw)).await
+}
+
+// 停用 K 线聚合
+pub async fn delete_candles(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    update_config_column(&state.pool, &id, "candles", None).await
+}
+
+// 启用最新值状态：按键保存每个键的最新消息（重新连接后生效）
+pub async fn set_state_store(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    Json(payload): Json<StateSettings>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    if let Err(e) = StateStore::new(&id, payload.clone()) {
+        tracing::warn!("Invalid websocket state store settings: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let raw = serde_json::to_string(&payload).map_err(|_| StatusCode::BAD_REQUEST)?;
+    update_config_column(&state.pool, &id, "state_store", Some(raw)).await
+}
+
+// 停用最新值状态（已保存的最新值保留）
+pub async fn delete_state_store(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<WebSocketConfig>>, StatusCode> {
+    update_config_column(&state.pool, &id, "state_store", None).await
+}
+
+// 用示例消息试运行转换管道
+pub async fn preview_transforms(
+    Json(payload): Json<TransformPreviewRequest>,
+) -> Result<Json<ApiResponse<TransformPreview>>, StatusCode> {
+    let pipeline = Pipeline::new(&payload.transforms).map_err(|e| {
+        tracing::warn!("Invalid websocket transforms: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let frame = if payload.base64.unwrap_or(false) {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(payload.content.trim())
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        Message::Binary(bytes)
+    } else {
+        Message::Text(payload.content)
+    };
+
+    let result = match pipeline.prepare_frame(frame) {
+        Ok(Message::Text(content)) => {
+            pipeline.apply(&Inbound { topic: None, event: None, content })
+        }
+        Ok(_) => Err("binary frames need a decompress transform".to_string()),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(messages) => Ok(Json(ApiResponse::ok(TransformPreview {
+            messages: messages.into_iter().map(|message| message.content).collect(),
+        }))),
+        Err(e) => Ok(Json(ApiResponse::err(e))),
+    }
+}
+
 // 删除WebSocket配置
 pub async fn delete_config(
     Path(id): Path<String>,
@@ -219,18 +614,32 @@ pub async fn get_config_status(
         .await
     {
         Ok(_) => {
-            // 这里应该从WebSocket连接管理器获取实际状态
-            // 现在返回模拟状态
-            let status = WebSocketStatus {
-                config_id: id,
-                is_connected: false,
-                connection_time: None,
-                last_message_time: None,
-                message_count: 0,
-                error_count: 0,
-                last_error: None,
+            // 从连接管理器获取实际状态，未连接时返回空统计
+            let status = match WEBSOCKET_MANAGER.get_connection_status(&id).await {
+                Some(info) => WebSocketStatus {
+                    config_id: id,
+                    is_connected: info.is_connected,
+                    connection_time: info.connection_time,
+                    last_message_time: info.last_message_time,
+                    message_count: info.message_count,
+                    error_count: info.error_count,
+                    last_error: info.last_error,
+                    schema_violations: info.schema_violations,
+                    sequence_gaps: info.sequence_gaps,
+                },
+                None => WebSocketStatus {
+                    config_id: id,
+                    is_connected: false,
+                    connection_time: None,
+                    last_message_time: None,
+                    message_count: 0,
+                    error_count: 0,
+                    last_error: None,
+                    schema_violations: 0,
+                    sequence_gaps: 0,
+                },
             };
-            Ok(Json(ApiResponse::success(status)))
+            Ok(Json(ApiResponse::ok(status)))
         }
         Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
@@ -240,28 +649,253 @@ pub async fn get_config_status(
     }
 }
 
-// 获取WebSocket消息历史
+#[derive(Debug, Deserialize)]
+pub struct MessageQuery {
+    pub page: Option<i64>, // Offset paging, prefer before/after for deep pages
+    pub limit: Option<i64>,
+    pub topic: Option<String>, // MQTT topic, GraphQL operation id, ...
+    pub before: Option<String>, // Cursor "<timestamp>:<id>", messages strictly before it
+    pub after: Option<String>, // Cursor "<timestamp>:<id>", messages strictly after it
+    pub since: Option<i64>, // timestamp >= since
+    pub until: Option<i64>, // timestamp <= until
+    pub message_type: Option<String>, // "sent" or "received"
+    pub status: Option<String>, // "success", "failed", "pending"
+    pub contains: Option<String>, // Case-sensitive substring of content
+    pub path: Option<String>, // JSON field compared with value via json_extract, e.g. "data.s"
+    pub value: Option<String>, // Parsed as JSON (true, 1.5, "1", null), plain text otherwise
+    pub order: Option<String>, // "desc" (default) or "asc"
+    #[serde(default)]
+    pub total: bool, // Also count all matching messages
+}
+
+fn message_cursor(message: &WebSocketMessage) -> String {
+    format!("{}:{}", message.timestamp, message.id)
+}
+
+fn parse_cursor(cursor: &str) -> Result<(i64, String), StatusCode> {
+    let parsed = cursor
+        .split_once(':')
+        .filter(|(_, id)| !id.is_empty())
+        .and_then(|(timestamp, id)| Some((timestamp.parse().ok()?, id.to_string())));
+    parsed.ok_or_else(|| {
+        tracing::warn!("Invalid message cursor: {}", cursor);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+// 追加过滤条件（不含游标），查询和计数共用
+fn push_message_filters(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    config_id: &str,
+    params: &MessageQuery,
+) {
+    builder.push(" WHERE config_id = ").push_bind(config_id.to_string());
+    if let Some(topic) = &params.topic {
+        builder.push(" AND topic = ").push_bind(topic.clone());
+    }
+    if let Some(since) = params.since {
+        builder.push(" AND timestamp >= ").push_bind(since);
+    }
+    if let Some(until) = params.until {
+        builder.push(" AND timestamp <= ").push_bind(until);
+    }
+    if let Some(message_type) = &params.message_type {
+        builder.push(" AND message_type = ").push_bind(message_type.clone());
+    }
+    if let Some(status) = &params.status {
+        builder.push(" AND status = ").push_bind(status.clone());
+    }
+    if let Some(contains) = &params.contains {
+        builder.push(" AND instr(content, ").push_bind(contains.clone()).push(") > 0");
+    }
+    if let (Some(path), Some(value)) = (&params.path, &params.value) {
+        // 非 JSON 内容直接跳过，避免 json_extract 报错
+        // 按 JSON 解析 value 并按类型比较：true/false 对应 json_extract 的 1/0，1 与 1.0 相等；不是 JSON 时按文本比较
+        let value = serde_json::from_str::<serde_json::Value>(value).map_err(|_| value.clone());
+        // null 只匹配字段值为 JSON null 的消息（不含缺少该字段的消息）
+        let function = if matches!(value, Ok(serde_json::Value::Null)) { "json_type" } else { "json_extract" };
+        builder
+            .push(format!(" AND (CASE WHEN json_valid(content) THEN {}(content, ", function))
+            .push_bind(json::sqlite_path(path))
+            .push(") END)");
+        match value {
+            Ok(serde_json::Value::Null) => {
+                builder.push(" = 'null'");
+            }
+            Ok(serde_json::Value::Bool(flag)) => {
+                builder.push(" = ").push_bind(flag as i64);
+            }
+            Ok(serde_json::Value::Number(number)) => match number.as_i64() {
+                Some(integer) => {
+                    builder.push(" = ").push_bind(integer);
+                }
+                None => {
+                    builder.push(" = ").push_bind(number.as_f64().unwrap_or(f64::NAN));
+                }
+            },
+            Ok(serde_json::Value::String(text)) => {
+                builder.push(" = ").push_bind(text);
+            }
+            Ok(other) => {
+                builder.push(" = ").push_bind(other.to_string());
+            }
+            Err(text) => {
+                builder.push(" = ").push_bind(text);
+            }
+        }
+    }
+}
+
+// 获取WebSocket消息历史：支持 (timestamp, id) 游标、时间范围、方向、状态、内容和 JSON 字段过滤
 pub async fn get_messages(
     Path(config_id): Path<String>,
-    Query(params): Query<HashMap<String, String>>,
+    Query(params): Query<MessageQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<MessagePage>>, StatusCode> {
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+    let ascending = match params.order.as_deref() {
+        None | Some("desc") => false,
+        Some("asc") => true,
+        Some(order) => {
+            tracing::warn!("Invalid message order: {}", order);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+    if params.path.is_some() != params.value.is_some() {
+        tracing::warn!("Message path and value must be given together");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let before = params.before.as_deref().map(parse_cursor).transpose()?;
+    let after = params.after.as_deref().map(parse_cursor).transpose()?;
+
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM t_websocket_message");
+    push_message_filters(&mut builder, &config_id, &params);
+    if let Some((timestamp, id)) = &before {
+        builder.push(" AND (timestamp, id) < (").push_bind(*timestamp).push(", ").push_bind(id.clone()).push(")");
+    }
+    if let Some((timestamp, id)) = &after {
+        builder.push(" AND (timestamp, id) > (").push_bind(*timestamp).push(", ").push_bind(id.clone()).push(")");
+    }
+    builder.push(if ascending { " ORDER BY timestamp ASC, id ASC" } else { " ORDER BY timestamp DESC, id DESC" });
+    // 多取一条判断是否还有下一页
+    builder.push(" LIMIT ").push_bind(limit + 1);
+    if before.is_none() && after.is_none() {
+        builder.push(" OFFSET ").push_bind((page - 1) * limit);
+    }
+
+    let mut messages = match builder.build_query_as::<WebSocketMessage>().fetch_all(&state.pool).await {
+        Ok(messages) => messages,
+        Err(e) => {
+            tracing::error!("Failed to fetch websocket messages: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let next_cursor = if messages.len() as i64 > limit {
+        messages.truncate(limit as usize);
+        messages.last().map(message_cursor)
+    } else {
+        None
+    };
+
+    let total = if params.total {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM t_websocket_message");
+        push_message_filters(&mut builder, &config_id, &params);
+        match builder.build_query_scalar::<i64>().fetch_one(&state.pool).await {
+            Ok(total) => Some(total),
+            Err(e) => {
+                tracing::error!("Failed to count websocket messages: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    } else {
+        None
+    };
+
+    Ok(Json(ApiResponse::ok(MessagePage { messages, next_cursor, total })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub config_id: Option<String>,
+    pub direction: Option<String>, // "sent" or "received"
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub sort: Option<String>, // "relevance" (default) or "time"
+    #[serde(default)]
+    pub raw: bool, // Pass q to FTS5 unchanged (supports AND/OR/NEAR, prefix*)
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
+
+// 把用户输入转成 FTS5 查询：每个词作为短语匹配，避免 "-"、":" 等被当作语法
+fn fts_query(q: &str) -> String {
+    q.split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 全文搜索消息
+pub async fn search_messages(
+    Query(params): Query<SearchQuery>,
     State(state): State<AppState>,
-) -> Result<Json<ApiResponse<Vec<WebSocketMessage>>>, StatusCode> {
-    let page: i32 = params.get("page").and_then(|p| p.parse().ok()).unwrap_or(1).max(1);
-    let limit: i32 = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(50).min(100);
+) -> Result<Json<ApiResponse<Vec<MessageSearchHit>>>, StatusCode> {
+    let query = if params.raw { params.q.trim().to_string() } else { fts_query(&params.q) };
+    if query.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let order = match params.sort.as_deref() {
+        None | Some("relevance") => "rank, m.timestamp DESC",
+        Some("time") => "COALESCE(m.timestamp_us, m.timestamp * 1000000) DESC",
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
     let offset = (page - 1) * limit;
 
-    match sqlx::query_as::<_, WebSocketMessage>(
-        "SELECT * FROM t_websocket_message WHERE config_id = ? ORDER BY timestamp DESC LIMIT ? OFFSET ?"
-    )
-    .bind(&config_id)
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(&state.pool)
-    .await
+    let sql = format!(
+        r#"
+        SELECT m.*,
+               snippet(t_websocket_message_fts, -1, '<mark>', '</mark>', '...', 16) AS snippet,
+               bm25(t_websocket_message_fts) AS rank
+        FROM t_websocket_message_fts
+        JOIN t_websocket_message m ON m.rowid = t_websocket_message_fts.rowid
+        WHERE t_websocket_message_fts MATCH ?
+          AND (? IS NULL OR m.config_id = ?)
+          AND (? IS NULL OR m.message_type = ?)
+          AND (? IS NULL OR m.timestamp >= ?)
+          AND (? IS NULL OR m.timestamp <= ?)
+        ORDER BY {}
+        LIMIT ? OFFSET ?
+        "#,
+        order
+    );
+
+    match sqlx::query_as::<_, MessageSearchHit>(&sql)
+        .bind(&query)
+        .bind(&params.config_id)
+        .bind(&params.config_id)
+        .bind(&params.direction)
+        .bind(&params.direction)
+        .bind(params.from)
+        .bind(params.from)
+        .bind(params.to)
+        .bind(params.to)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&state.pool)
+        .await
     {
-        Ok(messages) => Ok(Json(ApiResponse::success(messages))),
+        Ok(hits) => Ok(Json(ApiResponse::ok(hits))),
+        // 原始查询语法错误
+        Err(sqlx::Error::Database(e)) if params.raw => {
+            tracing::warn!("Invalid search query {}: {}", query, e);
+            Err(StatusCode::BAD_REQUEST)
+        }
         Err(e) => {
-            tracing::error!("Failed to fetch websocket messages: {}", e);
+            tracing::error!("Failed to search websocket messages: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
@@ -1,23 +1,30 @@
+use std::convert::Infallible;
+
 use axum::{
     extract::{Path, State},
     http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
     response::Json,
 };
+use futures::stream::BoxStream;
+use futures::StreamExt;
 use sqlx::SqlitePool;
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
 use crate::app::AppState;
 use crate::models::{
     ApiResponse, WebSocketConfig, SendMessageRequest, SubscribeRequest,
-    WebSocketStatus, TestConnectionRequest, TestConnectionResponse, WebSocketMessage
+    WebSocketStatus, TestConnectionRequest, TestConnectionResponse, WebSocketMessage, SentMessage
 };
+use crate::service::outbox;
 use crate::service::websocket_manager::WEBSOCKET_MANAGER;
 
 // 发送WebSocket消息（功能一）
 pub async fn send_message(
     State(state): State<AppState>,
     Json(payload): Json<SendMessageRequest>,
-) -> Result<Json<ApiResponse<()>>, StatusCode> {
+) -> Result<Json<ApiResponse<SentMessage>>, StatusCode> {
     // 首先验证配置是否存在
     let config = match sqlx::query_as::<_, WebSocketConfig>(
         "SELECT * FROM t_websocket_config WHERE id = ? AND config_type = 'sender'"
@@ -34,6 +41,25 @@ pub async fn send_message(
         }
     };
 
+    // 启用发件箱时，未连接或仍有待发送消息的情况下先排队，连接建立后按顺序发送
+    let outbox = outbox::settings(&config).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring invalid outbox of {}: {}", config.id, e);
+        None
+    });
+    if let Some(settings) = &outbox {
+        let connected = WEBSOCKET_MANAGER
+            .get_connection_status(&payload.config_id)
+            .await
+            .is_some_and(|info| info.is_connected);
+        if !connected || outbox::has_pending(&state.pool, &payload.config_id).await? {
+            let message_id = outbox::enqueue(&state.pool, &payload, settings).await?;
+            if connected {
+                outbox::spawn_flush(state.pool.clone(), payload.config_id.clone());
+            }
+            return Ok(Json(queued(message_id)));
+        }
+    }
+
     // 检查连接状态，如果未连接则先建立连接
     if WEBSOCKET_MANAGER.get_connection_status(&payload.config_id).await.is_none() {
         if let Err(e) = WEBSOCKET_MANAGER.connect(config.clone()).await {
@@ -42,66 +68,144 @@ pub async fn send_message(
         }
     }
 
-    // 发送消息
-    match WEBSOCKET_MANAGER.send_message(payload.clone()).await {
-        Ok(_) => {
-            // 记录消息到数据库
-            let message_id = Uuid::new_v4().to_string();
-            let now = chrono::Utc::now().timestamp();
-            
-            if let Err(e) = sqlx::query(
-                r#"
-                INSERT INTO t_websocket_message 
-                (id, config_id, message_type, content, timestamp, status)
-                VALUES (?, ?, 'sent', ?, ?, 'success')
-                "#
-            )
-            .bind(&message_id)
-            .bind(&payload.config_id)
-            .bind(&payload.message)
-            .bind(now)
-            .execute(&state.pool)
-            .await
-            {
-                tracing::error!("Failed to save message to database: {}", e);
-            }
+    // 当前连接的会话ID，用于会话录制
+    let session_id = WEBSOCKET_MANAGER
+        .get_connection_status(&payload.config_id)
+        .await
+        .map(|info| info.session_id);
 
-            Ok(Json(ApiResponse::success(())))
-        }
+    // 先记录为 pending，帧写出后由发送任务更新为 success 或 failed
+    let message_id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now();
+    if let Err(e) = sqlx::query(
+        r#"
+        INSERT INTO t_websocket_message 
+        (id, config_id, message_type, content, timestamp, status, topic, event, session_id, timestamp_us)
+        VALUES (?, ?, 'sent', ?, ?, 'pending', ?, ?, ?, ?)
+        "#
+    )
+    .bind(&message_id)
+    .bind(&payload.config_id)
+    .bind(&payload.message)
+    .bind(now.timestamp())
+    .bind(&payload.topic)
+    .bind(&payload.event)
+    .bind(&session_id)
+    .bind(now.timestamp_micros())
+    .execute(&state.pool)
+    .await
+    {
+        tracing::error!("Failed to save message to database: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    // 发送消息
+    match WEBSOCKET_MANAGER.send_message(payload.clone(), Some(message_id.clone())).await {
+        Ok(_) => Ok(Json(ApiResponse::ok(SentMessage {
+            id: message_id,
+            status: "pending".to_string(),
+            error_message: None,
+        }))),
         Err(e) => {
             tracing::error!("Failed to send WebSocket message: {}", e);
-            
-            // 记录失败的消息
-            let message_id = Uuid::new_v4().to_string();
-            let now = chrono::Utc::now().timestamp();
-            
-            let _ = sqlx::query(
-                r#"
-                INSERT INTO t_websocket_message 
-                (id, config_id, message_type, content, timestamp, status, error_message)
-                VALUES (?, ?, 'sent', ?, ?, 'failed', ?)
-                "#
-            )
-            .bind(&message_id)
-            .bind(&payload.config_id)
-            .bind(&payload.message)
-            .bind(now)
-            .bind(e.to_string())
-            .execute(&state.pool)
-            .await;
+
+            // 发送前连接已断开，放回发件箱等待重连
+            if outbox.is_some() && WEBSOCKET_MANAGER.get_connection_status(&payload.config_id).await.is_none() {
+                outbox::release(&state.pool, &message_id).await;
+                return Ok(Json(queued(message_id)));
+            }
+
+            // 记录失败原因
+            let _ = sqlx::query("UPDATE t_websocket_message SET status = 'failed', error_message = ? WHERE id = ?")
+                .bind(e.to_string())
+                .bind(&message_id)
+                .execute(&state.pool)
+                .await;
 
             Err(StatusCode::SERVICE_UNAVAILABLE)
         }
     }
 }
 
+// 消息已进入发件箱
+fn queued(id: String) -> ApiResponse<SentMessage> {
+    ApiResponse {
+        success: true,
+        data: Some(SentMessage { id, status: "pending".to_string(), error_message: None }),
+        message: Some("queued".to_string()),
+    }
+}
+
+async fn fetch_sent_message(pool: &SqlitePool, id: &str) -> Result<SentMessage, StatusCode> {
+    match sqlx::query_as::<_, SentMessage>(
+        "SELECT id, status, error_message FROM t_websocket_message WHERE id = ? AND message_type = 'sent'"
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(message) => Ok(message),
+        Err(sqlx::Error::RowNotFound) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to fetch sent message: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// 查询已发送消息的状态
+pub async fn get_sent_message(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<SentMessage>>, StatusCode> {
+    fetch_sent_message(&state.pool, &id).await.map(|message| Json(ApiResponse::ok(message)))
+}
+
+// 以 SSE 推送已发送消息的状态，变为 success 或 failed 后关闭
+pub async fn stream_sent_message(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Sse<BoxStream<'static, Result<Event, Infallible>>>, StatusCode> {
+    let to_event = |message: &SentMessage| {
+        Ok(Event::default().event("status").data(serde_json::to_string(message).unwrap_or_default()))
+    };
+
+    // 先订阅再查询，避免漏掉查询之后的状态变化
+    let receiver = WEBSOCKET_MANAGER.subscribe_deliveries();
+    let current = fetch_sent_message(&state.pool, &id).await?;
+    if current.status != "pending" {
+        return Ok(Sse::new(futures::stream::once(futures::future::ready(to_event(&current))).boxed()));
+    }
+
+    let updates = futures::stream::unfold((receiver, false), move |(mut receiver, done)| {
+        let id = id.clone();
+        async move {
+            if done {
+                return None;
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(message) if message.id == id => {
+                        let finished = message.status != "pending";
+                        return Some((to_event(&message), (receiver, finished)));
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    let stream = futures::stream::once(futures::future::ready(to_event(&current))).chain(updates);
+    Ok(Sse::new(stream.boxed()).keep_alive(KeepAlive::default()))
+}
+
 // 订阅WebSocket数据（功能二）
 pub async fn subscribe_websocket(
     State(state): State<AppState>,
     Json(payload): Json<SubscribeRequest>,
 ) -> Result<Json<ApiResponse<()>>, StatusCode> {
     // 验证配置是否存在且类型为subscriber
-    let config = match sqlx::query_as::<_, WebSocketConfig>(
+    let mut config = match sqlx::query_as::<_, WebSocketConfig>(
         "SELECT * FROM t_websocket_config WHERE id = ? AND config_type = 'subscriber'"
     )
     .bind(&payload.config_id)
@@ -116,9 +220,28 @@ pub async fn subscribe_websocket(
         }
     };
 
-    // 检查是否已经连接
+    // 保存订阅过滤器（如MQTT主题），重连时自动重新订阅
+    if let Some(filters) = &payload.filters {
+        let filters = filters.to_string();
+        if let Err(e) = sqlx::query("UPDATE t_websocket_config SET filters = ? WHERE id = ?")
+            .bind(&filters)
+            .bind(&payload.config_id)
+            .execute(&state.pool)
+            .await
+        {
+            tracing::error!("Failed to save subscription filters: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        config.filters = Some(filters);
+    }
+
+    // 检查是否已经连接，已连接时在现有连接上追加订阅
     if let Some(status) = WEBSOCKET_MANAGER.get_connection_status(&payload.config_id).await {
         if status.is_connected {
+            if let Err(e) = WEBSOCKET_MANAGER.subscribe(payload).await {
+                tracing::error!("Failed to subscribe on existing connection: {}", e);
+                return Err(StatusCode::SERVICE_UNAVAILABLE);
+            }
             return Ok(Json(ApiResponse::success(())));
         }
     }
@@ -208,17 +331,24 @@ pub async fn start_connection(
     }
 
     // 启动连接
+    spawn_connect(state.pool.clone(), config);
+
+    Ok(Json(ApiResponse::success(())))
+}
+
+// 在后台建立连接并更新配置状态；连接结束（或建立失败）时任务完成
+pub fn spawn_connect(pool: SqlitePool, config: WebSocketConfig) -> tokio::task::JoinHandle<Result<(), String>> {
     let manager = WEBSOCKET_MANAGER.clone();
-    let config_clone = config.clone();
-    let pool = state.pool.clone();
-    
+    let config_id = config.id.clone();
+
     tokio::spawn(async move {
-        match manager.connect(config_clone).await {
+        match manager.connect(config).await {
             Ok(_) => {
                 let _ = sqlx::query("UPDATE t_websocket_config SET status = 'active' WHERE id = ?")
                     .bind(&config_id)
                     .execute(&pool)
                     .await;
+                Ok(())
             }
             Err(e) => {
                 tracing::error!("Failed to start WebSocket connection: {}", e);
@@ -226,11 +356,20 @@ pub async fn start_connection(
                     .bind(&config_id)
                     .execute(&pool)
                     .await;
+                Err(e.to_string())
             }
         }
-    });
+    })
+}
 
-    Ok(Json(ApiResponse::success(())))
+// 断开连接并把配置标记为未启动
+pub async fn stop_config(pool: &SqlitePool, config_id: &str) {
+    WEBSOCKET_MANAGER.disconnect(config_id).await;
+
+    let _ = sqlx::query("UPDATE t_websocket_config SET status = 'inactive' WHERE id = ?")
+        .bind(config_id)
+        .execute(pool)
+        .await;
 }
 
 // 停止WebSocket连接
@@ -252,14 +391,8 @@ pub async fn stop_connection(
         }
     }
 
-    // 停止连接
-    WEBSOCKET_MANAGER.disconnect(&config_id).await;
-
-    // 更新配置状态
-    let _ = sqlx::query("UPDATE t_websocket_config SET status = 'inactive' WHERE id = ?")
-        .bind(&config_id)
-        .execute(&state.pool)
-        .await;
+    // 停止连接并更新配置状态
+    stop_config(&state.pool, &config_id).await;
 
     Ok(Json(ApiResponse::success(())))
 }
@@ -310,6 +443,8 @@ pub async fn get_all_connection_status() -> Result<Json<ApiResponse<Vec<WebSocke
             message_count: info.message_count,
             error_count: info.error_count,
             last_error: info.last_error,
+            schema_violations: info.schema_violations,
+            sequence_gaps: info.sequence_gaps,
         })
         .collect();
 
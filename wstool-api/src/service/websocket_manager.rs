@@ -1,31 +1,102 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{broadcast, Mutex, RwLock};
 use tokio_tungstenite::{connect_async, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
 use tokio_tungstenite::tungstenite::protocol::Message;
 use futures::{SinkExt, StreamExt};
 use serde_json::Value;
+use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use crate::models::{WebSocketConfig, WebSocketMessage, SendMessageRequest, SubscribeRequest};
+use crate::models::{WebSocketConfig, WebSocketMessage, SendMessageRequest, SubscribeRequest, SentMessage};
+use crate::service::protocol::{self, Inbound, ProtocolAdapter};
+use crate::service::{alert, bridge, candle, orderbook, outbox, state_store, webhook};
+use crate::service::candle::Aggregator;
+use crate::service::state_store::StateStore;
+use crate::service::orderbook::OrderBookFeed;
+use crate::service::schema_validation::SchemaValidator;
+use crate::service::sequence::{self, Anomaly, Resync, SequenceTracker};
+use crate::service::transform::Pipeline;
 
 pub type WebSocketConnection = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
 
+type SharedAdapter = Arc<Mutex<Box<dyn ProtocolAdapter>>>;
+
+// 发送状态广播的缓冲大小
+const DELIVERY_CHANNEL_CAPACITY: usize = 1024;
+
+// 发送队列中的一帧；message_id 只挂在一条消息的最后一帧上，写出后更新该消息记录的状态
+struct Outbound {
+    frame: Message,
+    message_id: Option<String>,
+}
+
+impl From<Message> for Outbound {
+    fn from(frame: Message) -> Self {
+        Self { frame, message_id: None }
+    }
+}
+
+// 待保存的一条接收消息
+struct ReceivedRow<'a> {
+    message: &'a Inbound,
+    raw_content: Option<&'a str>,
+    status: &'static str,
+    error_message: Option<String>,
+    valid: Option<bool>,
+    validation_error: Option<String>,
+}
+
+fn received_message(config_id: &str, session_id: &str, row: ReceivedRow<'_>) -> WebSocketMessage {
+    let now = chrono::Utc::now();
+    WebSocketMessage {
+        id: Uuid::new_v4().to_string(),
+        config_id: config_id.to_string(),
+        message_type: "received".to_string(),
+        content: row.message.content.clone(),
+        timestamp: now.timestamp(),
+        status: row.status.to_string(),
+        error_message: row.error_message,
+        topic: row.message.topic.clone(),
+        event: row.message.event.clone(),
+        session_id: Some(session_id.to_string()),
+        timestamp_us: Some(now.timestamp_micros()),
+        valid: row.valid,
+        validation_error: row.validation_error,
+        raw_content: row.raw_content.map(str::to_string),
+        qos: None,
+        retain: None,
+    }
+}
+
 #[derive(Clone)]
 pub struct ConnectionInfo {
     pub config: WebSocketConfig,
+    pub session_id: String,
     pub is_connected: bool,
     pub connection_time: Option<i64>,
     pub last_message_time: Option<i64>,
     pub message_count: i64,
     pub error_count: i64,
     pub last_error: Option<String>,
+    pub schema_violations: i64,
+    pub sequence_gaps: i64,
+    // 已交给协议适配器排队、尚未写出的发送消息ID
+    pub deferred: VecDeque<String>,
 }
 
 #[derive(Clone)]
 pub struct WebSocketManager {
     connections: Arc<RwLock<HashMap<String, Arc<Mutex<ConnectionInfo>>>>>,
-    message_handlers: Arc<RwLock<HashMap<String, tokio::sync::mpsc::UnboundedSender<Message>>>>,
+    message_handlers: Arc<RwLock<HashMap<String, tokio::sync::mpsc::UnboundedSender<Outbound>>>>,
+    adapters: Arc<RwLock<HashMap<String, SharedAdapter>>>,
+    // 正在重新连接的配置
+    reconnecting: Arc<RwLock<HashSet<String>>>,
+    pool: Arc<OnceLock<SqlitePool>>,
+    deliveries: broadcast::Sender<SentMessage>,
 }
 
 impl WebSocketManager {
@@ -33,29 +104,67 @@ impl WebSocketManager {
         Self {
             connections: Arc::new(RwLock::new(HashMap::new())),
             message_handlers: Arc::new(RwLock::new(HashMap::new())),
+            adapters: Arc::new(RwLock::new(HashMap::new())),
+            reconnecting: Arc::new(RwLock::new(HashSet::new())),
+            pool: Arc::new(OnceLock::new()),
+            deliveries: broadcast::channel(DELIVERY_CHANNEL_CAPACITY).0,
         }
     }
 
+    // 绑定数据库连接池（用于保存收到的消息）
+    pub fn set_pool(&self, pool: SqlitePool) {
+        let _ = self.pool.set(pool);
+    }
+
     // 建立WebSocket连接
     pub async fn connect(&self, config: WebSocketConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let config_id = config.id.clone();
-        
-        // 解析URL和headers
-        let url = &config.ws_url;
-        let mut request = url.parse::<http::Uri>()?;
-        
-        // 建立连接
-        let (ws_stream, _) = connect_async(url).await?;
-        
+
+        // 构建转换管道、编译消息校验 schema 并创建序号检查器
+        let pipeline = Pipeline::from_config(&config)?;
+        let validator = SchemaValidator::from_config(&config)?;
+        let mut sequence = SequenceTracker::from_config(&config)?;
+        let mut orderbooks = OrderBookFeed::from_config(&config)?;
+        let candles = Aggregator::from_config(&config)?;
+        let latest = StateStore::from_config(&config)?;
+        // 重新订阅时使用已保存的订阅
+        let filters = match config.filters.as_deref() {
+            Some(raw) if !raw.trim().is_empty() => Some(serde_json::from_str::<Value>(raw)?),
+            _ => None,
+        };
+
+        // 建立连接并完成协议握手
+        let OpenedConnection { stream: ws_stream, adapter, opening } = open_connection(&config).await?;
+        let heartbeat_interval = adapter.heartbeat_interval();
+        let timer_interval = adapter.timer_interval();
+        let adapter: SharedAdapter = Arc::new(Mutex::new(adapter));
+
+        if let Some(feed) = &orderbooks {
+            feed.reset().await;
+        }
+        let candles = match candles {
+            Some(aggregator) => Some(candle::register(aggregator).await),
+            None => None,
+        };
+        let latest = match (latest, self.pool.get()) {
+            (Some(store), Some(pool)) => Some(state_store::register(store, pool).await),
+            _ => None,
+        };
+
         // 创建连接信息
+        let session_id = Uuid::new_v4().to_string();
         let connection_info = Arc::new(Mutex::new(ConnectionInfo {
             config: config.clone(),
+            session_id: session_id.clone(),
             is_connected: true,
             connection_time: Some(chrono::Utc::now().timestamp()),
             last_message_time: None,
             message_count: 0,
             error_count: 0,
             last_error: None,
+            schema_violations: 0,
+            sequence_gaps: 0,
+            deferred: VecDeque::new(),
         }));
 
         // 存储连接信息
@@ -63,12 +172,27 @@ impl WebSocketManager {
             let mut connections = self.connections.write().await;
             connections.insert(config_id.clone(), connection_info.clone());
         }
+        self.reconnecting.write().await.remove(&config_id);
+        {
+            let mut adapters = self.adapters.write().await;
+            adapters.insert(config_id.clone(), adapter.clone());
+        }
 
         // 创建消息通道
-        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Message>();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Outbound>();
+        for message in opening {
+            tx.send(message.into())?;
+        }
         {
             let mut handlers = self.message_handlers.write().await;
-            handlers.insert(config_id.clone(), tx);
+            handlers.insert(config_id.clone(), tx.clone());
+        }
+
+        // 连接建立后补发发件箱中的消息
+        if config.outbox.is_some() {
+            if let Some(pool) = self.pool.get() {
+                outbox::spawn_flush(pool.clone(), config_id.clone());
+            }
         }
 
         // 分离读写流
@@ -77,39 +201,178 @@ impl WebSocketManager {
         let manager = self.clone();
         let config_id_clone = config_id.clone();
         let connection_info_clone = connection_info.clone();
+        let deferred_info = connection_info.clone();
 
-        // 启动消息发送任务
+        // 启动消息发送任务：帧写出并 flush 后才把对应消息标记为 success
+        let sender_manager = self.clone();
+        let requeue = config.outbox.is_some();
         let send_task = tokio::spawn(async move {
-            while let Some(message) = rx.recv().await {
-                if let Err(e) = ws_sender.send(message).await {
+            while let Some(outbound) = rx.recv().await {
+                if let Err(e) = ws_sender.send(outbound.frame).await {
                     tracing::error!("Failed to send WebSocket message: {}", e);
-                    let mut info = connection_info_clone.lock().await;
-                    info.error_count += 1;
-                    info.last_error = Some(e.to_string());
+                    {
+                        let mut info = connection_info_clone.lock().await;
+                        info.error_count += 1;
+                        info.last_error = Some(e.to_string());
+                    }
+                    // 本帧和队列中剩余的消息都没有写出
+                    let error = e.to_string();
+                    sender_manager.finish_delivery(outbound.message_id, Some(&error), requeue).await;
+                    rx.close();
+                    while let Ok(rest) = rx.try_recv() {
+                        sender_manager.finish_delivery(rest.message_id, Some(&error), requeue).await;
+                    }
                     break;
                 }
+                sender_manager.finish_delivery(outbound.message_id, None, requeue).await;
             }
         });
 
-        // 启动消息接收任务
+        // 启动协议心跳任务
+        let heartbeat_task = heartbeat_interval.map(|interval| {
+            let adapter = adapter.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    let frames = adapter.lock().await.heartbeat();
+                    for frame in frames {
+                        if tx.send(frame.into()).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+        });
+
+        // 启动协议定时任务
+        let timer_task = timer_interval.map(|interval| {
+            let adapter = adapter.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    let frames = adapter.lock().await.on_timer();
+                    for frame in frames {
+                        if tx.send(frame.into()).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+        });
+
+        // 启动消息接收任务，返回是否需要重连以重新同步序号
         let receive_task = tokio::spawn(async move {
+            let mut resync_reconnect = false;
             while let Some(message) = ws_receiver.next().await {
                 match message {
                     Ok(msg) => {
-                        let mut info = connection_info.lock().await;
-                        info.message_count += 1;
-                        info.last_message_time = Some(chrono::Utc::now().timestamp());
-                        
-                        // 处理接收到的消息
-                        if let Message::Text(text) = msg {
-                            tracing::info!("Received message from {}: {}", config_id, text);
-                            
-                            // 这里可以添加消息过滤和处理逻辑
-                            manager.handle_received_message(&config_id, &text).await;
+                        {
+                            let mut info = connection_info.lock().await;
+                            info.message_count += 1;
+                            info.last_message_time = Some(chrono::Utc::now().timestamp());
+                        }
+
+                        // 先解压二进制帧，再交给协议适配器解码，回写协议应答，最后处理业务消息
+                        let msg = match &pipeline {
+                            Some(pipeline) => match pipeline.prepare_frame(msg) {
+                                Ok(prepared) => prepared,
+                                Err(e) => {
+                                    tracing::warn!("Failed to decompress frame for {}: {}", config_id_clone, e);
+                                    let mut info = connection_info.lock().await;
+                                    info.error_count += 1;
+                                    info.last_error = Some(e);
+                                    continue;
+                                }
+                            },
+                            None => msg,
+                        };
+                        let decoded = adapter.lock().await.decode_incoming(msg);
+                        match decoded {
+                            Ok(decoded) => {
+                                let mut replies: Vec<Outbound> = decoded.replies.into_iter().map(Outbound::from).collect();
+                                // 适配器排队的发送请求随应答发出，写出后更新对应消息的状态
+                                if !decoded.flushed.is_empty() {
+                                    let mut info = connection_info.lock().await;
+                                    for index in decoded.flushed {
+                                        if let Some(reply) = replies.get_mut(index) {
+                                            reply.message_id = info.deferred.pop_front();
+                                        }
+                                    }
+                                }
+                                for reply in replies {
+                                    let _ = tx.send(reply);
+                                }
+                                for inbound in decoded.messages {
+                                    tracing::info!("Received message from {}: {}", config_id_clone, inbound.content);
+                                    // 按序号规则检查缺口、重复和乱序
+                                    if let Some(tracker) = sequence.as_mut() {
+                                        if let Some(anomaly) = tracker.check(&inbound.content) {
+                                            let resync = tracker.resync(&anomaly);
+                                            let dropped = anomaly.is_stale() && tracker.drop_stale();
+                                            manager
+                                                .record_sequence_anomaly(&config_id_clone, &session_id, &anomaly, resync, dropped)
+                                                .await;
+                                            match resync {
+                                                Some(Resync::Resubscribe) => {
+                                                    let mut frames = match &filters {
+                                                        Some(filters) => adapter.lock().await.resubscribe(filters).unwrap_or_else(|e| {
+                                                            tracing::warn!("Failed to resubscribe {}: {}", config_id_clone, e);
+                                                            Vec::new()
+                                                        }),
+                                                        None => Vec::new(),
+                                                    };
+                                                    frames.extend(tracker.resync_message().map(|text| Message::Text(text.to_string())));
+                                                    for frame in frames {
+                                                        let _ = tx.send(frame.into());
+                                                    }
+                                                }
+                                                Some(Resync::Reconnect) => resync_reconnect = true,
+                                                None => {}
+                                            }
+                                            if dropped {
+                                                continue;
+                                            }
+                                        }
+                                    }
+                                    // 更新订单簿，失效重建时回写重新订阅的帧
+                                    if let Some(feed) = orderbooks.as_mut() {
+                                        for frame in feed.handle(&inbound.content).await {
+                                            let _ = tx.send(frame.into());
+                                        }
+                                    }
+                                    // 聚合成交为 K 线
+                                    if let (Some(aggregator), Some(pool)) = (&candles, manager.pool.get()) {
+                                        candle::handle(aggregator, pool, &inbound.content).await;
+                                    }
+                                    // 更新各键的最新值
+                                    if let (Some(store), Some(pool)) = (&latest, manager.pool.get()) {
+                                        state_store::handle(store, pool, &inbound.content).await;
+                                    }
+                                    manager
+                                        .handle_received_message(&config_id_clone, &session_id, &inbound, pipeline.as_ref(), validator.as_ref())
+                                        .await;
+                                }
+                                if resync_reconnect {
+                                    break;
+                                }
+                            }
+                            Err(e) => {
+                                tracing::error!("Protocol error for {}: {}", config_id_clone, e);
+                                let mut info = connection_info.lock().await;
+                                info.error_count += 1;
+                                info.last_error = Some(e.to_string());
+                                info.is_connected = false;
+                                break;
+                            }
                         }
                     }
                     Err(e) => {
-                        tracing::error!("WebSocket receive error for {}: {}", config_id, e);
+                        tracing::error!("WebSocket receive error for {}: {}", config_id_clone, e);
                         let mut info = connection_info.lock().await;
                         info.error_count += 1;
                         info.last_error = Some(e.to_string());
@@ -118,17 +381,34 @@ impl WebSocketManager {
                     }
                 }
             }
+            resync_reconnect
         });
 
         // 等待任务完成或连接断开
+        let mut resync_reconnect = false;
         tokio::select! {
             _ = send_task => {},
-            _ = receive_task => {},
+            result = receive_task => resync_reconnect = result.unwrap_or(false),
+        }
+        for task in [heartbeat_task, timer_task].into_iter().flatten() {
+            task.abort();
+        }
+
+        // 适配器中尚未发出的消息随连接关闭而失败
+        let deferred = std::mem::take(&mut deferred_info.lock().await.deferred);
+        for message_id in deferred {
+            self.finish_delivery(Some(message_id), Some("connection closed before the message was sent"), requeue)
+                .await;
         }
 
         // 清理连接
         self.disconnect(&config_id).await;
-        
+
+        // 序号出现缺口时重新建立连接
+        if resync_reconnect {
+            spawn_reconnect(self.clone(), config);
+        }
+
         Ok(())
     }
 
@@ -147,23 +427,88 @@ impl WebSocketManager {
             let mut handlers = self.message_handlers.write().await;
             handlers.remove(config_id);
         }
+
+        {
+            let mut adapters = self.adapters.write().await;
+            adapters.remove(config_id);
+        }
+
+        orderbook::close(config_id).await;
+        if let Some(pool) = self.pool.get() {
+            candle::close(pool, config_id).await;
+            state_store::close(pool, config_id).await;
+        }
+    }
+
+    // 更新已出队消息的发送状态并广播；写出失败时启用发件箱的配置放回发件箱（清空会话ID）
+    async fn finish_delivery(&self, message_id: Option<String>, error: Option<&str>, requeue: bool) {
+        let Some(id) = message_id else {
+            return;
+        };
+        let status = match error {
+            None => "success",
+            Some(_) if requeue => "pending",
+            Some(_) => "failed",
+        };
+        if let Some(pool) = self.pool.get() {
+            if let Err(e) = sqlx::query(
+                r#"
+                UPDATE t_websocket_message
+                SET status = ?, error_message = ?, session_id = CASE WHEN ? = 'pending' THEN NULL ELSE session_id END
+                WHERE id = ?
+                "#
+            )
+            .bind(status)
+            .bind(error)
+            .bind(status)
+            .bind(&id)
+            .execute(pool)
+            .await
+            {
+                tracing::error!("Failed to update sent message status: {}", e);
+            }
+        }
+        let _ = self.deliveries.send(SentMessage {
+            id,
+            status: status.to_string(),
+            error_message: error.map(str::to_string),
+        });
+    }
+
+    // 订阅发送状态变化
+    pub fn subscribe_deliveries(&self) -> broadcast::Receiver<SentMessage> {
+        self.deliveries.subscribe()
     }
 
-    // 发送消息（功能一）
+    // 发送消息（功能一）；message_id 为已保存的消息记录，写出后更新其状态
     pub async fn send_message(
         &self, 
-        request: SendMessageRequest
+        request: SendMessageRequest,
+        message_id: Option<String>,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let handlers = self.message_handlers.read().await;
         
         if let Some(sender) = handlers.get(&request.config_id) {
-            let message = Message::Text(request.message);
-            sender.send(message)?;
+            // 按连接协议编码（MQTT PUBLISH 等）
+            let frames = match self.adapters.read().await.get(&request.config_id) {
+                Some(adapter) => adapter.lock().await.encode_outgoing(&request)?,
+                None => vec![Message::Text(request.message.clone())],
+            };
+            let deferred = frames.is_empty();
+            let last = frames.len().saturating_sub(1);
+            for (index, frame) in frames.into_iter().enumerate() {
+                let message_id = if index == last { message_id.clone() } else { None };
+                sender.send(Outbound { frame, message_id })?;
+            }
             
             // 更新连接信息
             let connections = self.connections.read().await;
             if let Some(connection_info) = connections.get(&request.config_id) {
                 let mut info = connection_info.lock().await;
+                // 适配器暂存的消息（如 GraphQL 等待 connection_ack）在发出时再更新状态
+                if deferred {
+                    info.deferred.extend(message_id);
+                }
                 info.message_count += 1;
                 info.last_message_time = Some(chrono::Utc::now().timestamp());
             }
@@ -180,14 +525,23 @@ impl WebSocketManager {
         request: SubscribeRequest
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         // 检查连接是否存在
-        let connections = self.connections.read().await;
-        if connections.contains_key(&request.config_id) {
-            // 订阅逻辑已经在connect方法中的接收任务中处理
-            tracing::info!("Subscribed to WebSocket config: {}", request.config_id);
-            Ok(())
-        } else {
-            Err("WebSocket connection not found".into())
+        let handlers = self.message_handlers.read().await;
+        let Some(sender) = handlers.get(&request.config_id) else {
+            return Err("WebSocket connection not found".into());
+        };
+
+        // 在已有连接上追加订阅（如MQTT主题）
+        if let Some(filters) = &request.filters {
+            if let Some(adapter) = self.adapters.read().await.get(&request.config_id) {
+                let frames = adapter.lock().await.subscribe(filters)?;
+                for frame in frames {
+                    sender.send(frame.into())?;
+                }
+            }
         }
+
+        tracing::info!("Subscribed to WebSocket config: {}", request.config_id);
+        Ok(())
     }
 
     // 获取连接状态
@@ -214,19 +568,151 @@ impl WebSocketManager {
         result
     }
 
-    // 处理接收到的消息
-    async fn handle_received_message(&self, config_id: &str, message: &str) {
-        // 这里可以添加消息处理逻辑，比如：
-        // 1. 消息过滤
-        // 2. 消息转发
-        // 3. 消息存储到数据库
-        // 4. 触发回调函数
-        
-        tracing::debug!("Processing received message from {}: {}", config_id, message);
-        
-        // 示例：简单的消息日志记录
-        if let Ok(json_value) = serde_json::from_str::<Value>(message) {
-            tracing::info!("Parsed JSON message: {:?}", json_value);
+    // 记录序号异常：缺口计入连接统计，事件保存到数据库
+    async fn record_sequence_anomaly(
+        &self,
+        config_id: &str,
+        session_id: &str,
+        anomaly: &Anomaly,
+        resync: Option<Resync>,
+        dropped: bool,
+    ) {
+        tracing::warn!(
+            "Sequence {} on {} [{}]: expected {}, got {}",
+            anomaly.kind, config_id, anomaly.partition_key, anomaly.expected, anomaly.actual
+        );
+        if !anomaly.is_stale() {
+            if let Some(connection_info) = self.connections.read().await.get(config_id) {
+                connection_info.lock().await.sequence_gaps += 1;
+            }
+        }
+        let action = match resync {
+            Some(resync) => resync.as_str(),
+            None if dropped => "dropped",
+            None => "none",
+        };
+        if let Some(pool) = self.pool.get() {
+            sequence::record(pool, config_id, session_id, anomaly, action).await;
+        }
+    }
+
+    // 处理接收到的消息：执行转换管道，逐条校验并保存
+    async fn handle_received_message(
+        &self,
+        config_id: &str,
+        session_id: &str,
+        message: &Inbound,
+        pipeline: Option<&Pipeline>,
+        validator: Option<&SchemaValidator>,
+    ) {
+        tracing::debug!("Processing received message from {}: {}", config_id, message.content);
+
+        let Some(pipeline) = pipeline else {
+            self.process_message(config_id, session_id, message, None, validator).await;
+            return;
+        };
+        match pipeline.apply(message) {
+            Ok(transformed) => {
+                for output in &transformed {
+                    // 转换改变了内容时同时保留原始消息
+                    let raw = (output.content != message.content).then_some(message.content.as_str());
+                    self.process_message(config_id, session_id, output, raw, validator).await;
+                }
+            }
+            Err(error) => {
+                // 转换失败时保存原始消息并标记为失败
+                tracing::warn!("Transform failed for {}: {}", config_id, error);
+                let record = received_message(config_id, session_id, ReceivedRow {
+                    message,
+                    raw_content: None,
+                    status: "failed",
+                    error_message: Some(error),
+                    valid: None,
+                    validation_error: None,
+                });
+                self.save_received_message(&record).await;
+            }
+        }
+    }
+
+    // 校验、转发、推送并保存单条（转换后的）消息
+    async fn process_message(
+        &self,
+        config_id: &str,
+        session_id: &str,
+        message: &Inbound,
+        raw_content: Option<&str>,
+        validator: Option<&SchemaValidator>,
+    ) {
+        // 按 schema 校验，记录违规次数
+        let verdict = validator.and_then(|validator| validator.validate(&message.content));
+        if let Some(Err(error)) = &verdict {
+            tracing::warn!("Schema violation from {}: {}", config_id, error);
+            if let Some(connection_info) = self.connections.read().await.get(config_id) {
+                connection_info.lock().await.schema_violations += 1;
+            }
+        }
+        // 按路由转发到其他配置
+        if let Some(pool) = self.pool.get() {
+            bridge::forward(pool, config_id, message).await;
+        }
+
+        let violations_only = validator.is_some_and(SchemaValidator::violations_only);
+        let (valid, validation_error) = match verdict {
+            Some(Ok(())) => (Some(true), None),
+            Some(Err(error)) => (Some(false), Some(error)),
+            None => (None, None),
+        };
+        let record = received_message(config_id, session_id, ReceivedRow {
+            message,
+            raw_content,
+            status: "success",
+            error_message: None,
+            valid,
+            validation_error,
+        });
+
+        // 推送给该配置的 webhook，并执行告警规则（与是否保存、保存是否成功无关）
+        webhook::dispatch(&record).await;
+        alert::evaluate(&record).await;
+
+        // 仅保存违规消息时丢弃通过校验的消息
+        if violations_only && valid != Some(false) {
+            return;
+        }
+        self.save_received_message(&record).await;
+    }
+
+    // 消息存储到数据库
+    async fn save_received_message(&self, message: &WebSocketMessage) {
+        let Some(pool) = self.pool.get() else {
+            return;
+        };
+        if let Err(e) = sqlx::query(
+            r#"
+            INSERT INTO t_websocket_message
+            (id, config_id, message_type, content, timestamp, status, error_message, topic, event, session_id, timestamp_us, valid, validation_error, raw_content)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&message.id)
+        .bind(&message.config_id)
+        .bind(&message.message_type)
+        .bind(&message.content)
+        .bind(message.timestamp)
+        .bind(&message.status)
+        .bind(&message.error_message)
+        .bind(&message.topic)
+        .bind(&message.event)
+        .bind(&message.session_id)
+        .bind(message.timestamp_us)
+        .bind(message.valid)
+        .bind(&message.validation_error)
+        .bind(&message.raw_content)
+        .execute(pool)
+        .await
+        {
+            tracing::error!("Failed to save received message: {}", e);
         }
     }
 
@@ -235,13 +721,23 @@ impl WebSocketManager {
         let config_id = config.id.clone();
         
         // 先断开现有连接
+        self.reconnecting.write().await.insert(config_id.clone());
         self.disconnect(&config_id).await;
         
         // 等待一段时间后重新连接
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         
         // 重新建立连接
-        self.connect(config).await
+        let result = self.connect(config).await;
+        if result.is_err() {
+            self.reconnecting.write().await.remove(&config_id);
+        }
+        result
+    }
+
+    // 是否正在重新连接
+    pub async fn is_reconnecting(&self, config_id: &str) -> bool {
+        self.reconnecting.read().await.contains(config_id)
     }
 
     // 测试连接
@@ -299,6 +795,100 @@ impl WebSocketManager {
     }
 }
 
+// 在后台重新连接（序号缺口重新同步）
+fn spawn_reconnect(manager: WebSocketManager, config: WebSocketConfig) {
+    tokio::spawn(async move {
+        let config_id = config.id.clone();
+        if let Err(e) = manager.reconnect(config).await {
+            tracing::error!("Failed to reconnect {} after sequence gap: {}", config_id, e);
+        }
+    });
+}
+
+// 已建立的连接：协议适配器及需要首先发送的握手/订阅帧
+pub struct OpenedConnection {
+    pub stream: WebSocketConnection,
+    pub adapter: Box<dyn ProtocolAdapter>,
+    pub opening: Vec<Message>,
+}
+
+// 按配置建立连接（不注册到管理器，压测等临时连接也使用）
+pub async fn open_connection(
+    config: &WebSocketConfig,
+) -> Result<OpenedConnection, Box<dyn std::error::Error + Send + Sync>> {
+    // 根据协议创建适配器，并用配置中的headers构造握手请求
+    let mut adapter = protocol::build_adapter(config)?;
+    let request = build_request(config, adapter.subprotocol())?;
+
+    let (stream, _) = connect_async(request).await?;
+
+    // 协议握手及已保存的订阅
+    let filters = match config.filters.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => Some(serde_json::from_str::<Value>(raw)?),
+        _ => None,
+    };
+    let opening = adapter.on_open(filters.as_ref())?;
+    Ok(OpenedConnection { stream, adapter, opening })
+}
+
+// 临时连接使用的原始协议配置（不保存到数据库）
+pub fn transient_config(prefix: &str, ws_url: &str) -> WebSocketConfig {
+    let now = chrono::Utc::now().timestamp();
+    WebSocketConfig {
+        id: format!("{}-{}", prefix, Uuid::new_v4()),
+        name: prefix.to_string(),
+        description: None,
+        ws_url: ws_url.to_string(),
+        config_type: "sender".to_string(),
+        headers: None,
+        auth_token: None,
+        message_template: None,
+        auto_reconnect: false,
+        status: "active".to_string(),
+        protocol: "raw".to_string(),
+        protocol_options: None,
+        filters: None,
+        message_schema: None,
+        transforms: None,
+        outbox: None,
+        sequence: None,
+        orderbook: None,
+        candles: None,
+        state_store: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+// 构造握手请求：应用配置中的headers，并声明协议所需的子协议
+pub fn build_request(
+    config: &WebSocketConfig,
+    subprotocol: Option<&str>,
+) -> Result<Request, Box<dyn std::error::Error + Send + Sync>> {
+    build_url_request(&config.ws_url, config.headers.as_deref(), subprotocol)
+}
+
+// 按URL和headers（JSON字符串）构造握手请求
+pub fn build_url_request(
+    ws_url: &str,
+    headers: Option<&str>,
+    subprotocol: Option<&str>,
+) -> Result<Request, Box<dyn std::error::Error + Send + Sync>> {
+    let mut request = ws_url.into_client_request()?;
+    for (name, value) in protocol::parse_headers(headers)? {
+        request.headers_mut().insert(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(&value)?,
+        );
+    }
+    if let Some(subprotocol) = subprotocol {
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", HeaderValue::from_str(subprotocol)?);
+    }
+    Ok(request)
+}
+
 // 全局WebSocket管理器实例
 lazy_static::lazy_static! {
     pub static ref WEBSOCKET_MANAGER: WebSocketManager = WebSocketManager::new();
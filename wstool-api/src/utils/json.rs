@@ -0,0 +1,141 @@
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+// 字段断言，op 见 compare
+#[derive(Debug, Clone, Deserialize)]
+pub struct Assertion {
+    pub path: String,
+    #[serde(default = "default_op")]
+    pub op: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+fn default_op() -> String {
+    "eq".to_string()
+}
+
+// 把路径拆成字段名：支持 `a.b.c`、数组下标 `data.0.p` / `data[0].p` 以及 `$.`、jq 风格的 `.` 前缀
+pub fn path_keys(path: &str) -> Vec<String> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    path.replace('[', ".")
+        .replace(']', "")
+        .split('.')
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// 转成 SQLite json_extract 的路径，如 `data.0.p` -> `$."data"[0]."p"`
+pub fn sqlite_path(path: &str) -> String {
+    let mut sqlite = String::from("$");
+    for key in path_keys(path) {
+        if key.parse::<usize>().is_ok() {
+            sqlite.push_str(&format!("[{}]", key));
+        } else {
+            sqlite.push_str(&format!(".\"{}\"", key.replace('"', "\\\"")));
+        }
+    }
+    sqlite
+}
+
+// 按路径取 JSON 字段
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path_keys(path).iter().try_fold(value, |current, key| match current {
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => current.get(key),
+    })
+}
+
+// 把字段值转为字符串（字符串不带引号）
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// 字段断言：eq、ne、gt、gte、lt、lte、exists、contains；数值比较时字符串会先尝试转为数字
+pub fn compare(actual: Option<&Value>, op: &str, expected: &Value) -> bool {
+    let as_number = |v: &Value| match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse::<f64>().ok(),
+        _ => None,
+    };
+    let Some(actual) = actual else {
+        return op == "exists" && expected == &Value::Bool(false);
+    };
+    match op {
+        "exists" => expected != &Value::Bool(false),
+        "eq" => actual == expected || matches!((as_number(actual), as_number(expected)), (Some(a), Some(b)) if a == b),
+        "ne" => !compare(Some(actual), "eq", expected),
+        "gt" | "gte" | "lt" | "lte" => match (as_number(actual), as_number(expected)) {
+            (Some(a), Some(b)) => match op {
+                "gt" => a > b,
+                "gte" => a >= b,
+                "lt" => a < b,
+                _ => a <= b,
+            },
+            _ => false,
+        },
+        "contains" => match (actual, expected) {
+            (Value::String(a), Value::String(b)) => a.contains(b.as_str()),
+            (Value::Array(items), _) => items.contains(expected),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// 文本消息是否匹配正则且满足全部字段断言（有断言时消息必须是 JSON）
+pub fn message_matches(text: &str, assertions: &[Assertion], regex: Option<&Regex>) -> bool {
+    if regex.is_some_and(|r| !r.is_match(text)) {
+        return false;
+    }
+    if assertions.is_empty() {
+        return true;
+    }
+    let Ok(value) = serde_json::from_str::<Value>(text) else {
+        return false;
+    };
+    assertions
+        .iter()
+        .all(|a| compare(lookup(&value, &a.path), &a.op, &a.value))
+}
+
+// 消息过滤条件：{"regex": "...", "assertions": [...]}，两者都满足才匹配
+#[derive(Debug, Default)]
+pub struct MessageFilter {
+    regex: Option<Regex>,
+    assertions: Vec<Assertion>,
+}
+
+impl MessageFilter {
+    // 解析 JSON 字符串形式的过滤条件，为空时匹配所有消息
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Raw {
+            regex: Option<String>,
+            #[serde(default)]
+            assertions: Vec<Assertion>,
+        }
+
+        let Some(raw) = raw.filter(|raw| !raw.trim().is_empty()) else {
+            return Ok(Self::default());
+        };
+        let filter: Raw = serde_json::from_str(raw).map_err(|e| format!("invalid filter: {}", e))?;
+        let regex = filter
+            .regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("invalid filter regex: {}", e))?;
+        Ok(Self { regex, assertions: filter.assertions })
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        message_matches(text, &self.assertions, self.regex.as_ref())
+    }
+}